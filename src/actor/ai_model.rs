@@ -4,35 +4,234 @@ use libc::printf;
 use steady_state::*;
 use crate::llm_engine::LlmEngine;
 use crate::actor::crawler::FileMeta;
+use crate::file_index::{self, FileIndex, IndexEntry};
+use crate::config::{Engine, LlmConfig};
+use crate::verdict_engine::{MockEngine, RecordingEngine, ReplayEngine, VerdictEngine, MOCK_ENGINE, REPLAY_ENGINE};
+use crate::duplicates::DuplicateGroup;
+use crate::quarantine::unix_now;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+/// Version of the prompt `build_prompt` produces. Bump it whenever the prompt
+/// changes so verdicts stored under the old one are inferred again.
+pub(crate) const PROMPT_VERSION: u32 = 2;
+
+/// Most files asked about in one prompt; `LLM.batch-size` can't go above it.
+/// Beyond that the numbered answers crowd the context window.
+pub(crate) const MAX_BATCH_FILES: usize = 10;
+
+/// A verdict as stored in the index, with enough context to audit it later.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct VerdictRecord {
+    /// `"keep"` or `"delete"`.
+    pub verdict:        String,
+    /// The model's explanation: the answer's `reason`, or for a free-text
+    /// answer the text before its decision line.
+    pub reasoning:      String,
+    pub raw_output:     String,
+    /// Engine that produced this verdict: the GGUF model's file name, `mock` or `replay`.
+    pub model:          String,
+    pub prompt_version: u32,
+    /// When the verdict was inferred (unix seconds).
+    pub decided_at:     i64,
+}
 
-// Scans `./src/models/` and returns the path to the first `.gguf` file found.
-/// Returns an error if the directory doesn't exist or contains no `.gguf` files.
-fn find_model_file() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let models_dir = std::path::Path::new("./src/models");
+impl VerdictRecord {
+    pub fn from_output(raw: &str, model: &str) -> Self {
+        let decided_at = unix_now();
+        Self {
+            verdict: parse_verdict(raw),
+            reasoning: parse_reasoning(raw),
+            raw_output: raw.to_string(),
+            model: model.to_string(),
+            prompt_version: PROMPT_VERSION,
+            decided_at,
+        }
+    }
+
+    pub fn is_delete(&self) -> bool {
+        self.verdict == "delete"
+    }
+
+    /// What the raw output decided, and how plainly it said so.
+    pub fn decision(&self) -> (Decision, u8) {
+        parse_decision(&self.raw_output)
+    }
+}
+
+/// What should become of a file.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Decision {
+    Keep,
+    Delete,
+    /// No clear answer; treated as keep.
+    Unsure,
+}
+
+/// The answer `llm_engine::VERDICT_GRAMMAR` holds the model to, as one line of JSON:
+/// `{"decision":"delete","confidence":90,"reason":"Old cache file."}`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Answer {
+    pub decision:   Decision,
+    /// 0 to 100.
+    pub confidence: u8,
+    pub reason:     String,
+}
+
+impl Answer {
+    /// `raw` as an answer, if it is one: a JSON object with exactly these
+    /// fields and a confidence of at most 100.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let answer: Self = serde_json::from_str(raw.trim()).ok()?;
+        (answer.confidence <= 100).then_some(answer)
+    }
+}
+
+/// Who reached a verdict.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum VerdictSource {
+    /// A fixed rule, without asking the model.
+    Rule,
+    /// The model, now or on an earlier scan.
+    Ai,
+    /// Byte-identical copy of `keeper`; `members` lists every copy, keeper first.
+    Duplicate { keeper: PathBuf, members: Vec<PathBuf> },
+}
+
+/// One file's verdict, as sent from the AI actor to the UI. The UI's review
+/// list holds these too, with duplicate copies turned into verdicts of their own.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Verdict {
+    pub meta:       FileMeta,
+    pub decision:   Decision,
+    /// How sure the source is of `decision`, as a percentage.
+    pub confidence: u8,
+    pub reasoning:  String,
+    pub source:     VerdictSource,
+}
+
+impl Verdict {
+    /// The verdict on `meta` from what its engine answered: a rule's when the
+    /// mock engine wrote the record, the model's otherwise.
+    pub fn from_record(meta: FileMeta, record: &VerdictRecord) -> Self {
+        let (decision, confidence) = record.decision();
+        let source = if record.model == MOCK_ENGINE { VerdictSource::Rule } else { VerdictSource::Ai };
+        Self { meta, decision, confidence, reasoning: record.reasoning.clone(), source }
+    }
+
+    /// A delete verdict for each redundant copy in the group.
+    pub fn from_duplicates(group: &DuplicateGroup) -> Vec<Verdict> {
+        let members = group.member_paths();
+        group.redundant
+            .iter()
+            .map(|m| Verdict {
+                meta:       m.clone(),
+                decision:   Decision::Delete,
+                confidence: 100,
+                reasoning:  format!("Identical to {}", group.keeper.abs_path.display()),
+                source:     VerdictSource::Duplicate {
+                    keeper:  group.keeper.abs_path.clone(),
+                    members: members.clone(),
+                },
+            })
+            .collect()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.meta.abs_path
+    }
+
+    pub fn is_delete(&self) -> bool {
+        self.decision == Decision::Delete
+    }
+}
 
+/// Scans `models_dir` (`LLM.models-dir`) and returns the path to the first `.gguf` file found.
+/// Returns an error if the directory doesn't exist or contains no `.gguf` files.
+pub(crate) fn find_model_file(models_dir: &std::path::Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let entry = fs::read_dir(models_dir)
-        .map_err(|e| format!("Could not open models directory './src/models': {}", e))?
+        .map_err(|e| format!("Could not open models directory '{}': {}", models_dir.display(), e))?
         .filter_map(|res| res.ok())
         .map(|e| e.path())
         .find(|p| p.extension().and_then(|ext| ext.to_str()) == Some("gguf"))
-        .ok_or_else(|| "No .gguf model file found in './src/models'".to_string())?;
+        .ok_or_else(|| format!("No .gguf model file found in '{}'", models_dir.display()))?;
 
     Ok(entry)
 }
 
+/// The name the engine `LLM.engine` names stores with its verdicts, worked out
+/// without loading it: the model's file name, `mock` or `replay`. `None` when
+/// there is no model to load, which `open_engine` reports.
+pub(crate) fn engine_name(config: &LlmConfig) -> Option<String> {
+    match config.engine {
+        Engine::Llama => {
+            let model_path = match &config.model {
+                Some(path) => path.clone(),
+                None => find_model_file(&config.models_dir).ok()?,
+            };
+            Some(model_path.file_name()?.to_string_lossy().into_owned())
+        }
+        Engine::Mock => Some(MOCK_ENGINE.to_string()),
+        Engine::Replay => Some(REPLAY_ENGINE.to_string()),
+    }
+}
+
+/// Whether `record` was given by `model` under the current prompt, so it
+/// need not be asked for again while the file is unchanged.
+pub(crate) fn is_current(record: &VerdictRecord, model: &str) -> bool {
+    record.prompt_version == PROMPT_VERSION && record.model == model
+}
+
+/// The engine `LLM.engine` names, recording its answers when `LLM.recording`
+/// is set and it isn't the replay engine itself.
+pub(crate) fn open_engine(config: &LlmConfig) -> Result<Box<dyn VerdictEngine>, Box<dyn std::error::Error>> {
+    let engine: Box<dyn VerdictEngine> = match config.engine {
+        Engine::Llama => {
+            // A model named in the configuration or on the command line wins over whatever is in the models directory
+            let model_path = match &config.model {
+                Some(path) => path.clone(),
+                None => {
+                    // Created on first run so there is an obvious place to put a model
+                    let _ = fs::create_dir_all(&config.models_dir);
+                    find_model_file(&config.models_dir)?
+                }
+            };
+            let model_path_str = model_path
+                .to_str()
+                .ok_or("Model path contains invalid UTF-8")?;
+            eprintln!("AI_MODEL: loading model from {}", model_path_str);
+
+            Box::new(LlmEngine::load_new_model(model_path_str, config.clone())?.with_prompt_prefix(PROMPT_PREFIX))
+        }
+        Engine::Mock => Box::new(MockEngine::new()),
+        Engine::Replay => {
+            eprintln!("AI_MODEL: replaying answers from {}", config.recording.display());
+            return Ok(Box::new(ReplayEngine::load(&config.recording)?));
+        }
+    };
 
+    if config.recording.as_os_str().is_empty() {
+        Ok(engine)
+    } else {
+        Ok(Box::new(RecordingEngine::new(engine, &config.recording)))
+    }
+}
 
 pub async fn run(
     actor: SteadyActorShadow,
     crawler_to_model_rx: SteadyRx<FileMeta>,
-    ai_model_to_ui_tx: SteadyTx<String>,
+    ai_model_to_ui_tx: SteadyTx<Verdict>,
+    ai_model_to_db_tx: SteadyTx<IndexEntry>,
+    index: FileIndex,
+    config: LlmConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let actor = actor.into_spotlight([&crawler_to_model_rx], [&ai_model_to_ui_tx]);
+    let actor = actor.into_spotlight([&crawler_to_model_rx], [&ai_model_to_ui_tx, &ai_model_to_db_tx]);
 
     if actor.use_internal_behavior {
-        internal_behavior(actor, crawler_to_model_rx, ai_model_to_ui_tx).await
+        internal_behavior(actor, crawler_to_model_rx, ai_model_to_ui_tx, ai_model_to_db_tx, index, config).await
     } else {
         actor.simulated_behavior(vec!(&crawler_to_model_rx)).await
     }
@@ -41,53 +240,71 @@ pub async fn run(
 async fn internal_behavior<A: SteadyActor>(
     mut actor: A,
     crawler_to_ai_model_rx: SteadyRx<FileMeta>,
-    ai_model_to_ui_tx: SteadyTx<String>,
+    ai_model_to_ui_tx: SteadyTx<Verdict>,
+    ai_model_to_db_tx: SteadyTx<IndexEntry>,
+    index: FileIndex,
+    config: LlmConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut crawler_to_ai_model_rx = crawler_to_ai_model_rx.lock().await;
     let mut ai_model_to_ui_tx = ai_model_to_ui_tx.lock().await;
+    let mut ai_model_to_db_tx = ai_model_to_db_tx.lock().await;
 
-    let model_path = find_model_file()?;
-    let model_path_str = model_path
-        .to_str()
-        .ok_or("Model path contains invalid UTF-8")?;
-    eprintln!("AI_MODEL: loading model from {}", model_path_str);
+    let engine = open_engine(&config)?;
+    let model_name = engine.name().to_string();
 
-    let engine = match LlmEngine::load_new_model(model_path_str) {
-        Ok(e) => e,
-        Err(e) => return Err(e.into()),
-    };
-
-    while actor.is_running(|| crawler_to_ai_model_rx.is_closed_and_empty() || ai_model_to_ui_tx.mark_closed()) {
+    while actor.is_running(|| crawler_to_ai_model_rx.is_closed_and_empty()
+                               || (ai_model_to_ui_tx.mark_closed() && ai_model_to_db_tx.mark_closed())) {
         await_for_all!(
             actor.wait_avail(&mut crawler_to_ai_model_rx, 1),
-            actor.wait_vacant(&mut ai_model_to_ui_tx, 1)
+            actor.wait_vacant(&mut ai_model_to_ui_tx, 1),
+            actor.wait_vacant(&mut ai_model_to_db_tx, 1)
         );
 
-        let file_meta = match actor.try_take(&mut crawler_to_ai_model_rx) {
-            Some(m) => m,
+        // Every file has been asked about — close our side so the DB actor and
+        // a headless run can tell the model is done
+        if crawler_to_ai_model_rx.is_closed_and_empty() {
+            ai_model_to_ui_tx.mark_closed();
+            ai_model_to_db_tx.mark_closed();
+            break;
+        }
+
+        let mut batch = match actor.try_take(&mut crawler_to_ai_model_rx) {
+            Some(m) => vec![m],
             None => continue,
         };
+        // Files waiting right behind it in the same directory go in the same prompt
+        while batch.len() < config.batch_size
+            && actor.try_peek(&mut crawler_to_ai_model_rx).is_some_and(|next| same_directory(next, &batch[0]))
+            && let Some(meta) = actor.try_take(&mut crawler_to_ai_model_rx)
+        {
+            batch.push(meta);
+        }
 
-        let prompt = build_prompt(&file_meta);
-       
-        let verdict = match engine.infer_model(&prompt) {
-            Ok(raw) => {
-                //eprintln!("AI_MODEL: raw output: {:?}", raw);
-                parse_verdict(&raw)
-            }
-            Err(e) => {
-                //eprintln!("AI_MODEL: inference FAILED: {}", e);
-                continue;
+        // An unchanged file keeps the verdict it got on an earlier scan
+        let mut verdicts = Vec::with_capacity(batch.len());
+        let mut ask = Vec::new();
+        for file_meta in batch {
+            match cached_verdict(&index, &file_meta, &model_name) {
+                Some(record) => verdicts.push((file_meta, record)),
+                None => ask.push(file_meta),
             }
-        };
+        }
+
+        for (file_meta, record) in infer_verdicts(engine.as_ref(), ask, &model_name) {
+            let entry = IndexEntry::new(file_meta.clone(), Some(record.clone()));
+            actor.send_async(&mut ai_model_to_db_tx, entry, SendSaturation::AwaitForRoom).await;
+            verdicts.push((file_meta, record));
+        }
 
-        let message = format!("{}|{}", verdict, file_meta.abs_path.display());
-        loop {
-            actor.wait_vacant(&mut ai_model_to_ui_tx, 1).await;
-            match actor.try_send(&mut ai_model_to_ui_tx, message.clone()) {
-                SendOutcome::Success => break,
-                SendOutcome::Blocked(_) => continue,
-                other => break,
+        for (file_meta, record) in verdicts {
+            let message = Verdict::from_record(file_meta, &record);
+            loop {
+                actor.wait_vacant(&mut ai_model_to_ui_tx, 1).await;
+                match actor.try_send(&mut ai_model_to_ui_tx, message.clone()) {
+                    SendOutcome::Success => break,
+                    SendOutcome::Blocked(_) => continue,
+                    other => break,
+                }
             }
         }
     }
@@ -95,10 +312,93 @@ async fn internal_behavior<A: SteadyActor>(
     Ok(())
 }
 
-/// Parses the model's response to find "Decision: keep" or "Decision: delete".
-/// Falls back to scanning for bare "keep"/"delete" keywords if the structured
-/// line is absent. Defaults to "keep" when nothing matches.
+fn same_directory(a: &FileMeta, b: &FileMeta) -> bool {
+    a.abs_path.parent() == b.abs_path.parent()
+}
+
+/// Fresh verdicts for `metas`. Several files are asked about in one prompt
+/// first; any the answer left out or didn't answer properly are then asked
+/// about one at a time. A file the engine can't answer for at all is left out.
+fn infer_verdicts(engine: &dyn VerdictEngine, metas: Vec<FileMeta>, model: &str) -> Vec<(FileMeta, VerdictRecord)> {
+    let batched = if metas.len() > 1 {
+        engine.infer_batch(&metas, &build_batch_prompt(&metas))
+            .ok()
+            .filter(|answers| answers.len() == metas.len())
+    } else {
+        None
+    };
+    let batched = batched.unwrap_or_else(|| vec![None; metas.len()]);
+
+    metas
+        .into_iter()
+        .zip(batched)
+        .filter_map(|(meta, answer)| {
+            let raw = match answer.filter(|raw| Answer::parse(raw).is_some()) {
+                Some(raw) => raw,
+                None => engine.infer(&meta, &build_prompt(&meta)).ok()?,
+            };
+            let record = VerdictRecord::from_output(&raw, model);
+            Some((meta, record))
+        })
+        .collect()
+}
+
+/// Verdict stored for this file by an earlier scan, if the file is still the
+/// same version (size, mtime, inode) it was given for and the verdict came from
+/// the same model and prompt version.
+fn cached_verdict(index: &FileIndex, meta: &FileMeta, model: &str) -> Option<VerdictRecord> {
+    let entry = index.get(&meta.abs_path).ok()??;
+    if !file_index::same_file_version(&entry.meta, meta) {
+        return None;
+    }
+    entry.verdict.filter(|v| is_current(v, model))
+}
+
+/// Extracts the model's reasoning: the `reason` of a structured answer, or for
+/// free text everything before the "Decision:" line, without a leading
+/// "Reasoning:" label. An answer that doesn't match the schema has none.
+fn parse_reasoning(raw: &str) -> String {
+    if is_structured(raw) {
+        return Answer::parse(raw).map(|a| a.reason.trim().to_string()).unwrap_or_default();
+    }
+    let text: Vec<&str> = raw
+        .lines()
+        .map(str::trim)
+        .take_while(|line| !line.to_lowercase().starts_with("decision:"))
+        .filter(|line| !line.is_empty())
+        .collect();
+    let text = text.join(" ");
+    match text.get(..10) {
+        Some(label) if label.eq_ignore_ascii_case("reasoning:") => text[10..].trim().to_string(),
+        _ => text,
+    }
+}
+
+/// The decision in the model's response as `"keep"` or `"delete"`; anything
+/// short of a clear delete is "keep".
 fn parse_verdict(raw: &str) -> String {
+    match parse_decision(raw).0 {
+        Decision::Delete => "delete".to_string(),
+        Decision::Keep | Decision::Unsure => "keep".to_string(),
+    }
+}
+
+/// The decision in the model's response, with how sure it is. A structured
+/// answer carries its own confidence; one that starts like JSON but doesn't
+/// match the schema — cut short by `LLM.max-tokens`, say — is `Unsure`.
+/// Free text, as answers were before the grammar, is read the old way: a
+/// "Decision:" line is trusted most, a bare keyword somewhere in the text
+/// less, and nothing at all is `Unsure`.
+pub(crate) fn parse_decision(raw: &str) -> (Decision, u8) {
+    if is_structured(raw) {
+        return Answer::parse(raw).map_or((Decision::Unsure, 0), |a| (a.decision, a.confidence));
+    }
+
+    let decision = |word: &str| match word {
+        "delete" => Some(Decision::Delete),
+        "keep" => Some(Decision::Keep),
+        _ => None,
+    };
     let lower = raw.to_lowercase();
 
     // Primary: look for "decision: <verdict>" anywhere in the output
@@ -113,8 +413,8 @@ fn parse_verdict(raw: &str) -> String {
                 .filter(|c| c.is_alphabetic())
                 .collect::<String>();
 
-            if word == "delete" || word == "keep" {
-                return word;
+            if let Some(decision) = decision(&word) {
+                return (decision, 90);
             }
         }
     }
@@ -122,52 +422,85 @@ fn parse_verdict(raw: &str) -> String {
     // Fallback: scan every word for an explicit keep/delete keyword
     for word in lower.split_whitespace() {
         let clean: String = word.chars().filter(|c| c.is_alphabetic()).collect();
-        if clean == "delete" || clean == "keep" {
-            return clean;
+        if let Some(decision) = decision(&clean) {
+            return (decision, 50);
         }
     }
 
     // Default: when uncertain, always keep
-    "keep".to_string()
+    (Decision::Unsure, 0)
 }
 
-fn build_prompt(meta: &FileMeta) -> String {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64;
-    let days_since_modified = (now - meta.modified) / 86400;
+/// Whether `raw` is meant as a structured answer rather than free text.
+fn is_structured(raw: &str) -> bool {
+    raw.trim_start().starts_with('{')
+}
 
-    format!(
-        r#"You are a file management assistant. Your job is to decide whether a file should be kept or deleted based on its metadata.
+/// The part of every prompt that doesn't depend on the files asked about:
+/// instructions and examples. The engine decodes it once and keeps it in its
+/// KV cache, for single and batched prompts alike.
+pub(crate) const PROMPT_PREFIX: &str = r#"You are a file management assistant. Your job is to decide whether a file should be kept or deleted based on its metadata.
 
         ### Decision Rules
         - DELETE if: the file has not been accessed or modified in over 365 days AND the file name suggests it is temporary, a draft, a cache, or a duplicate (e.g., contains "tmp", "temp", "cache", "copy", "backup", "old", "~", or ends in ".log", ".bak", ".swp")
         - DELETE if: the file is very small (under 512 bytes), has not been modified in over 180 days, and the name suggests it is a leftover or auto-generated artifact
         - KEEP if: the file is read-only (system or protected files are rarely safe to delete)
         - KEEP if: the file has been modified recently (within 30 days)
-        - KEEP if: uncertain — always prefer keeping over deleting
+        - UNSURE if: none of the rules clearly applies — unsure files are kept
+
+        ### Answer format
+        One line of JSON: the decision ("keep", "delete" or "unsure"), how confident you are from 0 to 100, and a short reason.
 
         ### Examples
         File: "cache_session_1A2B.tmp", 204 bytes, 412 days old, read-only: false
-        Reasoning: Name contains "cache" and ".tmp", very old, small, not protected.
-        Decision: delete
+        Answer: {"decision":"delete","confidence":90,"reason":"Name contains cache and .tmp, very old, small, not protected."}
 
         File: "project_report_final.pdf", 84200 bytes, 5 days old, read-only: false
-        Reasoning: Recently modified, meaningful name, substantial size.
-        Decision: keep
+        Answer: {"decision":"keep","confidence":95,"reason":"Recently modified, meaningful name, substantial size."}
 
         File: "libsystem_kernel.dylib", 512 bytes, 730 days old, read-only: true
-        Reasoning: Read-only flag suggests system file. Never delete.
-        Decision: keep
+        Answer: {"decision":"keep","confidence":99,"reason":"Read-only flag suggests a system file. Never delete."}
 
         File: "notes_backup_old.txt", 1100 bytes, 200 days old, read-only: false
-        Reasoning: Name contains "backup" and "old", moderately old, small, not protected.
-        Decision: delete
+        Answer: {"decision":"delete","confidence":70,"reason":"Name contains backup and old, moderately old, small, not protected."}
+
+        File: "thesis_draft.docx", 48000 bytes, 400 days old, read-only: false
+        Answer: {"decision":"unsure","confidence":40,"reason":"Old, but the name suggests work that may still matter."}
+
+        "#;
+
+fn build_prompt(meta: &FileMeta) -> String {
+    format!(
+        "{}### Now decide for this file\n        {}\n        Answer:",
+        PROMPT_PREFIX,
+        describe(meta, unix_now()),
+    )
+}
 
-        ### Now decide for this file
-        File: "{}", {} bytes, {} days since last modified, read-only: {}
-        Reasoning:"#,
+/// A prompt about every file in `metas`, numbered from 1. The answer is one
+/// line per file, `<number>: <answer>`, as `llm_engine::batch_grammar` holds it to.
+fn build_batch_prompt(metas: &[FileMeta]) -> String {
+    let now = unix_now();
+    let files: String = metas
+        .iter()
+        .enumerate()
+        .map(|(i, meta)| format!("{}. {}\n        ", i + 1, describe(meta, now)))
+        .collect();
+
+    format!(
+        "{}### Now decide for each of these files\n        \
+         Answer each on its own line, after its number: 1: {{...}}\n        \
+         {}Answers:\n",
+        PROMPT_PREFIX,
+        files,
+    )
+}
+
+/// What the prompt tells the model about a file.
+fn describe(meta: &FileMeta, now: i64) -> String {
+    let days_since_modified = (now - meta.modified) / 86400;
+    format!(
+        r#"File: "{}", {} bytes, {} days since last modified, read-only: {}"#,
         meta.file_name,
         meta.size,
         days_since_modified,
@@ -180,6 +513,7 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
     use crate::actor::crawler::FileMeta;
+    use crate::hashing::HashStrategy;
 
     // ── helpers ──────────────────────────────────────────────────────────────
 
@@ -200,7 +534,329 @@ mod tests {
             hash: String::new(),                   // empty hash for testing purposes
             is_file: true,                         // assume it's a file in all test cases
             created: 0,                            // epoch default, same as modified
+            hash_strategy: HashStrategy::default(),
+            full_hash: None,
+            inode: 0,
+        }
+    }
+
+    fn record(verdict: &str, model: &str) -> VerdictRecord {
+        VerdictRecord::from_output(&format!("Decision: {}", verdict), model)
+    }
+
+    /// Gives back `batch` for any batch, and a keep for any file asked about on its own.
+    struct Patchy {
+        batch:       Vec<Option<String>>,
+        asked_alone: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl Patchy {
+        fn new(batch: Vec<Option<&str>>) -> Self {
+            Self { batch: batch.into_iter().map(|a| a.map(String::from)).collect(), asked_alone: Default::default() }
         }
+
+        fn asked_alone(&self) -> Vec<String> {
+            self.asked_alone.lock().unwrap().clone()
+        }
+    }
+
+    impl VerdictEngine for Patchy {
+        fn infer(&self, meta: &FileMeta, _prompt: &str) -> anyhow::Result<String> {
+            self.asked_alone.lock().unwrap().push(meta.file_name.clone());
+            Ok(r#"{"decision":"keep","confidence":60,"reason":"Alone."}"#.into())
+        }
+
+        fn infer_batch(&self, _metas: &[FileMeta], _prompt: &str) -> anyhow::Result<Vec<Option<String>>> {
+            Ok(self.batch.clone())
+        }
+
+        fn name(&self) -> &str {
+            "patchy"
+        }
+    }
+
+    const BATCHED: &str = r#"{"decision":"delete","confidence":80,"reason":"Batched."}"#;
+
+    fn files(names: &[&str]) -> Vec<FileMeta> {
+        names.iter().map(|n| make_meta(n, 1, 0, false, &format!("/tmp/{}", n))).collect()
+    }
+
+    fn reasons(verdicts: &[(FileMeta, VerdictRecord)]) -> Vec<&str> {
+        verdicts.iter().map(|(_, r)| r.reasoning.as_str()).collect()
+    }
+
+    fn open_temp_index(test_name: &str) -> FileIndex {
+        let path = std::env::temp_dir().join(format!("cruft_test_ai_index_{}", test_name));
+        let _ = std::fs::remove_dir_all(&path);
+        FileIndex::open(&sled::open(&path).unwrap()).unwrap()
+    }
+
+    // ── cached_verdict ────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_cached_verdict_reused_for_same_version() {
+        let index = open_temp_index("same");
+        let meta = make_meta("old.tmp", 100, 1000, false, "/tmp/old.tmp");
+        index.put(&IndexEntry::new(meta.clone(), Some(record("delete", "m.gguf")))).unwrap();
+        let cached = cached_verdict(&index, &meta, "m.gguf").unwrap();
+        assert!(cached.is_delete());
+        assert_eq!(cached.model, "m.gguf");
+    }
+
+    #[test]
+    fn test_cached_verdict_ignored_when_file_changed() {
+        let index = open_temp_index("changed");
+        let meta = make_meta("old.tmp", 100, 1000, false, "/tmp/old.tmp");
+        index.put(&IndexEntry::new(meta.clone(), Some(record("delete", "m.gguf")))).unwrap();
+
+        let touched = make_meta("old.tmp", 100, 2000, false, "/tmp/old.tmp");
+        assert_eq!(cached_verdict(&index, &touched, "m.gguf"), None);
+    }
+
+    #[test]
+    fn test_cached_verdict_ignored_for_other_model() {
+        let index = open_temp_index("model");
+        let meta = make_meta("old.tmp", 100, 1000, false, "/tmp/old.tmp");
+        index.put(&IndexEntry::new(meta.clone(), Some(record("delete", "a.gguf")))).unwrap();
+        assert_eq!(cached_verdict(&index, &meta, "b.gguf"), None);
+    }
+
+    #[test]
+    fn test_cached_verdict_ignored_for_old_prompt_version() {
+        let index = open_temp_index("prompt_version");
+        let meta = make_meta("old.tmp", 100, 1000, false, "/tmp/old.tmp");
+        let mut old = record("delete", "m.gguf");
+        old.prompt_version = PROMPT_VERSION - 1;
+        index.put(&IndexEntry::new(meta.clone(), Some(old))).unwrap();
+        assert_eq!(cached_verdict(&index, &meta, "m.gguf"), None);
+    }
+
+    #[test]
+    fn test_cached_verdict_none_without_entry_or_verdict() {
+        let index = open_temp_index("none");
+        let meta = make_meta("new.txt", 1, 1, false, "/tmp/new.txt");
+        assert_eq!(cached_verdict(&index, &meta, "m.gguf"), None);
+
+        index.put(&IndexEntry::new(meta.clone(), None)).unwrap();
+        assert_eq!(cached_verdict(&index, &meta, "m.gguf"), None);
+    }
+
+    // ── infer_verdicts ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_infer_verdicts_falls_back_for_missing_and_invalid_answers() {
+        let engine = Patchy::new(vec![Some(BATCHED), Some(r#"{"decision":"#), None]);
+        let verdicts = infer_verdicts(&engine, files(&["a.tmp", "b.tmp", "c.tmp"]), "patchy");
+
+        assert_eq!(reasons(&verdicts), ["Batched.", "Alone.", "Alone."]);
+        assert_eq!(verdicts[0].0.file_name, "a.tmp");
+        assert_eq!(verdicts[0].1.decision(), (Decision::Delete, 80));
+        assert_eq!(engine.asked_alone(), ["b.tmp", "c.tmp"]);
+    }
+
+    #[test]
+    fn test_infer_verdicts_distrusts_a_batch_of_the_wrong_length() {
+        let engine = Patchy::new(vec![Some(BATCHED)]);
+        let verdicts = infer_verdicts(&engine, files(&["a.tmp", "b.tmp"]), "patchy");
+        assert_eq!(reasons(&verdicts), ["Alone.", "Alone."]);
+    }
+
+    #[test]
+    fn test_infer_verdicts_asks_about_a_single_file_alone() {
+        let engine = Patchy::new(vec![Some(BATCHED)]);
+        let verdicts = infer_verdicts(&engine, files(&["a.tmp"]), "patchy");
+        assert_eq!(reasons(&verdicts), ["Alone."]);
+        assert!(infer_verdicts(&engine, Vec::new(), "patchy").is_empty());
+    }
+
+    #[test]
+    fn test_infer_verdicts_skips_files_the_engine_cannot_answer() {
+        let recording = std::env::temp_dir().join("cruft_test_ai_partial_recording.jsonl");
+        let _ = std::fs::remove_file(&recording);
+        let [a, b] = <[FileMeta; 2]>::try_from(files(&["a.tmp", "b.tmp"])).unwrap();
+        RecordingEngine::new(Box::new(MockEngine::new()), &recording).infer(&a, "p").unwrap();
+
+        let replay = ReplayEngine::load(&recording).unwrap();
+        let verdicts = infer_verdicts(&replay, vec![a.clone(), b], "replay");
+        assert_eq!(verdicts.len(), 1);
+        assert_eq!(verdicts[0].0, a);
+        let _ = std::fs::remove_file(&recording);
+    }
+
+    // ── open_engine ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_open_engine_mock_needs_no_model() {
+        let config = LlmConfig { engine: Engine::Mock, models_dir: PathBuf::from("/nonexistent/models"), ..Default::default() };
+        assert_eq!(open_engine(&config).unwrap().name(), "mock");
+    }
+
+    #[test]
+    fn test_open_engine_records_then_replays() {
+        let recording = std::env::temp_dir().join("cruft_test_ai_recording.jsonl");
+        let _ = std::fs::remove_file(&recording);
+        let meta = make_meta("cache.tmp", 10, 0, false, "/tmp/cache.tmp");
+
+        let config = LlmConfig { engine: Engine::Mock, recording: recording.clone(), ..Default::default() };
+        let answer = open_engine(&config).unwrap().infer(&meta, "p").unwrap();
+
+        let config = LlmConfig { engine: Engine::Replay, ..config };
+        let replay = open_engine(&config).unwrap();
+        assert_eq!(replay.name(), "replay");
+        assert_eq!(replay.infer(&meta, "p").unwrap(), answer);
+        let _ = std::fs::remove_file(&recording);
+    }
+
+    #[test]
+    fn test_open_engine_llama_without_model_fails() {
+        let config = LlmConfig { model: Some(PathBuf::from("/nonexistent/m.gguf")), ..Default::default() };
+        assert!(open_engine(&config).is_err());
+    }
+
+    // ── VerdictRecord ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_record_from_output_keeps_everything() {
+        let raw = " Name contains \"tmp\", very old.\nDecision: delete";
+        let r = VerdictRecord::from_output(raw, "model.gguf");
+
+        assert_eq!(r.verdict, "delete");
+        assert!(r.is_delete());
+        assert_eq!(r.reasoning, "Name contains \"tmp\", very old.");
+        assert_eq!(r.raw_output, raw);
+        assert_eq!(r.model, "model.gguf");
+        assert_eq!(r.prompt_version, PROMPT_VERSION);
+        assert!(r.decided_at > 0);
+    }
+
+    #[test]
+    fn test_record_survives_index_roundtrip() {
+        let index = open_temp_index("roundtrip");
+        let meta = make_meta("a.txt", 1, 1, false, "/tmp/a.txt");
+        let r = record("keep", "m.gguf");
+        index.put(&IndexEntry::new(meta.clone(), Some(r.clone()))).unwrap();
+        assert_eq!(index.get(&meta.abs_path).unwrap().unwrap().verdict, Some(r));
+    }
+
+    // ── Verdict ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_verdict_from_record_keeps_meta_and_reasoning() {
+        let meta = make_meta("old.tmp", 100, 1000, false, "/tmp/old.tmp");
+        let r = VerdictRecord::from_output("Cache file, very old.\nDecision: delete", "m.gguf");
+
+        let verdict = Verdict::from_record(meta.clone(), &r);
+
+        assert_eq!(verdict.meta, meta);
+        assert_eq!(verdict.decision, Decision::Delete);
+        assert_eq!(verdict.confidence, 90);
+        assert_eq!(verdict.reasoning, "Cache file, very old.");
+        assert_eq!(verdict.source, VerdictSource::Ai);
+        assert!(verdict.is_delete());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_verdict_keeps_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt;
+        let raw = std::ffi::OsStr::from_bytes(b"/tmp/caf\xe9.tmp");
+        let meta = make_meta("caf.tmp", 1, 1, false, "/");
+        let meta = FileMeta { abs_path: PathBuf::from(raw), ..meta };
+
+        let verdict = Verdict::from_record(meta, &record("delete", "m.gguf"));
+
+        assert_eq!(verdict.path().as_os_str(), raw);
+    }
+
+    #[test]
+    fn test_mock_verdicts_come_from_rules() {
+        let meta = make_meta("old.tmp", 100, 1000, false, "/tmp/old.tmp");
+        let raw = MockEngine::at(1000 + 400 * 86400).infer(&meta, "").unwrap();
+
+        let verdict = Verdict::from_record(meta, &VerdictRecord::from_output(&raw, MOCK_ENGINE));
+
+        assert_eq!(verdict.source, VerdictSource::Rule);
+        assert_eq!(Verdict::from_record(verdict.meta.clone(), &record("delete", "replay")).source, VerdictSource::Ai);
+    }
+
+    #[test]
+    fn test_from_duplicates_is_sure_of_every_copy() {
+        let keeper = make_meta("a.iso", 10, 1, false, "/keep/a.iso");
+        let copy = make_meta("a.iso", 10, 2, false, "/copy/a.iso");
+        let group = DuplicateGroup { size: 10, content_hash: "h".into(), keeper, redundant: vec![copy.clone()] };
+
+        let verdicts = Verdict::from_duplicates(&group);
+
+        assert_eq!(verdicts.len(), 1);
+        assert_eq!(verdicts[0].meta, copy);
+        assert_eq!((verdicts[0].decision, verdicts[0].confidence), (Decision::Delete, 100));
+        assert_eq!(verdicts[0].reasoning, "Identical to /keep/a.iso");
+    }
+
+    // ── parse_decision ────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_decision_confidence_follows_how_it_was_found() {
+        assert_eq!(parse_decision("Decision: delete"), (Decision::Delete, 90));
+        assert_eq!(parse_decision("you should keep it"), (Decision::Keep, 50));
+        assert_eq!(parse_decision("no idea"), (Decision::Unsure, 0));
+    }
+
+    #[test]
+    fn test_parse_decision_takes_a_structured_answer_at_its_word() {
+        assert_eq!(parse_decision(r#"{"decision":"delete","confidence":72,"reason":"old"}"#), (Decision::Delete, 72));
+        assert_eq!(parse_decision(r#" {"decision": "keep", "confidence": 100, "reason": ""} "#), (Decision::Keep, 100));
+        assert_eq!(parse_decision(r#"{"decision":"unsure","confidence":30,"reason":"?"}"#), (Decision::Unsure, 30));
+    }
+
+    #[test]
+    fn test_parse_decision_invalid_answer_is_unsure() {
+        for raw in [
+            r#"{"decision":"delete","confidence":90,"reas"#,                         // cut short
+            r#"{"decision":"delete","confidence":101,"reason":"old"}"#,               // out of range
+            r#"{"decision":"remove","confidence":90,"reason":"old"}"#,                // not a decision
+            r#"{"decision":"delete","confidence":90}"#,                               // missing field
+            r#"{"decision":"delete","confidence":90,"reason":"old","extra":1}"#,      // unknown field
+            r#"{"decision":"delete","confidence":-1,"reason":"old"} Decision: delete"#,
+        ] {
+            assert_eq!(parse_decision(raw), (Decision::Unsure, 0), "{}", raw);
+            assert_eq!(parse_verdict(raw), "keep", "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_structured_answer_fills_the_record() {
+        let r = VerdictRecord::from_output(r#"{"decision":"delete","confidence":80,"reason":" Old cache file. "}"#, "m.gguf");
+        assert!(r.is_delete());
+        assert_eq!(r.reasoning, "Old cache file.");
+        assert_eq!(r.decision(), (Decision::Delete, 80));
+    }
+
+    #[test]
+    fn test_unsure_is_stored_as_keep() {
+        let r = VerdictRecord::from_output("Decision: uncertain", "m.gguf");
+        assert_eq!(r.verdict, "keep");
+        assert_eq!(r.decision().0, Decision::Unsure);
+    }
+
+    // ── parse_reasoning ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_reasoning_stops_at_decision() {
+        let raw = " Recently modified.\nMeaningful name.\nDecision: keep\nExtra text";
+        assert_eq!(parse_reasoning(raw), "Recently modified. Meaningful name.");
+    }
+
+    #[test]
+    fn test_parse_reasoning_strips_label() {
+        assert_eq!(parse_reasoning("Reasoning: old cache.\nDecision: delete"), "old cache.");
+        assert_eq!(parse_reasoning("REASONING:old cache."), "old cache.");
+    }
+
+    #[test]
+    fn test_parse_reasoning_empty_without_text() {
+        assert_eq!(parse_reasoning("Decision: keep"), "");
+        assert_eq!(parse_reasoning(""), "");
     }
 
     // ── parse_verdict: primary "Decision:" path ───────────────────────────────
@@ -412,10 +1068,49 @@ mod tests {
     }
 
     #[test]
-    fn test_build_prompt_structure_ends_with_reasoning_prompt() {
+    fn test_build_prompt_structure_ends_with_answer_prompt() {
+        let meta = make_meta("file.txt", 1000, 0, false, "/tmp/file.txt");
+        let prompt = build_prompt(&meta);
+        assert!(prompt.trim_end().ends_with("Answer:"));
+    }
+
+    #[test]
+    fn test_build_prompt_starts_with_the_shared_prefix() {
+        let a = build_prompt(&make_meta("a.txt", 1, 0, false, "/tmp/a.txt"));
+        let b = build_prompt(&make_meta("b.log", 2, 0, true, "/var/b.log"));
+        assert!(a.starts_with(PROMPT_PREFIX) && b.starts_with(PROMPT_PREFIX));
+        assert!(!PROMPT_PREFIX.contains("a.txt"));
+        assert!(a[PROMPT_PREFIX.len()..].starts_with("### Now decide for this file\n        File: \"a.txt\""));
+    }
+
+    #[test]
+    fn test_build_batch_prompt_numbers_every_file() {
+        let prompt = build_batch_prompt(&files(&["a.tmp", "b.log", "c.pdf"]));
+        assert!(prompt.starts_with(PROMPT_PREFIX));
+        for (n, name) in ["a.tmp", "b.log", "c.pdf"].iter().enumerate() {
+            assert!(prompt.contains(&format!("{}. File: \"{}\"", n + 1, name)), "{}", prompt);
+        }
+        assert!(!prompt.contains("4. File"));
+        assert!(prompt.trim_end().ends_with("Answers:"));
+    }
+
+    #[test]
+    fn test_build_batch_prompt_describes_files_like_build_prompt() {
+        let meta = make_meta("cache_old.tmp", 200, 0, true, "/tmp/cache_old.tmp");
+        let line = build_prompt(&meta).lines().rfind(|l| l.contains("File: ")).unwrap().trim().to_string();
+        assert!(build_batch_prompt(std::slice::from_ref(&meta)).contains(&format!("1. {}", line)));
+    }
+
+    #[test]
+    fn test_build_prompt_examples_are_answers() {
         let meta = make_meta("file.txt", 1000, 0, false, "/tmp/file.txt");
         let prompt = build_prompt(&meta);
-        assert!(prompt.trim_end().ends_with("Reasoning:"));
+        let examples: Vec<&str> = prompt
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("Answer: "))
+            .collect();
+        assert_eq!(examples.len(), 5);
+        assert!(examples.iter().all(|e| Answer::parse(e).is_some()), "{:?}", examples);
     }
 
     #[test]
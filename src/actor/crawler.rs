@@ -5,12 +5,20 @@ use steady_state::*;
 use std::path::{Path, PathBuf};
 use sha2::{Sha256, Digest};
 use std::io::prelude::*;
-use walkdir::WalkDir;
+use walkdir::{DirEntry, WalkDir};
 use std::ffi::OsStr;
 use filetime::FileTime;
 use std::error::Error;
 use serde::{Serialize, Deserialize};
-use hex;
+use crate::scan_roots::{self, ScanRoot};
+use crate::hashing::{self, Candidate, CandidateIndex, HashConfig, HashStrategy};
+use crate::file_index::{self, FileIndex, IndexEntry};
+use crate::exclude::{self, ExcludeRules};
+use crate::protection::Protections;
+use crate::config::DirectoryConfig;
+use crate::actor::ai_model;
+use std::collections::{HashSet, VecDeque};
+use std::fs::Metadata;
 
 const WINDOWS_TO_UNIX_EPOCH_OFFSET: i64 = 11_644_473_600;
 // TODO: implement fallback logic
@@ -32,6 +40,25 @@ pub(crate) struct FileMeta {
     pub modified:  i64,
     pub created:   i64,
     pub readonly:  bool,
+    #[serde(default = "HashStrategy::legacy")]
+    pub hash_strategy: HashStrategy,
+    /// Full-content SHA-256, present when `hash` covered the whole file or a
+    /// size+hash collision made it worth computing.
+    #[serde(default)]
+    pub full_hash: Option<String>,
+    /// Inode number on Unix; always 0 elsewhere, where size and mtime alone
+    /// decide whether a file changed between scans.
+    #[serde(default)]
+    pub inode: u64,
+}
+
+/// What the crawler tells the DB about each path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ScanEvent {
+    /// New since the last scan, or changed size, mtime or inode.
+    Upsert(FileMeta),
+    /// Indexed by an earlier scan but no longer found under its root.
+    Vanished(PathBuf),
 }
 
 impl FileMeta {
@@ -41,6 +68,9 @@ impl FileMeta {
         println!("Relative_Path: {:?}", self.rel_path);
         println!("File_Name: {}",       self.file_name);
         println!("hash: {}",            self.hash);
+        println!("hash_strategy: {:?}", self.hash_strategy);
+        println!("full_hash: {:?}",     self.full_hash);
+        println!("inode: {}",           self.inode);
         println!("is_file: {}",         self.is_file);
         println!("size: {}",            self.size);
         println!("modified: {}",        self.modified / 60);
@@ -49,6 +79,11 @@ impl FileMeta {
         println!("Printing Metadata Object -----------\n");
     }
 
+    /// Hash to compare when looking for identical contents, if one is known.
+    pub fn content_hash(&self) -> Option<&str> {
+        self.full_hash.as_deref()
+    }
+
     pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
         Ok(serde_cbor::to_vec(self)?)
     }
@@ -58,81 +93,87 @@ impl FileMeta {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     actor: SteadyActorShadow,
-    crawler_tx: SteadyTx<FileMeta>,
+    crawler_tx: SteadyTx<ScanEvent>,
     crawler_to_model_tx: SteadyTx<FileMeta>,
+    index: FileIndex,
+    protections: Protections,
+    directory: DirectoryConfig,
+    hashing: HashConfig,
+    model: Option<String>,
     state: SteadyState<CrawlerState>,
 ) -> Result<(), Box<dyn std::error::Error>> {
 
     let actor = actor.into_spotlight([], [&crawler_tx, &crawler_to_model_tx]);
 
     if actor.use_internal_behavior {
-        internal_behavior(actor, crawler_tx, crawler_to_model_tx, state).await
+        internal_behavior(actor, crawler_tx, crawler_to_model_tx, index, protections, directory, hashing, model, state).await
     } else {
         actor.simulated_behavior(vec!(&crawler_tx)).await
     }
 }
 
-fn load_scan_dir() -> PathBuf {
-    let config_file = PathBuf::from("scan_path.txt");
+/// Loads every root listed in the scan file in `[directory] scan-list-dir`, plus
+/// `[directory] path` when it is set. Unusable lines are reported and skipped;
+/// an empty result means there is nothing to crawl.
+pub(crate) fn load_scan_roots(directory: &DirectoryConfig) -> Vec<ScanRoot> {
+    let extra = (!directory.path.as_os_str().is_empty()).then(|| ScanRoot::new(directory.path.clone()));
 
-    if !config_file.exists() {
-        eprintln!("\n  ✗ CruftCrawler could not start.");
-        eprintln!("  No 'scan_path.txt' file was found in the current directory.");
-        eprintln!("  Please create a file named 'scan_path.txt' next to the executable");
-        eprintln!("  and put the directory you want to scan on the first line.");
+    let Some(config_file) = scan_roots::find_scan_file(directory.scan_list_dir()) else {
+        // The configured directory is enough to crawl without a scan list
+        if let Some(root) = extra {
+            return vec![root];
+        }
+        eprintln!("\n  ✗ CruftCrawler has nothing to scan.");
+        eprintln!("  No '{}' file was found in {}.", scan_roots::SCAN_FILE, directory.scan_list_dir().display());
+        eprintln!("  Please create it next to the executable and list one directory per line.");
         eprintln!();
         eprintln!("  Example (Windows):  C:\\Users\\YourName\\Documents");
-        eprintln!("  Example (Linux):    /home/yourname/documents");
+        eprintln!("  Example (Linux):    /home/yourname/documents | max_depth=5 exclude=node_modules");
         eprintln!();
-        std::process::exit(1);
-    }
-
-    let contents = std::fs::read_to_string(&config_file).unwrap_or_else(|e| {
-        eprintln!("\n  ✗ CruftCrawler could not start.");
-        eprintln!("  Found 'scan_path.txt' but could not read it: {}", e);
-        eprintln!();
-        std::process::exit(1);
-    });
-
-    let trimmed = contents.trim().to_string();
+        return Vec::new();
+    };
+
+    let (mut roots, errors) = match scan_roots::load_scan_roots(&config_file) {
+        Ok(parsed) => parsed,
+        Err(e) if extra.is_some() => {
+            eprintln!("  ⚠ Crawling only {}: {}", directory.path.display(), e);
+            (Vec::new(), Vec::new())
+        }
+        Err(e) => {
+            eprintln!("\n  ✗ CruftCrawler has nothing to scan: {}", e);
+            return Vec::new();
+        }
+    };
 
-    if trimmed.is_empty() {
-        eprintln!("\n  ✗ CruftCrawler could not start.");
-        eprintln!("  'scan_path.txt' exists but is empty.");
-        eprintln!("  Please put the directory you want to scan on the first line.");
-        eprintln!();
-        std::process::exit(1);
+    for err in &errors {
+        eprintln!("  ⚠ Skipping {} {}", config_file.display(), err);
     }
 
-    let path = PathBuf::from(&trimmed);
-
-    if !path.exists() {
-        eprintln!("\n  ✗ CruftCrawler could not start.");
-        eprintln!("  The path in 'scan_path.txt' does not exist:");
-        eprintln!("    {}", trimmed);
-        eprintln!("  Please check the path is spelled correctly.");
-        eprintln!();
-        std::process::exit(1);
+    if let Some(root) = extra && !roots.iter().any(|r| r.path == root.path) {
+        roots.push(root);
     }
 
-    if !path.is_dir() {
-        eprintln!("\n  ✗ CruftCrawler could not start.");
-        eprintln!("  The path in 'scan_path.txt' is not a directory:");
-        eprintln!("    {}", trimmed);
-        eprintln!("  Please provide a folder path, not a file path.");
+    if roots.is_empty() {
+        eprintln!("\n  ✗ '{}' lists no usable directories.", config_file.display());
         eprintln!();
-        std::process::exit(1);
     }
 
-    path
+    roots
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn internal_behavior<A: SteadyActor>(
     mut actor: A,
-    crawler_tx: SteadyTx<FileMeta>,
+    crawler_tx: SteadyTx<ScanEvent>,
     crawler_to_ai_model_tx: SteadyTx<FileMeta>,
+    index: FileIndex,
+    protections: Protections,
+    directory: DirectoryConfig,
+    hashing: HashConfig,
+    model: Option<String>,
     state: SteadyState<CrawlerState>,
 ) -> Result<(), Box<dyn std::error::Error>> {
 
@@ -144,161 +185,331 @@ async fn internal_behavior<A: SteadyActor>(
     let mut crawler_tx = crawler_tx.lock().await;
     let mut crawler_to_ai_model_tx = crawler_to_ai_model_tx.lock().await;
 
-    
-
-    let path = load_scan_dir();
-    let metas: Vec<FileMeta> = visit_dir(&path, &state)?;
-
-    // ← one file per iteration instead of dumping all at once
-    let mut metas_iter = metas.iter();
-    
-
-    while actor.is_running(|| crawler_tx.mark_closed()) {
-        // await_for_all!(
-        //     actor.wait_vacant(&mut crawler_to_ai_model_tx, 1),
-        //     actor.wait_vacant(&mut crawler_tx, 1)
-        // );
-        
-
-        match metas_iter.next() {
-            Some(m) => {
-                // Skip directories — only send files to AI model
-               // m.meta_print();
-                if !m.is_file {
-                    // Still send to DB for record keeping — wait and retry until sent
-                    loop {
-                        actor.wait_vacant(&mut crawler_tx, 1).await;
-                        match actor.try_send(&mut crawler_tx, m.clone()) {
-                            SendOutcome::Success => break,
-                            SendOutcome::Blocked(_) => continue,
-                            other => {
-                                
-                            }
-                        }
-                    }
-                    continue;
-                }
-
-                // Skip target/ build directory — no point analysing build artifacts
-                if m.abs_path.components().any(|c| c.as_os_str() == "target") {
-                    continue;
-                }
-
-                // Send to AI model — wait for room and retry until sent (don't drop or spam log)
-                loop {
-                    actor.wait_vacant(&mut crawler_to_ai_model_tx, 1).await;
-                    match actor.try_send(&mut crawler_to_ai_model_tx, m.clone()) {
-                        SendOutcome::Success => break,
-                        SendOutcome::Blocked(_) => continue,
-                        other => {
-                            
-                            break;
-                        }
-                    }
-                }
+    // Files are read and hashed one at a time, only once both channels have room,
+    // so a slow AI model throttles the walk instead of the walk piling up in memory.
+    let mut walker = FileWalker::new(load_scan_roots(&directory), hashing)
+        .with_global_excludes(exclude::load_global_patterns(directory.scan_list_dir()))
+        .with_index(index);
+
+    while actor.is_running(|| crawler_tx.mark_closed() && crawler_to_ai_model_tx.mark_closed()) {
+        await_for_all!(
+            actor.wait_vacant(&mut crawler_tx, 1),
+            actor.wait_vacant(&mut crawler_to_ai_model_tx, 1)
+        );
+
+        let Some(walked) = walker.next() else {
+            // Anything indexed under a root that the walk did not reach again is gone
+            for path in walker.vanished() {
+                actor.send_async(&mut crawler_tx, ScanEvent::Vanished(path), SendSaturation::AwaitForRoom).await;
+            }
+            // Walk finished — close our side so downstream actors can drain and finish
+            crawler_tx.mark_closed();
+            crawler_to_ai_model_tx.mark_closed();
+            break;
+        };
 
-                // Send to DB — wait and retry until sent
-                loop {
-                    actor.wait_vacant(&mut crawler_tx, 1).await;
-                    match actor.try_send(&mut crawler_tx, m.clone()) {
-                        SendOutcome::Success => break,
-                        SendOutcome::Blocked(_) => continue,
-                        other => {
-                          
-                            break;
-                        }
-                    }
+        match walked {
+            Walked::Changed(m) => {
+                // The DB hears of the file first, so it is indexed before anyone can ask to delete it.
+                // Directories still go to the DB for record keeping
+                actor.send_async(&mut crawler_tx, ScanEvent::Upsert(m.clone()), SendSaturation::AwaitForRoom).await;
+                if wants_review(&m) && !protections.is_protected(&m) {
+                    actor.send_async(&mut crawler_to_ai_model_tx, m, SendSaturation::AwaitForRoom).await;
                 }
             }
-            None => {
-                // All files sent — shut down cleanly
-                //actor.request_shutdown().await;
-                break;
+            // The DB already holds this record. Only a current keep verdict is
+            // left alone; the AI gets everything else and reuses whatever verdict
+            // is still current, so flagged files show up for review again.
+            Walked::Unchanged(entry) => {
+                if !is_settled(&entry, model.as_deref())
+                    && wants_review(&entry.meta)
+                    && !protections.is_protected(&entry.meta) {
+                    actor.send_async(&mut crawler_to_ai_model_tx, entry.meta, SendSaturation::AwaitForRoom).await;
+                }
             }
         }
-        actor.wait_periodic(std::time::Duration::from_secs(10)).await;
+
+        // Earlier files that turned out to share size+hash with this one now carry
+        // a full hash; the DB needs the update, the AI has already seen them
+        while let Some(updated) = walker.take_revisit() {
+            actor.send_async(&mut crawler_tx, ScanEvent::Upsert(updated), SendSaturation::AwaitForRoom).await;
+        }
     }
 
     Ok(())
 }
 
+/// Whether an unchanged file can be skipped: the verdict it holds is a keep
+/// that `model` gave under the current prompt. A file never asked about, or
+/// asked under another prompt or model, is not.
+fn is_settled(entry: &IndexEntry, model: Option<&str>) -> bool {
+    match (&entry.verdict, model) {
+        (Some(verdict), Some(model)) => !verdict.is_delete() && ai_model::is_current(verdict, model),
+        _ => false,
+    }
+}
+
+/// Whether the AI should look at this entry. Build output and other unwanted
+/// trees are excluded from the walk itself, so this only filters directories.
+fn wants_review(meta: &FileMeta) -> bool {
+    meta.is_file
+}
+
+/// Full-content SHA-256 of a file, hex encoded.
 pub fn get_file_hash(file_name: PathBuf) -> Result<String, Box<dyn Error>> {
-    let mut file = std::fs::File::open(file_name)?;
-    let mut buffer = [0u8; 1024];
-    let n = file.read(&mut buffer)?;
-    let mut hasher = Sha256::new();
-    hasher.update(&buffer[..n]);
-    let result = hasher.finalize();
-    let mut out: [u8; 32] = result.into();
-    out.copy_from_slice(&result);
-    Ok(hex::encode(out))
+    Ok(hashing::full_hash(&file_name)?)
+}
+
+/// One walked entry, compared against the index from the previous scan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Walked {
+    /// Not indexed, or its size, mtime or inode differ: freshly hashed.
+    Changed(FileMeta),
+    /// Same size, mtime and inode as the indexed record, which is reused unhashed.
+    Unchanged(IndexEntry),
+}
+
+impl Walked {
+    pub fn meta(&self) -> &FileMeta {
+        match self {
+            Walked::Changed(m) => m,
+            Walked::Unchanged(e) => &e.meta,
+        }
+    }
+
+    pub fn into_meta(self) -> FileMeta {
+        match self {
+            Walked::Changed(m) => m,
+            Walked::Unchanged(e) => e.meta,
+        }
+    }
+}
+
+/// Lazily walks each scan root in turn, yielding one entry per path.
+/// Nothing is read or hashed until the next item is requested.
+///
+/// `hash` is computed with the configured strategy; a full hash is only added
+/// when another file of the same size already produced the same hash.
+/// With an index attached, files unchanged since the last scan are not read at all.
+pub(crate) struct FileWalker {
+    roots:        std::vec::IntoIter<ScanRoot>,
+    current:      Option<Box<dyn Iterator<Item = walkdir::Result<DirEntry>> + Send>>,
+    hash_config:  HashConfig,
+    candidates:   CandidateIndex,
+    revisits:     VecDeque<FileMeta>,
+    index:        Option<FileIndex>,
+    // gitignore-style patterns applied under every root
+    global_excludes: Vec<String>,
+    // absolute roots walked and paths seen so far, to work out what vanished
+    walked_roots: Vec<PathBuf>,
+    seen:         HashSet<PathBuf>,
 }
 
-pub fn visit_dir(
-    dir: &Path,
-    state: &StateGuard<'_, CrawlerState>,
-) -> Result<Vec<FileMeta>, Box<dyn Error>> {
+impl FileWalker {
+    pub fn new(roots: Vec<ScanRoot>, hash_config: HashConfig) -> Self {
+        Self {
+            roots: roots.into_iter(),
+            current: None,
+            hash_config,
+            candidates: CandidateIndex::default(),
+            revisits: VecDeque::new(),
+            index: None,
+            global_excludes: Vec::new(),
+            walked_roots: Vec::new(),
+            seen: HashSet::new(),
+        }
+    }
 
-    let mut metas: Vec<FileMeta> = Vec::new();
+    /// Gitignore-style patterns excluded (or, with `!`, re-included) under every root.
+    pub fn with_global_excludes(mut self, patterns: Vec<String>) -> Self {
+        self.global_excludes = patterns;
+        self
+    }
 
-    for entry_res in WalkDir::new(dir) {
-        let entry = entry_res?;
-        let rel_path: &Path = entry.path();
-        let abs_path: PathBuf = std::path::absolute(&rel_path)?;
-        let rel_path: PathBuf = rel_path.to_path_buf();
-        let name_os: &OsStr = entry.file_name();
+    /// Compares each entry against the records of a previous scan.
+    pub fn with_index(mut self, index: FileIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
 
-        let file_name: String = match name_os.to_str() {
-            Some(s) => s.to_owned(),
-            None => name_os.to_string_lossy().into_owned(),
+    /// Indexed paths under the walked roots that this walk did not see.
+    /// Only meaningful once the walk is exhausted.
+    pub fn vanished(&self) -> Vec<PathBuf> {
+        let Some(index) = &self.index else {
+            return Vec::new();
         };
+        self.walked_roots
+            .iter()
+            .flat_map(|root| index.entries_under(root))
+            .map(|e| e.meta.abs_path)
+            .filter(|p| !self.seen.contains(p))
+            .collect()
+    }
 
-        match entry.metadata() {
-            Ok(md) => {
-                let is_file:  bool = md.is_file();
-                let size:     u64  = md.len();
-                //windows and unix timestamps are different so you must convert the timestamps to seconds differently
-                let modified: i64 = {
-                    let raw = FileTime::from_last_modification_time(&md).seconds();
-                    #[cfg(target_os = "windows")]
-                    let raw = raw - WINDOWS_TO_UNIX_EPOCH_OFFSET;
-                    raw
-                };
-                //windows and unix timestamps are different so you must convert the timestamps to seconds differently
-                let created: i64 = FileTime::from_creation_time(&md)
-                    .map(|ft| {
-                        let raw = ft.seconds();
-                        #[cfg(target_os = "windows")]
-                        let raw = raw - 11_644_473_600;
-                        raw
-                    })
-                    .unwrap_or(modified);
-                let readonly: bool = md.permissions().readonly();
-                let mut hash: String = String::new();
-
-                if is_file {
-                    match get_file_hash(abs_path.clone()) {
-                        Ok(h) => hash = h,
-                        Err(_) => continue,
-                    }
+    /// The indexed record for this path, if the file is still the same version
+    /// and was hashed the way we would hash it now.
+    fn unchanged_entry(&self, stat: &FileMeta) -> Option<IndexEntry> {
+        let index = self.index.as_ref()?;
+        let mut prev = index.get(&stat.abs_path).ok()??;
+        if !file_index::same_file_version(&prev.meta, stat) || prev.meta.hash_strategy != stat.hash_strategy {
+            return None;
+        }
+        prev.meta.rel_path = stat.rel_path.clone();
+        Some(prev)
+    }
+
+    fn classify(&mut self, stat: FileMeta) -> Option<Walked> {
+        if self.index.is_some() {
+            self.seen.insert(stat.abs_path.clone());
+        }
+
+        if let Some(mut prev) = self.unchanged_entry(&stat) {
+            let had_full_hash = prev.meta.full_hash.is_some();
+            self.resolve_candidates(&mut prev.meta);
+            // a collision with a newer file filled in the full hash, which the DB should store
+            if !had_full_hash && prev.meta.full_hash.is_some() {
+                return Some(Walked::Changed(prev.meta));
+            }
+            return Some(Walked::Unchanged(prev));
+        }
+
+        let mut meta = stat;
+        hash_meta(&mut meta, &self.hash_config)?;
+        self.resolve_candidates(&mut meta);
+        Some(Walked::Changed(meta))
+    }
+
+    /// Previously yielded files whose full hash was filled in after a collision.
+    pub fn take_revisit(&mut self) -> Option<FileMeta> {
+        self.revisits.pop_front()
+    }
+
+    fn resolve_candidates(&mut self, meta: &mut FileMeta) {
+        if !meta.is_file {
+            return;
+        }
+        if let Candidate::Collides { first } = self.candidates.observe(meta.size, &meta.hash, &meta.rel_path) {
+            if meta.full_hash.is_none() {
+                meta.full_hash = hashing::full_hash(&meta.abs_path).ok();
+            }
+            let earlier = first.and_then(|p| {
+                let md = std::fs::metadata(&p).ok()?;
+                file_meta_from_path(&p, &md, &self.hash_config)
+            });
+            if let Some(mut earlier) = earlier && earlier.full_hash.is_none() {
+                earlier.full_hash = hashing::full_hash(&earlier.abs_path).ok();
+                self.revisits.push_back(earlier);
+            }
+        }
+    }
+
+    fn walk_root(root: ScanRoot, global_excludes: &[String]) -> Box<dyn Iterator<Item = walkdir::Result<DirEntry>> + Send> {
+        let mut walker = WalkDir::new(&root.path).follow_links(root.follow_symlinks);
+        if let Some(depth) = root.max_depth {
+            walker = walker.max_depth(depth);
+        }
+        // filter_entry prunes excluded directories so their subtrees are never read
+        let mut rules = ExcludeRules::new(&root, global_excludes);
+        Box::new(walker.into_iter().filter_entry(move |e| !rules.is_excluded(e.path(), e.file_type().is_dir())))
+    }
+}
+
+impl Iterator for FileWalker {
+    type Item = Walked;
+
+    fn next(&mut self) -> Option<Walked> {
+        loop {
+            if self.current.is_none() {
+                let root = self.roots.next()?;
+                if let Ok(abs) = std::path::absolute(&root.path) {
+                    self.walked_roots.push(abs);
                 }
+                self.current = Some(Self::walk_root(root, &self.global_excludes));
+            }
 
-                metas.push(FileMeta {
-                    rel_path,
-                    abs_path,
-                    file_name,
-                    hash,
-                    is_file,
-                    size,
-                    modified,
-                    created,
-                    readonly,
-                });
+            let entries = self.current.as_mut()?;
+            match entries.next() {
+                Some(Ok(entry)) => {
+                    let stat = entry.metadata().ok()
+                        .and_then(|md| stat_meta(entry.path(), &md, self.hash_config.strategy));
+                    if let Some(walked) = stat.and_then(|s| self.classify(s)) {
+                        return Some(walked);
+                    }
+                }
+                // unreadable entries or symlink loops only lose that entry, not the root
+                Some(Err(e)) => eprintln!("  ⚠ Skipping entry: {}", e),
+                None => self.current = None,
             }
-            Err(_) => {}
         }
     }
-    Ok(metas)
+}
+
+/// Builds the metadata record for one walked path, hashing it if it is a file.
+/// Returns `None` when the file cannot be read.
+pub(crate) fn file_meta_from_path(path: &Path, md: &Metadata, hash_config: &HashConfig) -> Option<FileMeta> {
+    let mut meta = stat_meta(path, md, hash_config.strategy)?;
+    hash_meta(&mut meta, hash_config)?;
+    Some(meta)
+}
+
+/// Fills in `hash` (and `full_hash` when the strategy read the whole file).
+/// Directories are left unhashed. Returns `None` when the file cannot be read.
+fn hash_meta(meta: &mut FileMeta, hash_config: &HashConfig) -> Option<()> {
+    if meta.is_file {
+        let h = hashing::hash_file(&meta.abs_path, meta.size, hash_config).ok()?;
+        meta.full_hash = h.complete.then(|| h.hash.clone());
+        meta.hash = h.hash;
+    }
+    meta.hash_strategy = hash_config.strategy;
+    Some(())
+}
+
+/// Metadata record for one walked path without reading its contents.
+pub(crate) fn stat_meta(path: &Path, md: &Metadata, strategy: HashStrategy) -> Option<FileMeta> {
+    let rel_path: PathBuf = path.to_path_buf();
+    let abs_path: PathBuf = std::path::absolute(&rel_path).ok()?;
+    let file_name: String = path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| rel_path.to_string_lossy().into_owned());
+
+    let is_file:  bool = md.is_file();
+    let size:     u64  = md.len();
+    //windows and unix timestamps are different so you must convert the timestamps to seconds differently
+    let modified: i64 = {
+        let raw = FileTime::from_last_modification_time(md).seconds();
+        #[cfg(target_os = "windows")]
+        let raw = raw - WINDOWS_TO_UNIX_EPOCH_OFFSET;
+        raw
+    };
+    //windows and unix timestamps are different so you must convert the timestamps to seconds differently
+    let created: i64 = FileTime::from_creation_time(md)
+        .map(|ft| {
+            let raw = ft.seconds();
+            #[cfg(target_os = "windows")]
+            let raw = raw - WINDOWS_TO_UNIX_EPOCH_OFFSET;
+            raw
+        })
+        .unwrap_or(modified);
+    let readonly: bool = md.permissions().readonly();
+    #[cfg(unix)]
+    let inode: u64 = std::os::unix::fs::MetadataExt::ino(md);
+    #[cfg(not(unix))]
+    let inode: u64 = 0;
+
+    Some(FileMeta {
+        rel_path,
+        abs_path,
+        file_name,
+        hash: String::new(),
+        is_file,
+        size,
+        modified,
+        created,
+        readonly,
+        hash_strategy: strategy,
+        full_hash: None,
+        inode,
+    })
 }
 
 #[cfg(test)]
@@ -321,6 +532,9 @@ mod tests {
             modified,
             created: 0,
             readonly,
+            hash_strategy: HashStrategy::default(),
+            full_hash: None,
+            inode: 0,
         }
     }
 
@@ -346,6 +560,9 @@ mod tests {
             modified: 1700000000,
             created: 1600000000,
             readonly: true,
+            hash_strategy: HashStrategy::default(),
+            full_hash: None,
+            inode: 0,
         };
         let bytes = meta.to_bytes().unwrap();
         let restored = FileMeta::from_bytes(&bytes).unwrap();
@@ -375,6 +592,9 @@ mod tests {
             modified: 0,
             created: 0,
             readonly: false,
+            hash_strategy: HashStrategy::default(),
+            full_hash: None,
+            inode: 0,
         };
         let bytes = meta.to_bytes().unwrap();
         let restored = FileMeta::from_bytes(&bytes).unwrap();
@@ -468,4 +688,312 @@ mod tests {
 
         fs::remove_file(path).ok();
     }
-}
\ No newline at end of file
+
+    // ── FileWalker ────────────────────────────────────────────────────────────
+
+    fn make_tree(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("cruft_walker_{}", name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+        fs::write(root.join("top.txt"), b"top").unwrap();
+        fs::write(root.join("sub/mid.txt"), b"mid").unwrap();
+        fs::write(root.join("sub/deeper/low.txt"), b"low").unwrap();
+        fs::write(root.join("node_modules/dep.js"), b"dep").unwrap();
+        root
+    }
+
+    fn walk(roots: Vec<ScanRoot>) -> FileWalker {
+        FileWalker::new(roots, HashConfig::default())
+    }
+
+    fn file_names(walker: FileWalker) -> Vec<String> {
+        let mut names: Vec<String> = walker.map(Walked::into_meta).filter(|m| m.is_file).map(|m| m.file_name).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_walker_yields_every_file_with_hash() {
+        let root = make_tree("all");
+        let metas: Vec<FileMeta> = walk(vec![ScanRoot::new(root)]).map(Walked::into_meta).collect();
+
+        let files: Vec<&FileMeta> = metas.iter().filter(|m| m.is_file).collect();
+        assert_eq!(files.len(), 4);
+        assert!(files.iter().all(|m| m.hash.len() == 64));
+        // directories are yielded too, without a hash
+        assert!(metas.iter().any(|m| !m.is_file && m.hash.is_empty()));
+    }
+
+    #[test]
+    fn test_walker_is_lazy() {
+        let root = make_tree("lazy");
+        let mut walker = walk(vec![ScanRoot::new(root.clone())]);
+
+        // the root directory itself comes first, before anything below it is read
+        let first = walker.next().expect("root entry").into_meta();
+        assert_eq!(first.rel_path, root);
+        assert!(!first.is_file);
+    }
+
+    #[test]
+    fn test_walker_chains_multiple_roots() {
+        let a = make_tree("chain_a");
+        let b = make_tree("chain_b");
+        let walker = walk(vec![ScanRoot::new(a), ScanRoot::new(b)]);
+        assert_eq!(file_names(walker).len(), 8);
+    }
+
+    #[test]
+    fn test_walker_honors_max_depth() {
+        let root = make_tree("depth");
+        let mut scan_root = ScanRoot::new(root);
+        scan_root.max_depth = Some(1);
+        assert_eq!(file_names(walk(vec![scan_root])), vec!["top.txt"]);
+    }
+
+    #[test]
+    fn test_walker_prunes_excluded_dirs() {
+        let root = make_tree("exclude");
+        let mut scan_root = ScanRoot::new(root);
+        scan_root.excludes = vec!["node_modules".to_string()];
+
+        let metas: Vec<FileMeta> = walk(vec![scan_root]).map(Walked::into_meta).collect();
+        assert!(metas.iter().all(|m| m.file_name != "node_modules" && m.file_name != "dep.js"));
+    }
+
+    #[test]
+    fn test_walker_honors_gitignore_and_includes() {
+        let root = make_tree("gitignore");
+        fs::write(root.join("sub/.gitignore"), "*.txt\n").unwrap();
+        let mut scan_root = ScanRoot::new(root);
+        scan_root.includes = vec!["low.txt".to_string()];
+
+        // mid.txt is ignored by sub/.gitignore; low.txt is re-included by the root option
+        assert_eq!(file_names(walk(vec![scan_root])), vec![".gitignore", "dep.js", "low.txt", "top.txt"]);
+    }
+
+    #[test]
+    fn test_walker_applies_global_excludes() {
+        let root = make_tree("global_excludes");
+        let walker = walk(vec![ScanRoot::new(root)]).with_global_excludes(vec!["sub/".to_string()]);
+        assert_eq!(file_names(walker), vec!["dep.js", "top.txt"]);
+    }
+
+    #[test]
+    fn test_walker_keeps_user_folder_named_target() {
+        let root = make_tree("target_folder");
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("target/notes.txt"), b"mine").unwrap();
+
+        let metas: Vec<FileMeta> = walk(vec![ScanRoot::new(root)]).map(Walked::into_meta).collect();
+        let notes = metas.iter().find(|m| m.file_name == "notes.txt").expect("target/ was walked");
+        assert!(wants_review(notes));
+    }
+
+    #[test]
+    fn test_walker_no_roots_yields_nothing() {
+        assert_eq!(walk(Vec::new()).count(), 0);
+    }
+
+    #[test]
+    fn test_scan_list_is_read_from_configured_dir() {
+        let root = make_tree("scan_list_root");
+        let lists = std::env::temp_dir().join("cruft_walker_scan_list_dir");
+        let _ = fs::remove_dir_all(&lists);
+        fs::create_dir_all(&lists).unwrap();
+        let mut directory = DirectoryConfig { path: PathBuf::new(), scan_list_dir: lists.clone() };
+        assert!(load_scan_roots(&directory).is_empty(), "nothing is read from the working directory");
+
+        fs::write(lists.join(scan_roots::SCAN_FILE), format!("{}\n", root.display())).unwrap();
+        let roots: Vec<PathBuf> = load_scan_roots(&directory).into_iter().map(|r| r.path).collect();
+        assert_eq!(roots, vec![root.clone()]);
+
+        directory.path = root.join("sub");
+        let roots: Vec<PathBuf> = load_scan_roots(&directory).into_iter().map(|r| r.path).collect();
+        assert_eq!(roots, vec![root.clone(), root.join("sub")]);
+    }
+
+    #[test]
+    fn test_walker_adds_full_hash_only_on_collision() {
+        let root = std::env::temp_dir().join("cruft_walker_collision");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        // same size and same head/middle/tail samples, different bytes elsewhere
+        let mut a = vec![b'x'; 100];
+        let mut b = a.clone();
+        a[30] = b'a';
+        b[30] = b'b';
+        fs::write(root.join("a.bin"), &a).unwrap();
+        fs::write(root.join("b.bin"), &b).unwrap();
+        fs::write(root.join("other.bin"), vec![b'y'; 100]).unwrap();
+
+        let config = HashConfig { strategy: HashStrategy::Sampled, quick_bytes: 8, sample_bytes: 4 };
+        let mut walker = FileWalker::new(vec![ScanRoot::new(root)], config);
+        let mut metas: Vec<FileMeta> = Vec::new();
+        while let Some(m) = walker.next() {
+            metas.push(m.into_meta());
+            while let Some(r) = walker.take_revisit() {
+                metas.push(r);
+            }
+        }
+
+        let with_full: Vec<&FileMeta> = metas.iter().filter(|m| m.full_hash.is_some()).collect();
+        // the later of a/b on yield, plus the earlier one as a revisit
+        assert_eq!(with_full.len(), 2);
+        assert_ne!(with_full[0].full_hash, with_full[1].full_hash);
+        assert_eq!(with_full[0].hash, with_full[1].hash);
+        let other = metas.iter().find(|m| m.file_name == "other.bin").unwrap();
+        assert_eq!(other.full_hash, None);
+        assert_eq!(other.hash_strategy, HashStrategy::Sampled);
+    }
+
+    // ── FileWalker with index (incremental rescans) ───────────────────────────
+
+    use crate::actor::ai_model::VerdictRecord;
+
+    fn open_temp_index(name: &str) -> FileIndex {
+        let path = std::env::temp_dir().join(format!("cruft_walker_sled_{}", name));
+        let _ = fs::remove_dir_all(&path);
+        FileIndex::open(&sled::open(&path).unwrap()).unwrap()
+    }
+
+    /// Walks `root` against `index` and stores every changed record, the way
+    /// the DB manager would. Returns the walked entries and the vanished paths.
+    fn rescan(root: &Path, index: &FileIndex) -> (Vec<Walked>, Vec<PathBuf>) {
+        let mut walker = walk(vec![ScanRoot::new(root.to_path_buf())]).with_index(index.clone());
+        let walked: Vec<Walked> = walker.by_ref().collect();
+        for w in &walked {
+            if let Walked::Changed(m) = w {
+                index.upsert_meta(m.clone(), 1).unwrap();
+            }
+        }
+        (walked, walker.vanished())
+    }
+
+    fn changed_names(walked: &[Walked]) -> Vec<String> {
+        let mut names: Vec<String> = walked.iter()
+            .filter_map(|w| match w {
+                Walked::Changed(m) if m.is_file => Some(m.file_name.clone()),
+                _ => None,
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_first_scan_reports_everything_changed() {
+        let root = make_tree("index_first");
+        let index = open_temp_index("first");
+        let (walked, vanished) = rescan(&root, &index);
+        assert_eq!(changed_names(&walked).len(), 4);
+        assert!(vanished.is_empty());
+    }
+
+    #[test]
+    fn test_rescan_skips_unchanged_files() {
+        let root = make_tree("index_unchanged");
+        let index = open_temp_index("unchanged");
+        rescan(&root, &index);
+
+        let (walked, vanished) = rescan(&root, &index);
+        assert!(changed_names(&walked).is_empty());
+        assert!(walked.iter().all(|w| matches!(w, Walked::Unchanged(_))));
+        assert!(vanished.is_empty());
+    }
+
+    #[test]
+    fn test_rescan_reports_modified_file() {
+        let root = make_tree("index_modified");
+        let index = open_temp_index("modified");
+        rescan(&root, &index);
+
+        fs::write(root.join("top.txt"), b"top, but longer now").unwrap();
+        let (walked, _) = rescan(&root, &index);
+        assert_eq!(changed_names(&walked), vec!["top.txt"]);
+    }
+
+    #[test]
+    fn test_rescan_reports_vanished_file() {
+        let root = make_tree("index_vanished");
+        let index = open_temp_index("vanished");
+        rescan(&root, &index);
+
+        fs::remove_file(root.join("sub/mid.txt")).unwrap();
+        let (_, vanished) = rescan(&root, &index);
+        assert_eq!(vanished.len(), 1);
+        assert!(vanished[0].ends_with("sub/mid.txt"));
+    }
+
+    #[test]
+    fn test_unchanged_entry_carries_previous_verdict() {
+        let root = make_tree("index_verdict");
+        let index = open_temp_index("verdict");
+        rescan(&root, &index);
+
+        let top = std::path::absolute(root.join("top.txt")).unwrap();
+        let mut entry = index.get(&top).unwrap().unwrap();
+        entry.verdict = Some(VerdictRecord::from_output("Decision: delete", "test.gguf"));
+        index.put(&entry).unwrap();
+
+        let (walked, _) = rescan(&root, &index);
+        let top_entry = walked.iter().find_map(|w| match w {
+            Walked::Unchanged(e) if e.meta.abs_path == top => Some(e),
+            _ => None,
+        });
+        assert!(top_entry.unwrap().verdict.as_ref().unwrap().is_delete());
+    }
+
+    #[test]
+    fn test_only_a_current_keep_verdict_is_settled() {
+        let entry = |verdict: Option<VerdictRecord>| IndexEntry::new(make_meta("a.txt", 1, 1, false), verdict);
+        let keep = VerdictRecord::from_output("Decision: keep", "m.gguf");
+
+        assert!(is_settled(&entry(Some(keep.clone())), Some("m.gguf")));
+        assert!(!is_settled(&entry(None), Some("m.gguf")), "scanned but never asked about");
+        assert!(!is_settled(&entry(Some(VerdictRecord::from_output("Decision: delete", "m.gguf"))), Some("m.gguf")));
+        assert!(!is_settled(&entry(Some(keep.clone())), Some("other.gguf")));
+        assert!(!is_settled(&entry(Some(VerdictRecord { prompt_version: 0, ..keep.clone() })), Some("m.gguf")));
+        assert!(!is_settled(&entry(Some(keep)), None), "no model to compare with");
+    }
+
+    #[test]
+    fn test_changed_hash_strategy_forces_rehash() {
+        let root = make_tree("index_strategy");
+        let index = open_temp_index("strategy");
+        rescan(&root, &index);
+
+        let config = HashConfig { strategy: HashStrategy::Full, ..HashConfig::default() };
+        let walker = FileWalker::new(vec![ScanRoot::new(root)], config).with_index(index);
+        let walked: Vec<Walked> = walker.collect();
+        assert_eq!(changed_names(&walked).len(), 4);
+    }
+
+    #[test]
+    fn test_records_without_a_strategy_were_quick_hashed() {
+        let mut json = serde_json::to_value(make_meta("old.txt", 1, 0, false)).unwrap();
+        json.as_object_mut().unwrap().remove("hash_strategy");
+        let meta: FileMeta = serde_json::from_value(json).unwrap();
+        assert_eq!(meta.hash_strategy, HashStrategy::Quick);
+    }
+
+    #[test]
+    fn test_stat_meta_records_inode() {
+        let root = make_tree("inode");
+        let path = root.join("top.txt");
+        let meta = stat_meta(&path, &fs::metadata(&path).unwrap(), HashStrategy::Sampled).unwrap();
+        #[cfg(unix)]
+        assert_ne!(meta.inode, 0);
+        assert!(meta.hash.is_empty());
+    }
+
+    #[test]
+    fn test_content_hash_uses_full_hash_only() {
+        let mut meta = make_meta("x.txt", 1, 0, false);
+        meta.hash = "quick".to_string();
+        assert_eq!(meta.content_hash(), None);
+        meta.full_hash = Some("full".to_string());
+        assert_eq!(meta.content_hash(), Some("full"));
+    }
+}
@@ -2,57 +2,259 @@
 
 use steady_state::*;
 use std::error::Error;
-use crate::actor::crawler::FileMeta;
-use std::path::PathBuf;
+use crate::actor::crawler::{FileMeta, ScanEvent};
+use crate::duplicates::{DuplicateFinder, DuplicateGroup, KeeperPolicy};
+use crate::file_index::{FileIndex, IndexEntry};
+use crate::journal::{Journal, JournalEntry, Outcome, UserAction};
+use crate::protection::{Protection, Protections};
+use crate::quarantine::{self, Quarantine, QuarantineRecord};
+use crate::trash::{Trash, TrashedFile};
+use crate::verify::{self, Refusal};
+use crate::actor::user_interface::UiRequest;
+use crate::config::DatabaseConfig;
+use crate::plan::DeletionPlan;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::fs;
 
 
-// size of batch we want (# of FileMeta Structs before writing to DB)
-const BATCH_SIZE: usize = 1;
+// size of batch we want (# of FileMeta Structs before writing to DB), unless
+// `[database] batch-size` says otherwise
+pub(crate) const BATCH_SIZE: usize = 1;
+
+/// What the DB actor tells the UI.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DbReport {
+    /// Identical files found once the crawl finished.
+    Duplicates(DuplicateGroup),
+    /// A confirmed deletion was carried out; the file now sits at `destination`.
+    Deleted { path: PathBuf, destination: PathBuf },
+    /// A deletion the user confirmed was not carried out.
+    DeleteRefused { path: PathBuf, reason: String },
+    /// An undone deletion: the file is back where it was.
+    Restored(PathBuf),
+    /// An undone deletion that could not be put back.
+    RestoreFailed { path: PathBuf, reason: String },
+    /// Every never-delete mark, as the UI asked for.
+    Marks(Vec<Protection>),
+    /// A dry run's deletion plan was written once every verdict was in.
+    PlanWritten { path: PathBuf, files: usize, reclaimable: u64 },
+    /// The crawl and the model are done and every report above has been sent.
+    /// Later deletions are still answered.
+    Finished,
+}
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(actor: SteadyActorShadow, 
-                 crawler_to_db_rx: SteadyRx<FileMeta>,
-                 ui_to_db_rx: SteadyRx<PathBuf> ) -> Result<(),Box<dyn Error>> {
-
-    let actor = actor.into_spotlight([&crawler_to_db_rx, &ui_to_db_rx], []);
-	internal_behavior(actor, crawler_to_db_rx, ui_to_db_rx).await
+                 crawler_to_db_rx: SteadyRx<ScanEvent>,
+                 ai_model_to_db_rx: SteadyRx<IndexEntry>,
+                 ui_to_db_rx: SteadyRx<UiRequest>,
+                 db_to_ui_tx: SteadyTx<DbReport>,
+                 index: FileIndex,
+                 protections: Protections,
+                 quarantine: Quarantine,
+                 trash: Option<Trash>,
+                 journal: Journal,
+                 config: DatabaseConfig,
+                 keeper: KeeperPolicy,
+                 plan_file: Option<PathBuf>) -> Result<(),Box<dyn Error>> {
+
+    let actor = actor.into_spotlight([&crawler_to_db_rx, &ai_model_to_db_rx, &ui_to_db_rx], [&db_to_ui_tx]);
+	internal_behavior(actor, crawler_to_db_rx, ai_model_to_db_rx, ui_to_db_rx, db_to_ui_tx, index, protections, quarantine, trash, journal, config, keeper, plan_file).await
 }
 
 
+#[allow(clippy::too_many_arguments)]
 async fn internal_behavior<A: SteadyActor>(mut actor: A, 
-                                                crawler_to_db_rx: SteadyRx<FileMeta>, 
-                                                ui_to_db_rx: SteadyRx<PathBuf>) -> Result<(),Box<dyn Error>> {
+                                                crawler_to_db_rx: SteadyRx<ScanEvent>, 
+                                                ai_model_to_db_rx: SteadyRx<IndexEntry>,
+                                                ui_to_db_rx: SteadyRx<UiRequest>,
+                                                db_to_ui_tx: SteadyTx<DbReport>,
+                                                index: FileIndex,
+                                                protections: Protections,
+                                                quarantine: Quarantine,
+                                                trash: Option<Trash>,
+                                                journal: Journal,
+                                                config: DatabaseConfig,
+                                                keeper: KeeperPolicy,
+                                                plan_file: Option<PathBuf>) -> Result<(),Box<dyn Error>> {
 
     let mut crawler_to_db_rx = crawler_to_db_rx.lock().await;
 
+    let mut ai_model_to_db_rx = ai_model_to_db_rx.lock().await;
+
     let mut ui_to_db_rx = ui_to_db_rx.lock().await;
 
-    // TODO: example code that I need to change
-    let mut db: sled::Db = sled::open("./src/db").unwrap();
-    let ctr: i32 = 0;
+    let mut db_to_ui_tx = db_to_ui_tx.lock().await;
+
+    // Every record written during this run is tagged with the scan that wrote it
+    let scan_id = index.begin_scan()?;
+
+    // Files deleted on earlier runs are only recoverable for the retention period
+    match purge_quarantine(&quarantine, &journal, quarantine::unix_now()) {
+        Ok(purged) if !purged.is_empty() => {
+            eprintln!("Purged {} file(s) quarantined more than {} day(s) ago",
+                      purged.len(), quarantine.retention().as_secs() / 86400);
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to purge quarantine: {}", e),
+    }
 
-    while actor.is_running(|| crawler_to_db_rx.is_closed_and_empty()) {
-        // 1) Wait until there is at least one FileMeta from the crawler
-        actor.wait_avail(&mut crawler_to_db_rx, BATCH_SIZE).await;
+    // Duplicates can only be judged once every copy has been seen, so groups
+    // are sent to the UI once the crawl finishes
+    let mut duplicates_sent = false;
+
+    // Quarantine record of each file deleted during this run, for undo. Older
+    // records for the same path must never be restored in its place.
+    let mut undoable: HashMap<PathBuf, u64> = HashMap::new();
+
+    // Never-delete marks added during this run, for undo. Undoing a review
+    // decision must not lift a mark that was there before it.
+    let mut marked: HashSet<Protection> = HashSet::new();
+
+    // A dry run never deletes; what would be deleted is written to `plan_file`
+    // once the crawl and the model are both done
+    let mut plan_written = false;
+
+    // Sent once, after the duplicates and the plan, so a headless run knows when to stop
+    let mut finished_sent = false;
+
+    while actor.is_running(|| crawler_to_db_rx.is_closed_and_empty()
+                               && ai_model_to_db_rx.is_closed_and_empty()
+                               && db_to_ui_tx.mark_closed()) {
+        // 1) Wait for a scan event, a verdict or a deletion from the UI.
+        //    A closed channel never blocks, so only wait on the open ones
+        match (crawler_to_db_rx.is_closed_and_empty(), ai_model_to_db_rx.is_closed_and_empty()) {
+            (false, false) => {
+                await_for_any!(
+                    actor.wait_avail(&mut crawler_to_db_rx, config.batch_size),
+                    actor.wait_avail(&mut ai_model_to_db_rx, 1),
+                    actor.wait_avail(&mut ui_to_db_rx, 1)
+                );
+            }
+            (false, true) => {
+                await_for_any!(
+                    actor.wait_avail(&mut crawler_to_db_rx, config.batch_size),
+                    actor.wait_avail(&mut ui_to_db_rx, 1)
+                );
+            }
+            (true, false) => {
+                await_for_any!(
+                    actor.wait_avail(&mut ai_model_to_db_rx, 1),
+                    actor.wait_avail(&mut ui_to_db_rx, 1)
+                );
+            }
+            (true, true) => {
+                actor.wait_avail(&mut ui_to_db_rx, 1).await;
+            }
+        }
     
         
-        // Handle any confirmed user deletions from UI
-        if let Some(path) = actor.try_take(&mut ui_to_db_rx) {
-           // println!("User confirmed deletion: {:?}", path);
-            match fs::remove_file(&path) {
-                Ok(_) => (),
-                //Ok(_) => println!("Deleted from disk: {:?}", path),
-                Err(e) => eprintln!("Failed to delete {:?}: {}", path, e),
+        // Handle any confirmed user deletions and never-delete marks from UI
+        match actor.try_take(&mut ui_to_db_rx) {
+            Some(UiRequest::Delete(path)) if plan_file.is_some() => {
+                let report = DbReport::DeleteRefused { path, reason: "this is a dry run; nothing is deleted".into() };
+                actor.send_async(&mut db_to_ui_tx, report, SendSaturation::AwaitForRoom).await;
+            }
+            Some(UiRequest::Delete(path)) => {
+               // println!("User confirmed deletion: {:?}", path);
+                // The request can overtake the file's own scan event and verdict; write
+                // those first so the file is indexed and verified as it was reviewed
+                while let Some(event) = actor.try_take(&mut crawler_to_db_rx) {
+                    if let Err(e) = apply_scan_event(event, &index, scan_id) {
+                        eprintln!("Failed to record scan result: {}", e);
+                    }
+                }
+                while let Some(mut entry) = actor.try_take(&mut ai_model_to_db_rx) {
+                    entry.scan_id = scan_id;
+                    if let Err(e) = index.put(&entry) {
+                        eprintln!("Failed to store verdict for {:?}: {}", entry.meta.abs_path, e);
+                    }
+                }
+                // Deleting only moves the file aside so it can still be restored
+                let indexed = index.get(&path).ok().flatten();
+                let result = delete_file(&path, &index, &quarantine, trash.as_ref());
+                if let Err(e) = journal.record(journal_entry(&path, indexed.as_ref(), &result)) {
+                    eprintln!("Failed to journal deletion of {:?}: {}", path, e);
+                }
+                match result {
+                    Ok(removed) => {
+                        let destination = match removed {
+                            Removed::Quarantined(record) => {
+                                undoable.insert(path.clone(), record.id);
+                                record.stored_path
+                            }
+                            Removed::Trashed(trashed) => {
+                                undoable.remove(&path);
+                                trashed.stored_path
+                            }
+                        };
+                        if let Err(e) = index.remove(&path) {
+                            eprintln!("Failed to drop index entry for {:?}: {}", path, e);
+                        }
+                        let report = DbReport::Deleted { path, destination };
+                        actor.send_async(&mut db_to_ui_tx, report, SendSaturation::AwaitForRoom).await;
+                    }
+                    //Ok(_) => println!("Deleted from disk: {:?}", path),
+                    Err(e) => {
+                        let report = DbReport::DeleteRefused { path, reason: e.to_string() };
+                        actor.send_async(&mut db_to_ui_tx, report, SendSaturation::AwaitForRoom).await;
+                    }
+                }
+            }
+            Some(UiRequest::NeverDelete { path, scope }) => {
+                let added = Protection::for_path(&path, scope, &index)
+                    .and_then(|mark| mark_never_delete(mark, &protections, &mut marked));
+                if let Err(e) = added {
+                    eprintln!("Failed to mark {:?} never-delete: {}", path, e);
+                }
+            }
+            Some(UiRequest::Restore(path)) => {
+                let result = undo_delete(&path, &mut undoable, &index, &quarantine);
+                if let Err(e) = journal.record(restore_entry(&path, &result)) {
+                    eprintln!("Failed to journal restore of {:?}: {}", path, e);
+                }
+                let report = match result {
+                    Ok(_) => DbReport::Restored(path),
+                    Err(e) => DbReport::RestoreFailed { path, reason: e.to_string() },
+                };
+                actor.send_async(&mut db_to_ui_tx, report, SendSaturation::AwaitForRoom).await;
+            }
+            Some(UiRequest::RemoveNeverDelete { path, scope }) => {
+                let removed = Protection::for_path(&path, scope, &index)
+                    .and_then(|mark| lift_never_delete(&mark, &protections, &mut marked));
+                if let Err(e) = removed {
+                    eprintln!("Failed to lift never-delete mark on {:?}: {}", path, e);
+                }
+            }
+            Some(UiRequest::ListMarks) => {
+                let report = DbReport::Marks(protections.list());
+                actor.send_async(&mut db_to_ui_tx, report, SendSaturation::AwaitForRoom).await;
+            }
+            Some(UiRequest::LiftMark(mark)) => {
+                marked.remove(&mark);
+                if let Err(e) = protections.remove(&mark) {
+                    eprintln!("Failed to lift never-delete mark on {}: {}", mark, e);
+                }
+            }
+            None => {}
+        }
+
+        // 2) Store the AI's verdict so an unchanged file is not asked about again
+        if let Some(mut entry) = actor.try_take(&mut ai_model_to_db_rx) {
+            entry.scan_id = scan_id;
+            if let Err(e) = index.put(&entry) {
+                eprintln!("Failed to store verdict for {:?}: {}", entry.meta.abs_path, e);
             }
-            // TODO: remove corresponding sled entry by looking up key for this path
         }
     
-        // 3) Drain up to BATCH_SIZE items from crawler_to_db_rx
-        for _ in 0..BATCH_SIZE {
+        // 3) Drain up to batch_size items from crawler_to_db_rx
+        for _ in 0..config.batch_size {
             match actor.try_take(&mut crawler_to_db_rx) {
-                Some(file_meta) => {
-                    let _ = db_add(ctr, file_meta.clone(), &db);
-                    //file_meta.meta_print();
+                Some(event) => {
+                    if let Err(e) = apply_scan_event(event, &index, scan_id) {
+                        eprintln!("Failed to record scan result: {}", e);
+                    }
                 }
                 None => {
                     // nothing more to read right now
@@ -60,51 +262,190 @@ async fn internal_behavior<A: SteadyActor>(mut actor: A,
                 }
             }
         }
+
+        // 4) Crawl finished — hand every duplicate group to the UI once
+        if !duplicates_sent && crawler_to_db_rx.is_closed_and_empty() {
+            let groups = find_duplicates(&index).groups(&keeper);
+            for group in groups.into_iter().filter_map(|g| without_protected(g, &protections)) {
+                actor.send_async(&mut db_to_ui_tx, DbReport::Duplicates(group), SendSaturation::AwaitForRoom).await;
+            }
+            duplicates_sent = true;
+        }
+
+        // 5) Dry run finished — write down everything that would have been deleted
+        if let Some(file) = &plan_file
+            && !plan_written
+            && crawler_to_db_rx.is_closed_and_empty()
+            && ai_model_to_db_rx.is_closed_and_empty() {
+            let groups: Vec<DuplicateGroup> = find_duplicates(&index).groups(&keeper)
+                .into_iter()
+                .filter_map(|g| without_protected(g, &protections))
+                .collect();
+            let plan = DeletionPlan::build(&index, &protections, &groups);
+            match plan.write(file) {
+                Ok(_) => {
+                    let report = DbReport::PlanWritten { path: file.clone(), files: plan.entries.len(), reclaimable: plan.reclaimable };
+                    actor.send_async(&mut db_to_ui_tx, report, SendSaturation::AwaitForRoom).await;
+                }
+                Err(e) => eprintln!("Failed to write deletion plan {:?}: {}", file, e),
+            }
+            plan_written = true;
+        }
+
+        // 6) Nothing more will be found — say so once
+        if !finished_sent
+            && duplicates_sent
+            && (plan_file.is_none() || plan_written)
+            && crawler_to_db_rx.is_closed_and_empty()
+            && ai_model_to_db_rx.is_closed_and_empty() {
+            actor.send_async(&mut db_to_ui_tx, DbReport::Finished, SendSaturation::AwaitForRoom).await;
+            finished_sent = true;
+        }
     }
     
   Ok(())
 }
 
 
-// add db entry given key and value pair
-// TODO: add match to check if db operations are successful or not
-fn db_add(key: i32, value: FileMeta, db: &sled::Db) -> Result<(), Box<dyn Error>> {
+/// Where a deleted file ended up.
+#[derive(Debug)]
+pub(crate) enum Removed {
+    Quarantined(Box<QuarantineRecord>),
+    Trashed(TrashedFile),
+}
 
-    // serialise struct into u8
-    let value_s = value.to_bytes()?;
+// Checks the file is still the one the user reviewed, then moves it aside
+fn delete_file(path: &Path, index: &FileIndex, quarantine: &Quarantine, trash: Option<&Trash>) -> Result<Removed, Box<dyn Error>> {
+    let indexed = index.get(path)?.ok_or(Refusal::NotIndexed)?;
+    verify::verify_before_delete(&indexed.meta)?;
+    move_aside(path, Some(indexed), quarantine, trash)
+}
 
-    // serialize i32 to bytes
-    let key_s = key.to_be_bytes();
+/// Moves an already verified file into the desktop trash when one is given and
+/// usable for the file's filesystem, and into quarantine otherwise.
+pub(crate) fn move_aside(path: &Path, indexed: Option<IndexEntry>, quarantine: &Quarantine, trash: Option<&Trash>) -> Result<Removed, Box<dyn Error>> {
+    if let Some(trash) = trash
+        && let Some(trashed) = trash.trash(path)? {
+        return Ok(Removed::Trashed(trashed));
+    }
+    Ok(Removed::Quarantined(Box::new(quarantine.quarantine(path, indexed)?)))
+}
 
-    // insert into db
-    let _ = db.insert(key_s, value_s)?;
+// Puts back a file deleted earlier in this run, if it went to quarantine, and
+// re-indexes it as it was so its verdict is not asked for again
+fn undo_delete(path: &Path, undoable: &mut HashMap<PathBuf, u64>, index: &FileIndex, quarantine: &Quarantine) -> Result<QuarantineRecord, Box<dyn Error>> {
+    let id = *undoable.get(path).ok_or("it was not moved to quarantine in this session")?;
+    let record = restore_record(id, index, quarantine)?;
+    undoable.remove(path);
+    Ok(record)
+}
 
-Ok(())
+// Adds a never-delete mark, remembering it for undo only when it is new
+fn mark_never_delete(mark: Protection, protections: &Protections, marked: &mut HashSet<Protection>) -> Result<(), Box<dyn Error>> {
+    if protections.add(&mark)? {
+        marked.insert(mark);
+    }
+    Ok(())
 }
 
+// Lifts a never-delete mark added earlier in this run; false if this run
+// added no such mark, in which case whatever mark there is stays
+fn lift_never_delete(mark: &Protection, protections: &Protections, marked: &mut HashSet<Protection>) -> Result<bool, Box<dyn Error>> {
+    if !marked.remove(mark) {
+        return Ok(false);
+    }
+    protections.remove(mark)
+}
 
-// edit db entry given key
-// TODO: add match to check if db operations are successful or not
-fn db_edit(key: i32, value: FileMeta, db: &sled::Db) -> Result<(), Box<dyn Error>> {
+/// Puts quarantine record `id` back where it came from and restores its index
+/// entry, so the file is not hashed or reviewed again as if it were new.
+pub(crate) fn restore_record(id: u64, index: &FileIndex, quarantine: &Quarantine) -> Result<QuarantineRecord, Box<dyn Error>> {
+    let record = quarantine.restore(id)?;
+    if let Some(indexed) = &record.indexed {
+        index.put(indexed)?;
+    }
+    Ok(record)
+}
 
-    // sled has immutable db, so we need to delete old key then insert new
-    let _ = db_remove(key, &db);
-    let _ = db_add(key, value, &db);
+/// Journal entry for an attempt to restore `path` from quarantine.
+pub(crate) fn restore_entry(path: &Path, result: &Result<QuarantineRecord, Box<dyn Error>>) -> JournalEntry {
+    match result {
+        Ok(record) => JournalEntry {
+            destination: Some(record.stored_path.clone()),
+            ..JournalEntry::new(path, record.indexed.as_ref(), UserAction::Restore, Outcome::Restored)
+        },
+        Err(e) => JournalEntry {
+            error: Some(e.to_string()),
+            ..JournalEntry::new(path, None, UserAction::Restore, Outcome::Failed)
+        },
+    }
+}
 
-Ok(())
+/// Purges everything held past the retention period as of `now` and journals
+/// each file removed for good.
+pub(crate) fn purge_quarantine(quarantine: &Quarantine, journal: &Journal, now: i64) -> Result<Vec<QuarantineRecord>, Box<dyn Error>> {
+    let purged = quarantine.purge_expired(now)?;
+    for record in &purged {
+        let entry = JournalEntry::new(&record.original_path, record.indexed.as_ref(), UserAction::Purge, Outcome::Purged);
+        if let Err(e) = journal.record(entry) {
+            eprintln!("Failed to journal purge of {:?}: {}", record.original_path, e);
+        }
+    }
+    Ok(purged)
 }
 
+/// One journal entry for a confirmed deletion, whatever became of it.
+pub(crate) fn journal_entry(path: &Path, indexed: Option<&IndexEntry>, result: &Result<Removed, Box<dyn Error>>) -> JournalEntry {
+    let entry = JournalEntry::new(path, indexed, UserAction::Delete, Outcome::Failed);
+    match result {
+        Ok(Removed::Quarantined(record)) => JournalEntry {
+            outcome: Outcome::Quarantined,
+            destination: Some(record.stored_path.clone()),
+            ..entry
+        },
+        Ok(Removed::Trashed(trashed)) => JournalEntry {
+            outcome: Outcome::Trashed,
+            destination: Some(trashed.stored_path.clone()),
+            ..entry
+        },
+        Err(e) => JournalEntry {
+            outcome: if e.is::<Refusal>() { Outcome::Refused } else { Outcome::Failed },
+            error: Some(e.to_string()),
+            ..entry
+        },
+    }
+}
 
-// remove db entry given key
-// TODO: add match to check if db operations are successful or not
-fn db_remove(key: i32, db: &sled::Db) -> Result<(), Box<dyn Error>> {
+// write one crawler result to the path index
+fn apply_scan_event(event: ScanEvent, index: &FileIndex, scan_id: u64) -> Result<(), Box<dyn Error>> {
+    match event {
+        ScanEvent::Upsert(file_meta) => index.upsert_meta(file_meta, scan_id),
+        ScanEvent::Vanished(path) => index.remove(&path),
+    }
+}
 
-    let key_s = key.to_be_bytes();
-    
-    // remove entry based on key
-    let _ = db.remove(key_s);
 
-    Ok(())
+// Group indexed files by content. Unchanged files are never re-sent by the
+// crawler, so the index rather than this scan's events is the source of truth;
+// the hash tree means only files that actually share a hash are loaded.
+pub(crate) fn find_duplicates(index: &FileIndex) -> DuplicateFinder {
+    let mut duplicates = DuplicateFinder::default();
+    for paths in index.shared_hashes() {
+        for path in paths {
+            // entries from roots no longer in the scan list may be long gone
+            if let Ok(Some(entry)) = index.get(&path) && path.exists() {
+                duplicates.add(entry.meta);
+            }
+        }
+    }
+    duplicates
+}
+
+// Never-delete copies stay out of `redundant`; a group left with nothing to
+// delete is dropped. The keeper is kept either way, so it needs no check.
+fn without_protected(mut group: DuplicateGroup, protections: &Protections) -> Option<DuplicateGroup> {
+    group.redundant.retain(|m| !protections.is_protected(m));
+    (!group.redundant.is_empty()).then_some(group)
 }
 
 #[cfg(test)]
@@ -112,6 +453,7 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
     use crate::actor::crawler::FileMeta;
+    use crate::hashing::HashStrategy;
 
     // ── helpers ───────────────────────────────────────────────────────────────
 
@@ -126,136 +468,288 @@ mod tests {
             modified: 1700000000,
             created: 1600000000,
             readonly: false,
+            hash_strategy: HashStrategy::default(),
+            full_hash: None,
+            inode: 0,
         }
     }
 
-    fn open_temp_db(test_name: &str) -> sled::Db {
+    fn open_temp_index(test_name: &str) -> FileIndex {
         let path = std::env::temp_dir().join(format!("cruft_test_db_{}", test_name));
         // Always start fresh
         let _ = std::fs::remove_dir_all(&path);
-        sled::open(&path).expect("failed to open temp sled db")
+        FileIndex::open(&sled::open(&path).expect("failed to open temp sled db")).unwrap()
+    }
+
+    // ── apply_scan_event ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_apply_upsert_indexes_by_path() {
+        let index = open_temp_index("apply_upsert");
+        let meta = make_meta("indexed.txt", 10);
+
+        apply_scan_event(ScanEvent::Upsert(meta.clone()), &index, 7).unwrap();
+
+        let entry = index.get(&meta.abs_path).unwrap().unwrap();
+        assert_eq!(entry.meta, meta);
+        assert_eq!(entry.verdict, None);
+        assert_eq!(entry.scan_id, 7);
     }
 
-    // ── db_add ────────────────────────────────────────────────────────────────
+    #[test]
+    fn test_apply_upsert_keeps_every_path() {
+        // the old constant key meant each record overwrote the one before it
+        let index = open_temp_index("apply_every_path");
+        for name in ["one.txt", "two.txt", "three.txt"] {
+            apply_scan_event(ScanEvent::Upsert(make_meta(name, 1)), &index, 1).unwrap();
+        }
+        assert_eq!(index.entries().count(), 3);
+    }
 
     #[test]
-    fn test_db_add_inserts_entry() {
-        let db = open_temp_db("add_inserts");
-        let meta = make_meta("file1.txt", 1024);
-        db_add(1, meta.clone(), &db).expect("db_add should succeed");
+    fn test_apply_vanished_drops_index_entry() {
+        let index = open_temp_index("apply_vanished");
+        let meta = make_meta("gone.txt", 10);
+        apply_scan_event(ScanEvent::Upsert(meta.clone()), &index, 1).unwrap();
 
-        let key = 1i32.to_be_bytes();
-        let raw = db.get(key).unwrap().expect("entry should exist");
-        let restored = FileMeta::from_bytes(&raw).unwrap();
-        assert_eq!(restored.file_name, "file1.txt");
-        assert_eq!(restored.size, 1024);
+        apply_scan_event(ScanEvent::Vanished(meta.abs_path.clone()), &index, 1).unwrap();
+
+        assert!(index.get(&meta.abs_path).unwrap().is_none());
     }
 
+    // ── find_duplicates ───────────────────────────────────────────────────────
+
     #[test]
-    fn test_db_add_multiple_entries_distinct_keys() {
-        let db = open_temp_db("add_multiple");
-        let meta1 = make_meta("alpha.rs", 100);
-        let meta2 = make_meta("beta.rs", 200);
+    fn test_find_duplicates_skips_missing_files() {
+        let index = open_temp_index("find_duplicates");
+        let dir = std::env::temp_dir().join("cruft_db_find_duplicates");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for name in ["a.txt", "b.txt"] {
+            let path = dir.join(name);
+            std::fs::write(&path, b"same").unwrap();
+            let mut meta = make_meta(name, 4);
+            meta.abs_path = path;
+            meta.full_hash = Some("same".to_string());
+            index.put(&IndexEntry::new(meta, None)).unwrap();
+        }
+        // indexed but no longer on disk
+        let mut stale = make_meta("missing_copy.txt", 4);
+        stale.abs_path = dir.join("missing_copy.txt");
+        stale.full_hash = Some("same".to_string());
+        index.put(&IndexEntry::new(stale, None)).unwrap();
+
+        let groups = find_duplicates(&index).groups(&KeeperPolicy::default());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].redundant.len(), 1);
+    }
 
-        db_add(1, meta1, &db).unwrap();
-        db_add(2, meta2, &db).unwrap();
+    // ── delete_file ───────────────────────────────────────────────────────────
 
-        let r1 = FileMeta::from_bytes(&db.get(1i32.to_be_bytes()).unwrap().unwrap()).unwrap();
-        let r2 = FileMeta::from_bytes(&db.get(2i32.to_be_bytes()).unwrap().unwrap()).unwrap();
+    fn delete_setup(name: &str) -> (FileIndex, Quarantine, PathBuf) {
+        let base = std::env::temp_dir().join(format!("cruft_test_db_delete_{}", name));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("files")).unwrap();
+        let db = sled::open(base.join("db")).unwrap();
+        let quarantine = Quarantine::open(&db, base.join("held"), quarantine::DEFAULT_RETENTION).unwrap();
+        (FileIndex::open(&db).unwrap(), quarantine, base)
+    }
 
-        assert_eq!(r1.file_name, "alpha.rs");
-        assert_eq!(r2.file_name, "beta.rs");
+    /// Writes a file and indexes it the way a scan would have.
+    fn write_indexed(index: &FileIndex, path: &Path, contents: &[u8]) {
+        std::fs::write(path, contents).unwrap();
+        let md = std::fs::metadata(path).unwrap();
+        let meta = crate::actor::crawler::file_meta_from_path(path, &md, &Default::default()).unwrap();
+        index.put(&IndexEntry::new(meta, None)).unwrap();
     }
 
     #[test]
-    fn test_db_add_overwrites_same_key() {
-        let db = open_temp_db("add_overwrite");
-        let meta1 = make_meta("old.txt", 50);
-        let meta2 = make_meta("new.txt", 999);
+    fn test_delete_file_quarantines_without_trash() {
+        let (index, quarantine, base) = delete_setup("quarantine");
+        let path = base.join("files/a.txt");
+        write_indexed(&index, &path, b"x");
 
-        db_add(42, meta1, &db).unwrap();
-        db_add(42, meta2, &db).unwrap();
+        let removed = delete_file(&path, &index, &quarantine, None).unwrap();
 
-        let raw = db.get(42i32.to_be_bytes()).unwrap().unwrap();
-        let restored = FileMeta::from_bytes(&raw).unwrap();
-        // sled insert overwrites — should have the second value
-        assert_eq!(restored.file_name, "new.txt");
-        assert_eq!(restored.size, 999);
+        assert!(matches!(removed, Removed::Quarantined(_)));
+        assert!(!path.exists());
+        assert_eq!(quarantine.list().len(), 1);
     }
 
-    // ── db_remove ─────────────────────────────────────────────────────────────
+    #[test]
+    fn test_delete_file_uses_trash_when_configured() {
+        let (index, quarantine, base) = delete_setup("trash");
+        let path = base.join("files/a.txt");
+        write_indexed(&index, &path, b"x");
+        let trash = Trash::new(base.join("Trash"));
+
+        let removed = delete_file(&path, &index, &quarantine, Some(&trash)).unwrap();
+
+        match removed {
+            Removed::Trashed(t) => assert_eq!(t.stored_path, base.join("Trash/files/a.txt")),
+            other => panic!("expected the trash to be used, got {:?}", other),
+        }
+        assert!(quarantine.list().is_empty());
+    }
 
     #[test]
-    fn test_db_remove_deletes_entry() {
-        let db = open_temp_db("remove_deletes");
-        let meta = make_meta("to_remove.log", 256);
-        db_add(10, meta, &db).unwrap();
+    fn test_delete_file_refuses_changed_file() {
+        let (index, quarantine, base) = delete_setup("changed");
+        let path = base.join("files/a.txt");
+        write_indexed(&index, &path, b"x");
+        std::fs::write(&path, b"replaced with something else").unwrap();
 
-        db_remove(10, &db).expect("db_remove should succeed");
+        let err = delete_file(&path, &index, &quarantine, None).unwrap_err();
 
-        let result = db.get(10i32.to_be_bytes()).unwrap();
-        assert!(result.is_none(), "entry should no longer exist after remove");
+        assert_eq!(err.to_string(), Refusal::Changed.to_string());
+        assert!(path.exists());
+        assert!(quarantine.list().is_empty());
     }
 
     #[test]
-    fn test_db_remove_nonexistent_key_is_ok() {
-        let db = open_temp_db("remove_nonexistent");
-        // Removing a key that was never inserted should not error
-        assert!(db_remove(999, &db).is_ok());
+    fn test_delete_file_refuses_unindexed_file() {
+        let (index, quarantine, base) = delete_setup("unindexed");
+        let path = base.join("files/a.txt");
+        std::fs::write(&path, b"x").unwrap();
+
+        assert!(delete_file(&path, &index, &quarantine, None).is_err());
+        assert!(path.exists());
     }
 
-    // ── db_edit ───────────────────────────────────────────────────────────────
+    // ── journal_entry ─────────────────────────────────────────────────────────
 
     #[test]
-    fn test_db_edit_updates_value() {
-        let db = open_temp_db("edit_updates");
-        let original = make_meta("original.txt", 100);
-        let updated = make_meta("updated.txt", 9999);
+    fn test_journal_entry_records_destination() {
+        let (index, quarantine, base) = delete_setup("journal_ok");
+        let path = base.join("files/a.txt");
+        write_indexed(&index, &path, b"x");
+        let indexed = index.get(&path).unwrap();
+
+        let result = delete_file(&path, &index, &quarantine, None);
+        let entry = journal_entry(&path, indexed.as_ref(), &result);
+
+        assert_eq!(entry.outcome, Outcome::Quarantined);
+        assert_eq!(entry.destination, Some(quarantine.list()[0].stored_path.clone()));
+        assert_eq!(entry.size, Some(1));
+        assert_eq!(entry.error, None);
+    }
 
-        db_add(5, original, &db).unwrap();
-        db_edit(5, updated, &db).expect("db_edit should succeed");
+    #[test]
+    fn test_journal_entry_records_refusal() {
+        let (index, quarantine, base) = delete_setup("journal_refused");
+        let path = base.join("files/a.txt");
+        write_indexed(&index, &path, b"x");
+        std::fs::write(&path, b"replaced with something else").unwrap();
+        let indexed = index.get(&path).unwrap();
+
+        let result = delete_file(&path, &index, &quarantine, None);
+        let entry = journal_entry(&path, indexed.as_ref(), &result);
+
+        assert_eq!(entry.outcome, Outcome::Refused);
+        assert_eq!(entry.error, Some(Refusal::Changed.to_string()));
+        assert_eq!(entry.destination, None);
+    }
 
-        let raw = db.get(5i32.to_be_bytes()).unwrap().unwrap();
-        let restored = FileMeta::from_bytes(&raw).unwrap();
-        assert_eq!(restored.file_name, "updated.txt");
-        assert_eq!(restored.size, 9999);
+    #[test]
+    fn test_journal_entry_other_errors_are_failures() {
+        let result: Result<Removed, Box<dyn Error>> = Err("disk full".into());
+        let entry = journal_entry(Path::new("/a"), None, &result);
+        assert_eq!(entry.outcome, Outcome::Failed);
+        assert_eq!(entry.error.as_deref(), Some("disk full"));
     }
 
+    // ── undo_delete ───────────────────────────────────────────────────────────
+
     #[test]
-    fn test_db_edit_on_nonexistent_key_inserts() {
-        let db = open_temp_db("edit_inserts_new");
-        let meta = make_meta("brand_new.txt", 512);
+    fn test_undo_delete_restores_and_reindexes() {
+        let (index, quarantine, base) = delete_setup("undo");
+        let path = base.join("files/a.txt");
+        write_indexed(&index, &path, b"x");
+        let mut undoable = HashMap::new();
+        if let Removed::Quarantined(record) = delete_file(&path, &index, &quarantine, None).unwrap() {
+            undoable.insert(path.clone(), record.id);
+        }
+        index.remove(&path).unwrap();
 
-        // db_edit on a key that doesn't exist: removes (no-op) then adds
-        db_edit(77, meta, &db).expect("db_edit on new key should succeed");
+        let record = undo_delete(&path, &mut undoable, &index, &quarantine).unwrap();
 
-        let raw = db.get(77i32.to_be_bytes()).unwrap();
-        assert!(raw.is_some(), "should have been inserted by db_edit");
+        assert_eq!(record.original_path, path);
+        assert_eq!(std::fs::read(&path).unwrap(), b"x");
+        assert!(index.get(&path).unwrap().is_some());
+        assert!(undoable.is_empty());
     }
 
-    // ── key serialization ─────────────────────────────────────────────────────
+    #[test]
+    fn test_undo_delete_ignores_records_from_earlier_runs() {
+        let (index, quarantine, base) = delete_setup("undo_earlier");
+        let path = base.join("files/a.txt");
+        write_indexed(&index, &path, b"old");
+        delete_file(&path, &index, &quarantine, None).unwrap();
+
+        let err = undo_delete(&path, &mut HashMap::new(), &index, &quarantine).unwrap_err();
+
+        assert!(err.to_string().contains("not moved to quarantine"));
+        assert!(!path.exists());
+    }
 
     #[test]
-    fn test_key_zero_works() {
-        let db = open_temp_db("key_zero");
-        let meta = make_meta("zero_key.txt", 1);
-        db_add(0, meta, &db).unwrap();
-        assert!(db.get(0i32.to_be_bytes()).unwrap().is_some());
+    fn test_undo_delete_keeps_entry_when_restore_fails() {
+        let (index, quarantine, base) = delete_setup("undo_blocked");
+        let path = base.join("files/a.txt");
+        write_indexed(&index, &path, b"x");
+        let mut undoable = HashMap::new();
+        if let Removed::Quarantined(record) = delete_file(&path, &index, &quarantine, None).unwrap() {
+            undoable.insert(path.clone(), record.id);
+        }
+        // something new took its place
+        std::fs::write(&path, b"new").unwrap();
+
+        assert!(undo_delete(&path, &mut undoable, &index, &quarantine).is_err());
+        assert_eq!(undoable.len(), 1);
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+    }
+
+    // ── without_protected ─────────────────────────────────────────────────────
+
+    fn dup_group(keeper: &str, redundant: &[&str]) -> DuplicateGroup {
+        DuplicateGroup {
+            size: 10,
+            content_hash: "h".to_string(),
+            keeper: make_meta(keeper, 10),
+            redundant: redundant.iter().map(|n| make_meta(n, 10)).collect(),
+        }
     }
 
     #[test]
-    fn test_key_negative_works() {
-        let db = open_temp_db("key_negative");
-        let meta = make_meta("neg_key.txt", 1);
-        db_add(-1, meta, &db).unwrap();
-        assert!(db.get((-1i32).to_be_bytes()).unwrap().is_some());
+    fn test_lift_only_marks_added_this_run() {
+        let path = std::env::temp_dir().join("cruft_test_db_lift_marks");
+        let _ = std::fs::remove_dir_all(&path);
+        let protections = Protections::open(&sled::open(&path).unwrap()).unwrap();
+        let earlier = Protection::Dir(PathBuf::from("/photos"));
+        let new = Protection::Path(PathBuf::from("/tmp/b.txt"));
+        protections.add(&earlier).unwrap();
+        let mut marked = HashSet::new();
+
+        mark_never_delete(earlier.clone(), &protections, &mut marked).unwrap();
+        mark_never_delete(new.clone(), &protections, &mut marked).unwrap();
+
+        assert!(!lift_never_delete(&earlier, &protections, &mut marked).unwrap());
+        assert!(lift_never_delete(&new, &protections, &mut marked).unwrap());
+        assert_eq!(protections.list(), vec![earlier]);
     }
 
     #[test]
-    fn test_key_max_i32_works() {
-        let db = open_temp_db("key_max");
-        let meta = make_meta("max_key.txt", 1);
-        db_add(i32::MAX, meta, &db).unwrap();
-        assert!(db.get(i32::MAX.to_be_bytes()).unwrap().is_some());
+    fn test_without_protected_drops_marked_copies() {
+        let path = std::env::temp_dir().join("cruft_test_db_protect_copies");
+        let _ = std::fs::remove_dir_all(&path);
+        let protections = Protections::open(&sled::open(&path).unwrap()).unwrap();
+        protections.add(&Protection::Path(PathBuf::from("/tmp/b.txt"))).unwrap();
+
+        let group = without_protected(dup_group("a.txt", &["b.txt", "c.txt"]), &protections).unwrap();
+        assert_eq!(group.redundant.len(), 1);
+        assert_eq!(group.redundant[0].file_name, "c.txt");
+
+        assert_eq!(without_protected(dup_group("a.txt", &["b.txt"]), &protections), None);
     }
-}
\ No newline at end of file
+}
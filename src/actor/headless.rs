@@ -0,0 +1,490 @@
+use steady_state::*;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::actor::db_manager::DbReport;
+use crate::actor::ai_model::{Verdict, VerdictSource};
+use crate::actor::user_interface::UiRequest;
+use crate::cli::AutoApply;
+use crate::commands::human_size;
+use crate::config::HeadlessConfig;
+use crate::dirs;
+
+/// What a headless run may delete without asking. Every limit applies to the
+/// whole run; a suggestion outside them is reported and left alone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Policy {
+    /// Which suggestions may be deleted; `None` only reports.
+    pub apply:     Option<AutoApply>,
+    pub max_files: usize,
+    pub max_bytes: u64,
+    /// How long a file must have gone unmodified.
+    pub min_age:   Duration,
+}
+
+impl Policy {
+    pub fn new(apply: Option<AutoApply>, config: &HeadlessConfig) -> Self {
+        Self {
+            apply,
+            max_files: config.max_files,
+            max_bytes: config.max_bytes(),
+            min_age:   config.min_age(),
+        }
+    }
+
+    /// Why `suggestion` must not be deleted, given what the run has already
+    /// deleted (`files`, `bytes`) and the file as it is now.
+    pub fn hold_reason(&self, verdict: &Verdict, file: &io::Result<fs::Metadata>, files: usize, bytes: u64, now: SystemTime) -> Option<String> {
+        let Some(apply) = self.apply else {
+            return Some("--auto-apply was not given".into());
+        };
+        if apply == AutoApply::Duplicates && !matches!(verdict.source, VerdictSource::Duplicate { .. }) {
+            return Some("only duplicates are applied".into());
+        }
+        let file = match file {
+            Ok(file) => file,
+            Err(e) => return Some(format!("can't be read: {}", e)),
+        };
+        let age = file.modified().ok().and_then(|m| now.duration_since(m).ok()).unwrap_or_default();
+        if age < self.min_age {
+            return Some(format!("modified {} day(s) ago; headless.min-age-days is {}",
+                                age.as_secs() / 86400, self.min_age.as_secs() / 86400));
+        }
+        if files >= self.max_files {
+            return Some(format!("headless.max-files ({}) reached", self.max_files));
+        }
+        if bytes.saturating_add(file.len()) > self.max_bytes {
+            return Some(format!("would exceed headless.max-mb ({})", human_size(self.max_bytes)));
+        }
+        None
+    }
+}
+
+/// Stands in for the review UI: writes every suggestion to a report and, if
+/// the policy allows, asks the DB actor to delete it. The DB actor still
+/// checks, quarantines and journals each deletion exactly as for the UI.
+pub(crate) struct Sink<W: Write> {
+    out:       W,
+    policy:    Policy,
+    /// Paths already reported; a file the model flagged may also be a duplicate.
+    seen:      HashSet<PathBuf>,
+    /// Deletions sent and not yet answered, with the size they count against the limit.
+    pending:   HashMap<PathBuf, u64>,
+    suggested: usize,
+    deleted:   usize,
+    freed:     u64,
+    refused:   usize,
+    finished:  bool,
+}
+
+impl<W: Write> Sink<W> {
+    pub fn new(out: W, policy: Policy) -> Self {
+        Self {
+            out,
+            policy,
+            seen:      HashSet::new(),
+            pending:   HashMap::new(),
+            suggested: 0,
+            deleted:   0,
+            freed:     0,
+            refused:   0,
+            finished:  false,
+        }
+    }
+
+    /// The first line of the report: what this run may do.
+    pub fn start(&mut self) -> io::Result<()> {
+        match self.policy.apply {
+            None => writeln!(self.out, "Headless review: suggestions are only reported"),
+            Some(apply) => {
+                let what = match apply {
+                    AutoApply::Duplicates => "duplicates",
+                    AutoApply::All => "duplicates and model suggestions",
+                };
+                writeln!(self.out, "Headless review: deleting {} — at most {} file(s) and {}, untouched for {} day(s)",
+                         what, self.policy.max_files, human_size(self.policy.max_bytes), self.policy.min_age.as_secs() / 86400)
+            }
+        }
+    }
+
+    /// Reports `verdict` and returns the deletion to send, if the policy allows one.
+    pub fn suggest(&mut self, verdict: Verdict, now: SystemTime) -> io::Result<Option<UiRequest>> {
+        let path = verdict.path().to_path_buf();
+        if !self.seen.insert(path.clone()) {
+            return Ok(None);
+        }
+        self.suggested += 1;
+
+        let file = fs::symlink_metadata(&path);
+        let size = file.as_ref().map(|f| f.len()).unwrap_or(0);
+        let mut why = match &verdict.source {
+            VerdictSource::Ai => format!("model, {}% sure", verdict.confidence),
+            VerdictSource::Rule => "rule".to_string(),
+            VerdictSource::Duplicate { keeper, .. } => format!("duplicate of {}", keeper.display()),
+        };
+        if !verdict.reasoning.is_empty() && !matches!(verdict.source, VerdictSource::Duplicate { .. }) {
+            why = format!("{}: {}", why, verdict.reasoning);
+        }
+        writeln!(self.out, "suggest  {:>10}  {}  ({})", human_size(size), path.display(), why)?;
+
+        if self.policy.apply.is_none() {
+            return Ok(None);
+        }
+        let files = self.deleted + self.pending.len();
+        let bytes = self.freed + self.pending.values().sum::<u64>();
+        if let Some(reason) = self.policy.hold_reason(&verdict, &file, files, bytes, now) {
+            writeln!(self.out, "held     {:>10}  {}  ({})", "", path.display(), reason)?;
+            return Ok(None);
+        }
+        self.pending.insert(path.clone(), size);
+        Ok(Some(UiRequest::Delete(path)))
+    }
+
+    /// Reports what the DB actor says and returns any deletions it leads to.
+    pub fn report(&mut self, report: DbReport, now: SystemTime) -> io::Result<Vec<UiRequest>> {
+        let mut requests = Vec::new();
+        match report {
+            DbReport::Duplicates(group) => {
+                for verdict in Verdict::from_duplicates(&group) {
+                    requests.extend(self.suggest(verdict, now)?);
+                }
+            }
+            DbReport::Deleted { path, destination } => {
+                let size = self.pending.remove(&path).unwrap_or(0);
+                self.deleted += 1;
+                self.freed += size;
+                writeln!(self.out, "deleted  {:>10}  {}  (moved to {})", human_size(size), path.display(), destination.display())?;
+            }
+            DbReport::DeleteRefused { path, reason } => {
+                self.pending.remove(&path);
+                self.refused += 1;
+                writeln!(self.out, "refused  {:>10}  {}  ({})", "", path.display(), reason)?;
+            }
+            DbReport::PlanWritten { path, files, reclaimable } => {
+                writeln!(self.out, "Dry run: plan of {} file(s), {} reclaimable, written to {}",
+                         files, human_size(reclaimable), path.display())?;
+            }
+            DbReport::Finished => self.finished = true,
+            // Nothing is ever undone here, nor are marks asked for
+            DbReport::Restored(_) | DbReport::RestoreFailed { .. } | DbReport::Marks(_) => {}
+        }
+        Ok(requests)
+    }
+
+    /// Every file is reviewed and every deletion sent has been answered.
+    pub fn is_done(&self) -> bool {
+        self.finished && self.pending.is_empty()
+    }
+
+    /// The last line of the report.
+    pub fn summary(&mut self) -> io::Result<()> {
+        writeln!(self.out, "{} suggestion(s); deleted {} file(s), {} freed; {} refused",
+                 self.suggested, self.deleted, human_size(self.freed), self.refused)?;
+        self.out.flush()
+    }
+}
+
+pub async fn run(
+    actor: SteadyActorShadow,
+    ai_model_to_ui_rx: SteadyRx<Verdict>,
+    db_to_ui_rx: SteadyRx<DbReport>,
+    ui_to_db_tx: SteadyTx<UiRequest>,
+    config: HeadlessConfig,
+    auto_apply: Option<AutoApply>,
+) -> Result<(), Box<dyn Error>> {
+    let actor = actor.into_spotlight([&ai_model_to_ui_rx, &db_to_ui_rx], [&ui_to_db_tx]);
+    if actor.use_internal_behavior {
+        internal_behavior(actor, ai_model_to_ui_rx, db_to_ui_rx, ui_to_db_tx, config, auto_apply).await
+    } else {
+        actor.simulated_behavior(vec![&ai_model_to_ui_rx, &db_to_ui_rx]).await
+    }
+}
+
+async fn internal_behavior<A: SteadyActor>(
+    mut actor: A,
+    ai_model_to_ui_rx: SteadyRx<Verdict>,
+    db_to_ui_rx: SteadyRx<DbReport>,
+    ui_to_db_tx: SteadyTx<UiRequest>,
+    config: HeadlessConfig,
+    auto_apply: Option<AutoApply>,
+) -> Result<(), Box<dyn Error>> {
+    let mut ai_model_to_ui_rx = ai_model_to_ui_rx.lock().await;
+    let mut db_to_ui_rx = db_to_ui_rx.lock().await;
+    let mut ui_to_db_tx = ui_to_db_tx.lock().await;
+
+    let mut sink = Sink::new(LineWriter::new(open_report(&config.report)?), Policy::new(auto_apply, &config));
+    sink.start()?;
+    let mut done = false;
+
+    while actor.is_running(|| ai_model_to_ui_rx.is_closed_and_empty()
+                               && db_to_ui_rx.is_closed_and_empty()
+                               && ui_to_db_tx.mark_closed()) {
+        // A closed channel never blocks, so only wait on the open ones
+        if ai_model_to_ui_rx.is_closed_and_empty() {
+            actor.wait_avail(&mut db_to_ui_rx, 1).await;
+        } else {
+            await_for_any!(
+                actor.wait_avail(&mut ai_model_to_ui_rx, 1),
+                actor.wait_avail(&mut db_to_ui_rx, 1)
+            );
+        }
+
+        let mut requests = Vec::new();
+        while let Some(verdict) = actor.try_take(&mut ai_model_to_ui_rx) {
+            if verdict.is_delete() {
+                requests.extend(sink.suggest(verdict, SystemTime::now())?);
+            }
+        }
+        while let Some(report) = actor.try_take(&mut db_to_ui_rx) {
+            requests.extend(sink.report(report, SystemTime::now())?);
+        }
+        for request in requests {
+            actor.send_async(&mut ui_to_db_tx, request, SendSaturation::AwaitForRoom).await;
+        }
+
+        // Nobody is there to press `q`: stop the whole graph once everything is answered
+        if !done && sink.is_done() {
+            sink.summary()?;
+            done = true;
+            // Nothing more will be asked of the DB actor; closing lets it stop waiting on us
+            ui_to_db_tx.mark_closed();
+            actor.request_shutdown().await;
+        }
+    }
+
+    Ok(())
+}
+
+/// The report file, truncated, or standard output when `file` is empty.
+fn open_report(file: &Path) -> io::Result<Box<dyn Write + Send>> {
+    if file.as_os_str().is_empty() {
+        return Ok(Box::new(io::stdout()));
+    }
+    dirs::create_parent(file)?;
+    Ok(Box::new(File::create(file)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::ai_model::Decision;
+    use crate::actor::crawler::FileMeta;
+    use crate::duplicates::DuplicateGroup;
+    use crate::hashing::HashStrategy;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    const DAY: Duration = Duration::from_secs(86400);
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cruft_headless_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_file(dir: &Path, name: &str, len: usize) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, vec![b'x'; len]).unwrap();
+        path
+    }
+
+    fn policy(apply: Option<AutoApply>) -> Policy {
+        Policy { apply, max_files: 2, max_bytes: 100, min_age: 30 * DAY }
+    }
+
+    /// A month and a half from now, so files written by the test are old enough.
+    fn later() -> SystemTime {
+        SystemTime::now() + 45 * DAY
+    }
+
+    fn sink(apply: Option<AutoApply>) -> Sink<Vec<u8>> {
+        Sink::new(Vec::new(), policy(apply))
+    }
+
+    fn text(sink: &Sink<Vec<u8>>) -> String {
+        String::from_utf8(sink.out.clone()).unwrap()
+    }
+
+    fn meta(path: &Path) -> FileMeta {
+        FileMeta {
+            rel_path: PathBuf::from(path.file_name().unwrap()),
+            abs_path: path.to_path_buf(),
+            file_name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            hash: "h".to_string(),
+            is_file: true,
+            size: 10,
+            modified: 0,
+            created: 0,
+            readonly: false,
+            hash_strategy: HashStrategy::default(),
+            full_hash: None,
+            inode: 0,
+        }
+    }
+
+    /// The model's delete verdict on `path`.
+    fn ai(path: &Path) -> Verdict {
+        Verdict {
+            meta: meta(path),
+            decision: Decision::Delete,
+            confidence: 90,
+            reasoning: String::new(),
+            source: VerdictSource::Ai,
+        }
+    }
+
+    fn group(keeper: &Path, redundant: &[&Path]) -> DuplicateGroup {
+        DuplicateGroup {
+            size: 10,
+            content_hash: "h".to_string(),
+            keeper: meta(keeper),
+            redundant: redundant.iter().map(|p| meta(p)).collect(),
+        }
+    }
+
+    // ── Policy ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_duplicates_policy_holds_model_suggestions() {
+        let dir = temp_dir("policy_kind");
+        let path = write_file(&dir, "a.o", 1);
+        let file = fs::symlink_metadata(&path);
+
+        let held = policy(Some(AutoApply::Duplicates)).hold_reason(&ai(&path), &file, 0, 0, later());
+        assert_eq!(held.as_deref(), Some("only duplicates are applied"));
+        assert_eq!(policy(Some(AutoApply::All)).hold_reason(&ai(&path), &file, 0, 0, later()), None);
+    }
+
+    #[test]
+    fn test_recently_modified_files_are_held() {
+        let dir = temp_dir("policy_age");
+        let path = write_file(&dir, "a.o", 1);
+
+        let held = policy(Some(AutoApply::All)).hold_reason(&ai(&path), &fs::symlink_metadata(&path), 0, 0, SystemTime::now());
+        assert!(held.unwrap().contains("min-age-days is 30"));
+    }
+
+    #[test]
+    fn test_limits_are_for_the_whole_run() {
+        let dir = temp_dir("policy_limits");
+        let path = write_file(&dir, "a.o", 40);
+        let file = fs::symlink_metadata(&path);
+        let policy = policy(Some(AutoApply::All));
+        let suggestion = ai(&path);
+
+        assert_eq!(policy.hold_reason(&suggestion, &file, 1, 60, later()), None, "exactly at the byte limit");
+        assert!(policy.hold_reason(&suggestion, &file, 2, 0, later()).unwrap().contains("max-files"));
+        assert!(policy.hold_reason(&suggestion, &file, 1, 61, later()).unwrap().contains("max-mb"));
+    }
+
+    #[test]
+    fn test_missing_file_is_held() {
+        let dir = temp_dir("policy_missing");
+        let path = dir.join("gone.o");
+
+        let held = policy(Some(AutoApply::All)).hold_reason(&ai(&path), &fs::symlink_metadata(&path), 0, 0, later());
+        assert!(held.unwrap().starts_with("can't be read"));
+    }
+
+    // ── Sink ──────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_report_only_never_deletes() {
+        let dir = temp_dir("sink_report_only");
+        let path = write_file(&dir, "a.o", 5);
+        let mut sink = sink(None);
+
+        assert_eq!(sink.suggest(ai(&path), later()).unwrap(), None);
+
+        let text = text(&sink);
+        assert!(text.contains("5 B"), "{}", text);
+        assert!(text.contains(&format!("{}  (model, 90% sure)", path.display())), "{}", text);
+    }
+
+    #[test]
+    fn test_each_path_is_reported_once() {
+        let dir = temp_dir("sink_once");
+        let keeper = write_file(&dir, "a.iso", 10);
+        let copy = write_file(&dir, "b.iso", 10);
+        let mut sink = sink(None);
+
+        sink.suggest(ai(&copy), later()).unwrap();
+        sink.report(DbReport::Duplicates(group(&keeper, &[&copy])), later()).unwrap();
+
+        assert_eq!(sink.suggested, 1);
+        assert_eq!(text(&sink).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_duplicates_are_deleted_within_policy() {
+        let dir = temp_dir("sink_duplicates");
+        let keeper = write_file(&dir, "a.iso", 10);
+        let copies: Vec<PathBuf> = ["b.iso", "c.iso", "d.iso"].iter().map(|n| write_file(&dir, n, 10)).collect();
+        let mut sink = sink(Some(AutoApply::Duplicates));
+
+        let copy_refs: Vec<&Path> = copies.iter().map(|p| p.as_path()).collect();
+        let requests = sink.report(DbReport::Duplicates(group(&keeper, &copy_refs)), later()).unwrap();
+
+        assert_eq!(requests, vec![UiRequest::Delete(copies[0].clone()), UiRequest::Delete(copies[1].clone())]);
+        assert!(text(&sink).contains("headless.max-files (2) reached"));
+        assert!(!sink.pending.contains_key(&keeper), "the keeper is never deleted");
+    }
+
+    #[test]
+    fn test_refusal_frees_its_place_in_the_limits() {
+        let dir = temp_dir("sink_refused");
+        let a = write_file(&dir, "a.o", 60);
+        let b = write_file(&dir, "b.o", 60);
+        let mut sink = sink(Some(AutoApply::All));
+
+        assert!(sink.suggest(ai(&a), later()).unwrap().is_some());
+        sink.report(DbReport::DeleteRefused { path: a, reason: "it changed".into() }, later()).unwrap();
+
+        assert!(sink.suggest(ai(&b), later()).unwrap().is_some(), "the refused file no longer counts");
+        assert_eq!(sink.refused, 1);
+    }
+
+    #[test]
+    fn test_done_once_finished_and_answered() {
+        let dir = temp_dir("sink_done");
+        let path = write_file(&dir, "a.o", 7);
+        let mut sink = sink(Some(AutoApply::All));
+        sink.suggest(ai(&path), later()).unwrap();
+
+        sink.report(DbReport::Finished, later()).unwrap();
+        assert!(!sink.is_done(), "a deletion is still unanswered");
+
+        sink.report(DbReport::Deleted { path, destination: dir.join("held") }, later()).unwrap();
+        assert!(sink.is_done());
+
+        sink.summary().unwrap();
+        assert!(text(&sink).ends_with("1 suggestion(s); deleted 1 file(s), 7 B freed; 0 refused\n"), "{}", text(&sink));
+    }
+
+    #[test]
+    fn test_start_line_states_the_policy() {
+        let mut reporting = sink(None);
+        reporting.start().unwrap();
+        assert_eq!(text(&reporting), "Headless review: suggestions are only reported\n");
+
+        let mut applying = sink(Some(AutoApply::Duplicates));
+        applying.start().unwrap();
+        assert!(text(&applying).contains("deleting duplicates — at most 2 file(s) and 100 B, untouched for 30 day(s)"));
+    }
+
+    // ── open_report ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_report_file_is_created_with_its_directory() {
+        let dir = temp_dir("open_report");
+        let file = dir.join("reports/run.txt");
+
+        writeln!(open_report(&file).unwrap(), "hello").unwrap();
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), "hello\n");
+    }
+}
@@ -1,8 +1,16 @@
 #![allow(unused)]
 
 use steady_state::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
+use std::time::Duration;
+use crate::duplicates::DuplicateGroup;
+use crate::actor::ai_model::{Verdict, VerdictSource};
+use crate::actor::db_manager::DbReport;
+use crate::protection::{ProtectScope, Protection};
+use crate::config::UiConfig;
+use crate::commands::human_size;
+use crate::trash::local_timestamp;
 
 use ratatui::{
     DefaultTerminal, Frame,
@@ -13,64 +21,155 @@ use ratatui::{
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
 };
 
+// How often the actor checks for work from the TUI thread, which can't wake it,
+// unless `[ui] poll-ms` says otherwise
+pub(crate) const TUI_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// How many review decisions `u` can take back, unless `[ui] undo-limit` says otherwise
+pub(crate) const UNDO_LIMIT: usize = 50;
+
+/// What the UI asks the DB actor to do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum UiRequest {
+    /// The user confirmed this file should be deleted.
+    Delete(PathBuf),
+    /// Never suggest this file again, nor anything else `scope` widens the mark to.
+    NeverDelete { path: PathBuf, scope: ProtectScope },
+    /// Undo a deletion: put the file back if it is still in quarantine.
+    Restore(PathBuf),
+    /// Undo a never-delete mark made with the same `path` and `scope`.
+    RemoveNeverDelete { path: PathBuf, scope: ProtectScope },
+    /// Send every never-delete mark, for the marks view.
+    ListMarks,
+    /// Lift a mark chosen in the marks view.
+    LiftMark(Protection),
+}
+
+/// A review decision, as remembered for undo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Decision {
+    Kept,
+    Deleted,
+    /// A deletion the DB actor refused, so the file never left its place.
+    Refused,
+    NeverDelete(ProtectScope),
+}
+
+/// What undoing a decision has to put back: the selected path and every
+/// suggestion the decision took off the list, with the position it had.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Undo {
+    decision: Decision,
+    path:     PathBuf,
+    removed:  Vec<(usize, Verdict)>,
+}
+
+/// What the actor tells the TUI thread besides new suggestions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Notice {
+    /// A message for the status bar.
+    Status(String),
+    /// A deletion the DB actor refused; the file is still where it was.
+    DeleteRefused { path: PathBuf, reason: String },
+    /// An undone deletion: the file is back where it was.
+    Restored(PathBuf),
+    /// An undone deletion that could not be put back.
+    RestoreFailed { path: PathBuf, reason: String },
+    /// Every never-delete mark, for the marks view.
+    Marks(Vec<Protection>),
+}
+
 pub async fn run(
     actor: SteadyActorShadow,
-    ai_model_to_ui_rx: SteadyRx<String>,
-    ui_to_db_tx: SteadyTx<PathBuf>,
+    ai_model_to_ui_rx: SteadyRx<Verdict>,
+    db_to_ui_rx: SteadyRx<DbReport>,
+    ui_to_db_tx: SteadyTx<UiRequest>,
+    config: UiConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let actor = actor.into_spotlight([&ai_model_to_ui_rx], [&ui_to_db_tx]);
+    let actor = actor.into_spotlight([&ai_model_to_ui_rx, &db_to_ui_rx], [&ui_to_db_tx]);
     if actor.use_internal_behavior {
-        internal_behavior(actor, ai_model_to_ui_rx, ui_to_db_tx).await
+        internal_behavior(actor, ai_model_to_ui_rx, db_to_ui_rx, ui_to_db_tx, config).await
     } else {
-        actor.simulated_behavior(vec![&ai_model_to_ui_rx]).await
+        actor.simulated_behavior(vec![&ai_model_to_ui_rx, &db_to_ui_rx]).await
     }
 }
 
 async fn internal_behavior<A: SteadyActor>(
     mut actor: A,
-    ai_model_to_ui_rx: SteadyRx<String>,
-    ui_to_db_tx: SteadyTx<PathBuf>,
+    ai_model_to_ui_rx: SteadyRx<Verdict>,
+    db_to_ui_rx: SteadyRx<DbReport>,
+    ui_to_db_tx: SteadyTx<UiRequest>,
+    config: UiConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut ai_model_to_ui_rx = ai_model_to_ui_rx.lock().await;
+    let mut db_to_ui_rx = db_to_ui_rx.lock().await;
     let mut ui_to_db_tx = ui_to_db_tx.lock().await;
 
     // actor → TUI thread: send new suggested files
-    let (suggest_tx, suggest_rx) = mpsc::channel::<PathBuf>();
-    // TUI thread → actor: send confirmed deletions
-    let (delete_tx, delete_rx) = mpsc::channel::<PathBuf>();
+    let (suggest_tx, suggest_rx) = mpsc::channel::<Verdict>();
+    // TUI thread → actor: send confirmed deletions and never-delete marks
+    let (request_tx, request_rx) = mpsc::channel::<UiRequest>();
+    // actor → TUI thread: status messages, e.g. a deletion the DB refused, and restores
+    let (notice_tx, notice_rx) = mpsc::channel::<Notice>();
 
     // Spawn TUI on a plain OS thread (no Tokio reactor needed)
+    let tui_config = config.clone();
 	std::thread::spawn(move || {
 		let mut terminal = ratatui::init();
-		let result = run_tui(&mut terminal, suggest_rx, delete_tx);
+		let result = run_tui(&mut terminal, suggest_rx, request_tx, notice_rx, &tui_config);
 		ratatui::restore();
 		if let Err(e) = result {
 			eprintln!("TUI error: {}", e);
 		}
 	});
 
-    while actor.is_running(|| ai_model_to_ui_rx.is_closed_and_empty()) {
-        // Forward AI verdicts to the TUI thread
-        while let Some(message) = actor.try_take(&mut ai_model_to_ui_rx) {
-            //eprintln!("UI received message: {:?}", message);  // ← add this
-            if let Some((verdict, path_str)) = message.split_once('|') {
-               // eprintln!("Verdict: {:?}, Path: {:?}", verdict, path_str);  // ← add this
-                if verdict.trim() == "delete" {
-                    //eprintln!("Sending to TUI: {:?}", path_str);  // ← add this
-                    let _ = suggest_tx.send(PathBuf::from(path_str.trim()));
+    while actor.is_running(|| ai_model_to_ui_rx.is_closed_and_empty() && db_to_ui_rx.is_closed_and_empty()) {
+        // Forward the files the AI wants deleted to the TUI thread
+        while let Some(verdict) = actor.try_take(&mut ai_model_to_ui_rx) {
+            if verdict.is_delete() {
+                let _ = suggest_tx.send(verdict);
+            }
+        }
+
+        // Forward duplicate groups and refused deletions from the DB actor
+        while let Some(report) = actor.try_take(&mut db_to_ui_rx) {
+            match report {
+                DbReport::Duplicates(group) => {
+                    for suggestion in Verdict::from_duplicates(&group) {
+                        let _ = suggest_tx.send(suggestion);
+                    }
+                }
+                // The status bar already said so when the user pressed `d`
+                DbReport::Deleted { .. } => {}
+                DbReport::DeleteRefused { path, reason } => {
+                    let _ = notice_tx.send(Notice::DeleteRefused { path, reason });
+                }
+                DbReport::Restored(path) => {
+                    let _ = notice_tx.send(Notice::Restored(path));
+                }
+                DbReport::RestoreFailed { path, reason } => {
+                    let _ = notice_tx.send(Notice::RestoreFailed { path, reason });
+                }
+                DbReport::Marks(marks) => {
+                    let _ = notice_tx.send(Notice::Marks(marks));
+                }
+                DbReport::PlanWritten { path, files, reclaimable } => {
+                    let _ = notice_tx.send(Notice::Status(format!("Dry run: plan of {} file(s), {} reclaimable, written to {:?}",
+                                                                  files, human_size(reclaimable), path)));
+                }
+                DbReport::Finished => {
+                    let _ = notice_tx.send(Notice::Status("Every file has been reviewed".to_string()));
                 }
-            } else {
-                eprintln!("Message did not split on '|': {:?}", message);  // ← add this
             }
         }
 
-        // Forward confirmed deletions to DB actor
-        while let Ok(path) = delete_rx.try_recv() {
+        // Forward confirmed deletions and never-delete marks to DB actor
+        while let Ok(request) = request_rx.try_recv() {
             actor.wait_vacant(&mut ui_to_db_tx, 1).await;
-            actor.try_send(&mut ui_to_db_tx, path);
+            actor.try_send(&mut ui_to_db_tx, request);
         }
 
-        actor.wait_avail(&mut ai_model_to_ui_rx, 1).await; 
+        actor.wait_periodic(config.poll_interval()).await;
     }
 
     Ok(())
@@ -79,15 +178,28 @@ async fn internal_behavior<A: SteadyActor>(
 // ── TUI App State ────────────────────────────────────────────────────────────
 
 struct App {
-    suggested_files: Vec<PathBuf>,
+    suggested_files: Vec<Verdict>,
     list_state: ListState,
     status: String,
-    suggest_rx: mpsc::Receiver<PathBuf>,
-    delete_tx: mpsc::Sender<PathBuf>,
+    suggest_rx: mpsc::Receiver<Verdict>,
+    request_tx: mpsc::Sender<UiRequest>,
+    notice_rx: mpsc::Receiver<Notice>,
+    // most recent decision last, at most undo_limit
+    undo_stack: Vec<Undo>,
+    undo_limit: usize,
+    // undone deletions waiting for the DB actor to put the file back
+    restoring: Vec<Undo>,
+    // the never-delete marks, while the marks view is open
+    marks: Option<Vec<Protection>>,
+    marks_state: ListState,
 }
 
 impl App {
-    fn new(suggest_rx: mpsc::Receiver<PathBuf>, delete_tx: mpsc::Sender<PathBuf>) -> Self {
+    fn new(
+        suggest_rx: mpsc::Receiver<Verdict>,
+        request_tx: mpsc::Sender<UiRequest>,
+        notice_rx: mpsc::Receiver<Notice>,
+    ) -> Self {
         let mut list_state = ListState::default();
         list_state.select(Some(0));
         Self {
@@ -95,15 +207,29 @@ impl App {
             list_state,
             status: String::from("Waiting for AI suggestions..."),
             suggest_rx,
-            delete_tx,
+            request_tx,
+            notice_rx,
+            undo_stack: Vec::new(),
+            undo_limit: UNDO_LIMIT,
+            restoring: Vec::new(),
+            marks: None,
+            marks_state: ListState::default(),
         }
     }
 
-    fn selected_path(&self) -> Option<PathBuf> {
+    fn with_undo_limit(mut self, undo_limit: usize) -> Self {
+        self.undo_limit = undo_limit.max(1);
+        self
+    }
+
+    fn selected(&self) -> Option<&Verdict> {
         self.list_state
             .selected()
             .and_then(|i| self.suggested_files.get(i))
-            .cloned()
+    }
+
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.selected().map(|s| s.path().to_path_buf())
     }
 
     fn clamp_selection(&mut self) {
@@ -135,11 +261,29 @@ impl App {
         }
     }
 
+    // Takes every covered suggestion off the list, remembering where each was
+    fn remove_where(&mut self, covered: impl Fn(&Verdict) -> bool) -> Vec<(usize, Verdict)> {
+        let (removed, remaining): (Vec<_>, Vec<_>) = std::mem::take(&mut self.suggested_files)
+            .into_iter()
+            .enumerate()
+            .partition(|(_, s)| covered(s));
+        self.suggested_files = remaining.into_iter().map(|(_, s)| s).collect();
+        removed
+    }
+
+    fn remember(&mut self, decision: Decision, path: PathBuf, removed: Vec<(usize, Verdict)>) {
+        if self.undo_stack.len() >= self.undo_limit {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(Undo { decision, path, removed });
+    }
+
     fn delete_selected(&mut self) {
         if let Some(path) = self.selected_path() {
-            self.suggested_files.retain(|p| *p != path);
-            let _ = self.delete_tx.send(path.clone());
+            let removed = self.remove_where(|s| s.path() == path);
+            let _ = self.request_tx.send(UiRequest::Delete(path.clone()));
             self.status = format!("Deleted: {:?}", path);
+            self.remember(Decision::Deleted, path, removed);
             self.clamp_selection();
         } else {
             self.status = String::from("No file selected.");
@@ -148,62 +292,237 @@ impl App {
 
     fn keep_selected(&mut self) {
         if let Some(path) = self.selected_path() {
-            self.suggested_files.retain(|p| *p != path);
+            let removed = self.remove_where(|s| s.path() == path);
             self.status = format!("Kept: {:?}", path);
+            self.remember(Decision::Kept, path, removed);
             self.clamp_selection();
         } else {
             self.status = String::from("No file selected.");
         }
     }
 
-    fn never_delete_selected(&mut self) {
+    /// Marks the selected file, every copy of its content, or its whole folder
+    /// as never-delete, and drops every listed suggestion the mark covers.
+    fn never_delete_selected(&mut self, scope: ProtectScope) {
         if let Some(path) = self.selected_path() {
-            self.suggested_files.retain(|p| *p != path);
-            self.status = format!("Marked never-delete: {:?}", path);
-            // TODO: persist to sled DB
+            let folder = path.parent().map(PathBuf::from).unwrap_or_default();
+            let removed = self.remove_where(|s| match scope {
+                ProtectScope::File => s.path() == path,
+                ProtectScope::Directory => s.path().starts_with(&folder),
+                ProtectScope::Content => s.path() == path || matches!(
+                    &s.source,
+                    VerdictSource::Duplicate { members, .. } if members.contains(&path)
+                ),
+            });
+            let _ = self.request_tx.send(UiRequest::NeverDelete { path: path.clone(), scope });
+            self.status = match scope {
+                ProtectScope::File => format!("Marked never-delete: {:?}", path),
+                ProtectScope::Content => format!("Marked never-delete, every copy of: {:?}", path),
+                ProtectScope::Directory => format!("Marked never-delete, whole folder: {:?}", folder),
+            };
+            self.remember(Decision::NeverDelete(scope), path, removed);
             self.clamp_selection();
         } else {
             self.status = String::from("No file selected.");
         }
     }
 
+    /// Takes back the most recent decision: its suggestions go back where they
+    /// were and a never-delete mark made by it is lifted. A deleted file is
+    /// restored from quarantine by the DB actor, and only listed again once
+    /// the DB actor reports it is back; one the DB actor refused to delete is
+    /// listed again straight away.
+    fn undo_last(&mut self) {
+        let Some(undo) = self.undo_stack.pop() else {
+            self.status = String::from("Nothing to undo.");
+            return;
+        };
+
+        self.status = match undo.decision {
+            Decision::Kept => format!("Undone keep: {:?}", undo.path),
+            Decision::Refused => format!("Undone delete, which was refused: {:?}", undo.path),
+            Decision::Deleted => {
+                let _ = self.request_tx.send(UiRequest::Restore(undo.path.clone()));
+                self.status = format!("Restoring: {:?}", undo.path);
+                self.restoring.push(undo);
+                return;
+            }
+            Decision::NeverDelete(scope) => {
+                let _ = self.request_tx.send(UiRequest::RemoveNeverDelete { path: undo.path.clone(), scope });
+                format!("Undone never-delete: {:?}", undo.path)
+            }
+        };
+        self.put_back(undo);
+    }
+
+    // Lists the suggestions a decision took off again, selecting its own file
+    fn put_back(&mut self, undo: Undo) {
+        let mut selected = None;
+        for (position, suggestion) in undo.removed {
+            let position = position.min(self.suggested_files.len());
+            if suggestion.path() == undo.path {
+                selected = Some(position);
+            }
+            self.suggested_files.insert(position, suggestion);
+        }
+
+        if selected.is_some() {
+            self.list_state.select(selected);
+        }
+        self.clamp_selection();
+    }
+
+    // The latest undone deletion of `path` still waiting on the DB actor
+    fn take_restoring(&mut self, path: &Path) -> Option<Undo> {
+        let i = self.restoring.iter().rposition(|undo| undo.path == path)?;
+        Some(self.restoring.remove(i))
+    }
+
+    // A refused deletion never left its file in quarantine, so undoing it
+    // must not wait on a restore. When the undo already asked for one, the
+    // entry is listed again now instead.
+    fn deletion_refused(&mut self, path: &Path) {
+        if let Some(undo) = self.take_restoring(path) {
+            self.put_back(undo);
+        } else if let Some(undo) = self.undo_stack.iter_mut().rev().find(|undo| undo.path == path)
+            && undo.decision == Decision::Deleted {
+            undo.decision = Decision::Refused;
+        }
+    }
+
+    /// Opens the never-delete marks view, asking the DB actor for the marks,
+    /// or closes it again.
+    fn toggle_marks(&mut self) {
+        if self.marks.take().is_some() {
+            self.status = String::from("Back to suggestions.");
+            return;
+        }
+        let _ = self.request_tx.send(UiRequest::ListMarks);
+        self.marks = Some(Vec::new());
+        self.marks_state.select(None);
+        self.status = String::from("Loading never-delete marks...");
+    }
+
+    fn selected_mark(&self) -> Option<&Protection> {
+        self.marks_state.selected().and_then(|i| self.marks.as_ref()?.get(i))
+    }
+
+    fn mark_up(&mut self) {
+        if let Some(i) = self.marks_state.selected() && i > 0 {
+            self.marks_state.select(Some(i - 1));
+        }
+    }
+
+    fn mark_down(&mut self) {
+        let len = self.marks.as_ref().map_or(0, Vec::len);
+        if let Some(i) = self.marks_state.selected() && i + 1 < len {
+            self.marks_state.select(Some(i + 1));
+        }
+    }
+
+    /// Lifts the mark selected in the marks view. Files it covered are
+    /// suggested again from the next review on.
+    fn lift_selected_mark(&mut self) {
+        let Some(mark) = self.selected_mark().cloned() else {
+            self.status = String::from("No mark selected.");
+            return;
+        };
+        let _ = self.request_tx.send(UiRequest::LiftMark(mark.clone()));
+        self.status = format!("Lifted never-delete mark on {}", mark);
+
+        let marks = self.marks.get_or_insert_default();
+        marks.retain(|m| *m != mark);
+        let last = marks.len().checked_sub(1);
+        self.marks_state.select(self.marks_state.selected().zip(last).map(|(i, last)| i.min(last)));
+    }
+
     // Pull any new suggestions from the actor
     fn poll_suggestions(&mut self) {
-        while let Ok(path) = self.suggest_rx.try_recv() {
-            self.suggested_files.push(path);
+        while let Ok(suggestion) = self.suggest_rx.try_recv() {
+            self.suggested_files.push(suggestion);
             if self.list_state.selected().is_none() {
                 self.list_state.select(Some(0));
             }
         }
     }
+
+    // Show the latest message from the actor in the status bar, and list an
+    // undone deletion again once its file has been restored
+    fn poll_notices(&mut self) {
+        while let Ok(notice) = self.notice_rx.try_recv() {
+            self.status = match notice {
+                Notice::Status(message) => message,
+                Notice::DeleteRefused { path, reason } => {
+                    self.deletion_refused(&path);
+                    format!("Not deleted: {:?} — {}", path, reason)
+                }
+                Notice::Restored(path) => {
+                    if let Some(undo) = self.take_restoring(&path) {
+                        self.put_back(undo);
+                    }
+                    format!("Restored from quarantine: {:?}", path)
+                }
+                Notice::RestoreFailed { path, reason } => {
+                    self.take_restoring(&path);
+                    format!("Not restored: {:?} — {}", path, reason)
+                }
+                // Only wanted while the marks view is still open
+                Notice::Marks(_) if self.marks.is_none() => continue,
+                Notice::Marks(marks) => {
+                    self.marks_state.select((!marks.is_empty()).then_some(0));
+                    let status = format!("{} never-delete mark(s)", marks.len());
+                    self.marks = Some(marks);
+                    status
+                }
+            };
+        }
+    }
 }
 
 // ── TUI Render + Event Loop ──────────────────────────────────────────────────
 
 fn run_tui(
     terminal: &mut DefaultTerminal,
-    suggest_rx: mpsc::Receiver<PathBuf>,
-    delete_tx: mpsc::Sender<PathBuf>,
+    suggest_rx: mpsc::Receiver<Verdict>,
+    request_tx: mpsc::Sender<UiRequest>,
+    notice_rx: mpsc::Receiver<Notice>,
+    config: &UiConfig,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let mut app = App::new(suggest_rx, delete_tx);
+    let mut app = App::new(suggest_rx, request_tx, notice_rx).with_undo_limit(config.undo_limit);
 
     loop {
         app.poll_suggestions();
+        app.poll_notices();
         terminal.draw(|frame| render(frame, &mut app))?;
 
         // Poll for key events with a short timeout so we keep polling suggestions
-        if event::poll(std::time::Duration::from_millis(100))? {
+        if event::poll(config.poll_interval())? {
             if let Event::Key(key) = event::read()? {
                 if key.kind != KeyEventKind::Press {
                     continue;
                 }
+                if app.marks.is_some() {
+                    match key.code {
+                        KeyCode::Char('q') => break,
+                        KeyCode::Up        => app.mark_up(),
+                        KeyCode::Down      => app.mark_down(),
+                        KeyCode::Char('l') => app.lift_selected_mark(),
+                        KeyCode::Char('m') | KeyCode::Esc => app.toggle_marks(),
+                        _ => {}
+                    }
+                    continue;
+                }
                 match key.code {
                     KeyCode::Char('q') => break,
                     KeyCode::Up       => app.move_up(),
                     KeyCode::Down     => app.move_down(),
                     KeyCode::Char('d') => app.delete_selected(),
                     KeyCode::Char('k') => app.keep_selected(),
-                    KeyCode::Char('n') => app.never_delete_selected(),
+                    KeyCode::Char('n') => app.never_delete_selected(ProtectScope::File),
+                    KeyCode::Char('c') => app.never_delete_selected(ProtectScope::Content),
+                    KeyCode::Char('f') => app.never_delete_selected(ProtectScope::Directory),
+                    KeyCode::Char('u') => app.undo_last(),
+                    KeyCode::Char('m') => app.toggle_marks(),
                     _ => {}
                 }
             }
@@ -214,21 +533,35 @@ fn run_tui(
 }
 
 fn render(frame: &mut Frame, app: &mut App) {
+    if app.marks.is_some() {
+        return render_marks(frame, app);
+    }
+
     let vertical = Layout::vertical([
         Constraint::Min(0),    // file list
+        Constraint::Length(6), // details of the selected entry
         Constraint::Length(1), // key hints
         Constraint::Length(1), // status bar
     ]);
-    let [list_area, hints_area, status_area] = vertical.areas(frame.area());
+    let [list_area, details_area, hints_area, status_area] = vertical.areas(frame.area());
 
     // ── File list ────────────────────────────────────────────────────────────
     let items: Vec<ListItem> = app
         .suggested_files
         .iter()
         .enumerate()
-        .map(|(i, path)| {
-            let label = format!("[{}] {}", i + 1, path.display());
-            ListItem::new(label)
+        .map(|(i, suggestion)| {
+            let tag = match suggestion.source {
+                VerdictSource::Ai => Span::from(format!("[ai {:>3}%] ", suggestion.confidence)).fg(Color::Magenta),
+                VerdictSource::Rule => Span::from("[rule]    ").fg(Color::Green),
+                VerdictSource::Duplicate { .. } => Span::from("[dup]     ").fg(Color::Blue),
+            };
+            ListItem::new(Line::from(vec![
+                Span::from(format!("[{}] ", i + 1)),
+                tag,
+                Span::from(format!("{:>10}  ", human_size(suggestion.meta.size))),
+                Span::from(suggestion.path().display().to_string()),
+            ]))
         })
         .collect();
 
@@ -244,12 +577,47 @@ fn render(frame: &mut Frame, app: &mut App) {
 
     frame.render_stateful_widget(list, list_area, &mut app.list_state);
 
+    // ── Details ──────────────────────────────────────────────────────────────
+    let details: Vec<Line> = match app.selected() {
+        Some(Verdict { source: VerdictSource::Duplicate { keeper, members }, .. }) => {
+            let mut lines = vec![Line::from(format!(
+                "Identical copy — {} files in group, keeping {}",
+                members.len(),
+                keeper.display()
+            ))];
+            lines.extend(members.iter().map(|m| {
+                let marker = if m == keeper { "  keep  " } else { "  copy  " };
+                Line::from(format!("{}{}", marker, m.display()))
+            }));
+            lines
+        }
+        Some(verdict) => {
+            let by = if verdict.source == VerdictSource::Rule { "a rule" } else { "the AI model" };
+            let mut lines = vec![
+                Line::from(format!("Suggested by {}, {}% sure", by, verdict.confidence)),
+                Line::from(format!("{}, last modified {}",
+                                   human_size(verdict.meta.size), local_timestamp(verdict.meta.modified))),
+            ];
+            if !verdict.reasoning.is_empty() {
+                lines.push(Line::from(format!("Why: {}", verdict.reasoning)));
+            }
+            lines
+        }
+        None => Vec::new(),
+    };
+    let details = Paragraph::new(details).block(Block::bordered().title(" Details "));
+    frame.render_widget(details, details_area);
+
     // ── Key hints ────────────────────────────────────────────────────────────
     let hints = Line::from(vec![
         " (↑↓) navigate ".into(),
         " (d) delete ".bold().fg(Color::Red),
         " (k) keep ".bold().fg(Color::Green),
         " (n) never-delete ".bold().fg(Color::Cyan),
+        " (c) …every copy ".bold().fg(Color::Cyan),
+        " (f) …whole folder ".bold().fg(Color::Cyan),
+        " (u) undo ".bold().fg(Color::Yellow),
+        " (m) marks ".bold().fg(Color::Cyan),
         " (q) quit ".bold().fg(Color::Gray),
     ]);
     frame.render_widget(hints, hints_area);
@@ -259,6 +627,54 @@ fn render(frame: &mut Frame, app: &mut App) {
     frame.render_widget(status, status_area);
 }
 
+// The never-delete marks view, in place of the suggestions
+fn render_marks(frame: &mut Frame, app: &mut App) {
+    let vertical = Layout::vertical([
+        Constraint::Min(0),    // marks
+        Constraint::Length(3), // what the selected mark covers
+        Constraint::Length(1), // key hints
+        Constraint::Length(1), // status bar
+    ]);
+    let [list_area, details_area, hints_area, status_area] = vertical.areas(frame.area());
+
+    let items: Vec<ListItem> = app
+        .marks
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, mark)| ListItem::new(format!("[{}] {}", i + 1, mark)))
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title(" CruftCrawler — Never-delete Marks "))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("▶ ");
+    frame.render_stateful_widget(list, list_area, &mut app.marks_state);
+
+    let covers = match app.selected_mark() {
+        Some(Protection::Path(_)) => "Covers this file only",
+        Some(Protection::Content { .. }) => "Covers every file with this content, wherever it is",
+        Some(Protection::Dir(_)) => "Covers this folder and everything below it",
+        None => "",
+    };
+    frame.render_widget(Paragraph::new(covers).block(Block::bordered().title(" Details ")), details_area);
+
+    let hints = Line::from(vec![
+        " (↑↓) navigate ".into(),
+        " (l) lift mark ".bold().fg(Color::Cyan),
+        " (m) back ".bold().fg(Color::Yellow),
+        " (q) quit ".bold().fg(Color::Gray),
+    ]);
+    frame.render_widget(hints, hints_area);
+
+    let status = Paragraph::new(app.status.as_str()).fg(Color::DarkGray);
+    frame.render_widget(status, status_area);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -268,26 +684,46 @@ mod tests {
     // ── helpers ───────────────────────────────────────────────────────────────
 
     fn make_app() -> App {
-        let (suggest_tx, suggest_rx) = mpsc::channel::<PathBuf>();
-        let (delete_tx, delete_rx) = mpsc::channel::<PathBuf>();
-        // We keep suggest_tx and delete_rx alive in the returned app;
+        let (suggest_tx, suggest_rx) = mpsc::channel::<Verdict>();
+        let (request_tx, request_rx) = mpsc::channel::<UiRequest>();
+        // We keep suggest_tx and request_rx alive in the returned app;
         // leak them so they don't close the channels mid-test.
         std::mem::forget(suggest_tx);
-        std::mem::forget(delete_rx);
-        App::new(suggest_rx, delete_tx)
+        std::mem::forget(request_rx);
+        let (_, notice_rx) = mpsc::channel::<Notice>();
+        App::new(suggest_rx, request_tx, notice_rx)
+    }
+
+    fn make_app_with_channels() -> (App, mpsc::Sender<Verdict>, mpsc::Receiver<UiRequest>) {
+        let (suggest_tx, suggest_rx) = mpsc::channel::<Verdict>();
+        let (request_tx, request_rx) = mpsc::channel::<UiRequest>();
+        let (_, notice_rx) = mpsc::channel::<Notice>();
+        let app = App::new(suggest_rx, request_tx, notice_rx);
+        (app, suggest_tx, request_rx)
     }
 
-    fn make_app_with_channels() -> (App, mpsc::Sender<PathBuf>, mpsc::Receiver<PathBuf>) {
-        let (suggest_tx, suggest_rx) = mpsc::channel::<PathBuf>();
-        let (delete_tx, delete_rx) = mpsc::channel::<PathBuf>();
-        let app = App::new(suggest_rx, delete_tx);
-        (app, suggest_tx, delete_rx)
+    fn make_app_with_notices() -> (App, mpsc::Sender<Notice>, mpsc::Receiver<UiRequest>) {
+        let (_, suggest_rx) = mpsc::channel::<Verdict>();
+        let (request_tx, request_rx) = mpsc::channel::<UiRequest>();
+        let (notice_tx, notice_rx) = mpsc::channel::<Notice>();
+        (App::new(suggest_rx, request_tx, notice_rx), notice_tx, request_rx)
     }
 
     fn path(s: &str) -> PathBuf {
         PathBuf::from(s)
     }
 
+    /// The model's delete verdict on `p`.
+    fn ai(p: &str) -> Verdict {
+        Verdict {
+            meta: dup_meta(p, 0),
+            decision: crate::actor::ai_model::Decision::Delete,
+            confidence: 90,
+            reasoning: String::new(),
+            source: VerdictSource::Ai,
+        }
+    }
+
     // ── App::new ──────────────────────────────────────────────────────────────
 
     #[test]
@@ -315,8 +751,8 @@ mod tests {
     #[test]
     fn test_selected_path_returns_correct_item() {
         let mut app = make_app();
-        app.suggested_files.push(path("/tmp/a.txt"));
-        app.suggested_files.push(path("/tmp/b.txt"));
+        app.suggested_files.push(ai("/tmp/a.txt"));
+        app.suggested_files.push(ai("/tmp/b.txt"));
         app.list_state.select(Some(1));
         assert_eq!(app.selected_path(), Some(path("/tmp/b.txt")));
     }
@@ -334,8 +770,8 @@ mod tests {
     #[test]
     fn test_clamp_selection_out_of_bounds_clamps_to_last() {
         let mut app = make_app();
-        app.suggested_files.push(path("/tmp/a.txt"));
-        app.suggested_files.push(path("/tmp/b.txt"));
+        app.suggested_files.push(ai("/tmp/a.txt"));
+        app.suggested_files.push(ai("/tmp/b.txt"));
         app.list_state.select(Some(99));
         app.clamp_selection();
         assert_eq!(app.list_state.selected(), Some(1));
@@ -344,7 +780,7 @@ mod tests {
     #[test]
     fn test_clamp_selection_none_selects_first() {
         let mut app = make_app();
-        app.suggested_files.push(path("/tmp/a.txt"));
+        app.suggested_files.push(ai("/tmp/a.txt"));
         app.list_state.select(None);
         app.clamp_selection();
         assert_eq!(app.list_state.selected(), Some(0));
@@ -353,9 +789,9 @@ mod tests {
     #[test]
     fn test_clamp_selection_in_bounds_unchanged() {
         let mut app = make_app();
-        app.suggested_files.push(path("/a.txt"));
-        app.suggested_files.push(path("/b.txt"));
-        app.suggested_files.push(path("/c.txt"));
+        app.suggested_files.push(ai("/a.txt"));
+        app.suggested_files.push(ai("/b.txt"));
+        app.suggested_files.push(ai("/c.txt"));
         app.list_state.select(Some(1));
         app.clamp_selection();
         assert_eq!(app.list_state.selected(), Some(1));
@@ -366,8 +802,8 @@ mod tests {
     #[test]
     fn test_move_up_decrements_selection() {
         let mut app = make_app();
-        app.suggested_files.push(path("/a.txt"));
-        app.suggested_files.push(path("/b.txt"));
+        app.suggested_files.push(ai("/a.txt"));
+        app.suggested_files.push(ai("/b.txt"));
         app.list_state.select(Some(1));
         app.move_up();
         assert_eq!(app.list_state.selected(), Some(0));
@@ -376,7 +812,7 @@ mod tests {
     #[test]
     fn test_move_up_at_zero_stays_zero() {
         let mut app = make_app();
-        app.suggested_files.push(path("/a.txt"));
+        app.suggested_files.push(ai("/a.txt"));
         app.list_state.select(Some(0));
         app.move_up();
         assert_eq!(app.list_state.selected(), Some(0));
@@ -385,8 +821,8 @@ mod tests {
     #[test]
     fn test_move_down_increments_selection() {
         let mut app = make_app();
-        app.suggested_files.push(path("/a.txt"));
-        app.suggested_files.push(path("/b.txt"));
+        app.suggested_files.push(ai("/a.txt"));
+        app.suggested_files.push(ai("/b.txt"));
         app.list_state.select(Some(0));
         app.move_down();
         assert_eq!(app.list_state.selected(), Some(1));
@@ -395,8 +831,8 @@ mod tests {
     #[test]
     fn test_move_down_at_last_stays_at_last() {
         let mut app = make_app();
-        app.suggested_files.push(path("/a.txt"));
-        app.suggested_files.push(path("/b.txt"));
+        app.suggested_files.push(ai("/a.txt"));
+        app.suggested_files.push(ai("/b.txt"));
         app.list_state.select(Some(1));
         app.move_down();
         assert_eq!(app.list_state.selected(), Some(1));
@@ -406,8 +842,8 @@ mod tests {
 
     #[test]
     fn test_delete_selected_removes_file_and_sends_to_channel() {
-        let (mut app, _, delete_rx) = make_app_with_channels();
-        app.suggested_files.push(path("/tmp/del.txt"));
+        let (mut app, _, request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/tmp/del.txt"));
         app.list_state.select(Some(0));
 
         app.delete_selected();
@@ -415,8 +851,8 @@ mod tests {
         assert!(app.suggested_files.is_empty());
         assert!(app.status.contains("Deleted"));
         // Confirm the path was forwarded to the delete channel
-        let received = delete_rx.try_recv().expect("path should have been sent");
-        assert_eq!(received, path("/tmp/del.txt"));
+        let received = request_rx.try_recv().expect("path should have been sent");
+        assert_eq!(received, UiRequest::Delete(path("/tmp/del.txt")));
     }
 
     #[test]
@@ -429,10 +865,10 @@ mod tests {
 
     #[test]
     fn test_delete_selected_clamps_after_removal() {
-        let (mut app, _, _delete_rx) = make_app_with_channels();
-        app.suggested_files.push(path("/a.txt"));
-        app.suggested_files.push(path("/b.txt"));
-        app.suggested_files.push(path("/c.txt"));
+        let (mut app, _, _request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/a.txt"));
+        app.suggested_files.push(ai("/b.txt"));
+        app.suggested_files.push(ai("/c.txt"));
         app.list_state.select(Some(2)); // last item
 
         app.delete_selected();
@@ -446,8 +882,8 @@ mod tests {
 
     #[test]
     fn test_keep_selected_removes_file_without_sending_to_delete() {
-        let (mut app, _, delete_rx) = make_app_with_channels();
-        app.suggested_files.push(path("/tmp/keep.txt"));
+        let (mut app, _, request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/tmp/keep.txt"));
         app.list_state.select(Some(0));
 
         app.keep_selected();
@@ -455,7 +891,7 @@ mod tests {
         assert!(app.suggested_files.is_empty());
         assert!(app.status.contains("Kept"));
         // Nothing should have been sent to the delete channel
-        assert!(delete_rx.try_recv().is_err());
+        assert!(request_rx.try_recv().is_err());
     }
 
     #[test]
@@ -470,22 +906,228 @@ mod tests {
 
     #[test]
     fn test_never_delete_removes_file_from_list() {
-        let (mut app, _, _) = make_app_with_channels();
-        app.suggested_files.push(path("/tmp/never.txt"));
+        let (mut app, _, _request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/tmp/never.txt"));
         app.list_state.select(Some(0));
 
-        app.never_delete_selected();
+        app.never_delete_selected(ProtectScope::File);
 
         assert!(app.suggested_files.is_empty());
         assert!(app.status.contains("never-delete"));
     }
 
+    #[test]
+    fn test_never_delete_sends_mark_to_db() {
+        let (mut app, _, request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/tmp/never.txt"));
+        app.list_state.select(Some(0));
+
+        app.never_delete_selected(ProtectScope::Content);
+
+        assert_eq!(
+            request_rx.try_recv().unwrap(),
+            UiRequest::NeverDelete { path: path("/tmp/never.txt"), scope: ProtectScope::Content }
+        );
+    }
+
+    #[test]
+    fn test_never_delete_folder_drops_everything_below_it() {
+        let (mut app, _, _request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/photos/a.jpg"));
+        app.suggested_files.push(ai("/photos/2020/b.jpg"));
+        app.suggested_files.push(ai("/tmp/c.tmp"));
+        app.list_state.select(Some(0));
+
+        app.never_delete_selected(ProtectScope::Directory);
+
+        assert_eq!(app.suggested_files, vec![ai("/tmp/c.tmp")]);
+        assert!(app.status.contains("/photos"));
+    }
+
     #[test]
     fn test_never_delete_nothing_selected_updates_status() {
-        let mut app = make_app();
+        let (mut app, _, request_rx) = make_app_with_channels();
         app.list_state.select(None);
-        app.never_delete_selected();
+        app.never_delete_selected(ProtectScope::File);
         assert_eq!(app.status, "No file selected.");
+        assert!(request_rx.try_recv().is_err());
+    }
+
+    // ── undo_last ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_undo_keep_puts_file_back_in_place() {
+        let (mut app, _, request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/a.txt"));
+        app.suggested_files.push(ai("/b.txt"));
+        app.suggested_files.push(ai("/c.txt"));
+        app.list_state.select(Some(1));
+
+        app.keep_selected();
+        app.undo_last();
+
+        let paths: Vec<_> = app.suggested_files.iter().map(|s| s.path().to_path_buf()).collect();
+        assert_eq!(paths, vec![path("/a.txt"), path("/b.txt"), path("/c.txt")]);
+        assert_eq!(app.selected_path(), Some(path("/b.txt")));
+        assert!(app.status.contains("Undone keep"));
+        assert!(request_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_undo_delete_asks_db_to_restore() {
+        let (mut app, notice_tx, request_rx) = make_app_with_notices();
+        app.suggested_files.push(ai("/tmp/del.txt"));
+        app.list_state.select(Some(0));
+
+        app.delete_selected();
+        let _ = request_rx.try_recv();
+        app.undo_last();
+
+        assert_eq!(request_rx.try_recv().unwrap(), UiRequest::Restore(path("/tmp/del.txt")));
+        assert!(app.suggested_files.is_empty(), "not listed until it is back");
+        assert!(app.status.contains("Restoring"));
+
+        notice_tx.send(Notice::Restored(path("/tmp/del.txt"))).unwrap();
+        app.poll_notices();
+        assert_eq!(app.suggested_files, vec![ai("/tmp/del.txt")]);
+        assert_eq!(app.selected_path(), Some(path("/tmp/del.txt")));
+        assert!(app.status.starts_with("Restored from quarantine"));
+    }
+
+    #[test]
+    fn test_undo_delete_that_cannot_be_restored_stays_off_the_list() {
+        // A trashed deletion has nothing in quarantine to restore
+        let (mut app, notice_tx, _request_rx) = make_app_with_notices();
+        app.suggested_files.push(ai("/tmp/trashed.txt"));
+        app.list_state.select(Some(0));
+
+        app.delete_selected();
+        app.undo_last();
+        notice_tx.send(Notice::RestoreFailed {
+            path: path("/tmp/trashed.txt"),
+            reason: "it was not moved to quarantine in this session".to_string(),
+        }).unwrap();
+        app.poll_notices();
+
+        assert!(app.suggested_files.is_empty());
+        assert!(app.restoring.is_empty());
+        assert!(app.status.starts_with("Not restored"));
+    }
+
+    #[test]
+    fn test_undo_refused_delete_lists_it_again_without_a_restore() {
+        let (mut app, notice_tx, request_rx) = make_app_with_notices();
+        app.suggested_files.push(ai("/tmp/busy.txt"));
+        app.list_state.select(Some(0));
+
+        app.delete_selected();
+        let _ = request_rx.try_recv();
+        notice_tx.send(Notice::DeleteRefused { path: path("/tmp/busy.txt"), reason: "it is open".to_string() }).unwrap();
+        app.poll_notices();
+        assert!(app.status.starts_with("Not deleted"));
+
+        app.undo_last();
+        assert!(request_rx.try_recv().is_err(), "nothing to restore");
+        assert_eq!(app.suggested_files, vec![ai("/tmp/busy.txt")]);
+        assert!(app.restoring.is_empty());
+    }
+
+    #[test]
+    fn test_refusal_after_undo_lists_it_again() {
+        let (mut app, notice_tx, request_rx) = make_app_with_notices();
+        app.suggested_files.push(ai("/tmp/busy.txt"));
+        app.list_state.select(Some(0));
+
+        app.delete_selected();
+        app.undo_last();
+        let _ = request_rx.try_recv();
+        notice_tx.send(Notice::DeleteRefused { path: path("/tmp/busy.txt"), reason: "it is open".to_string() }).unwrap();
+        app.poll_notices();
+
+        assert_eq!(app.suggested_files, vec![ai("/tmp/busy.txt")]);
+        assert!(app.restoring.is_empty());
+    }
+
+    #[test]
+    fn test_undo_never_delete_folder_restores_every_entry() {
+        let (mut app, _, request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/photos/a.jpg"));
+        app.suggested_files.push(ai("/tmp/c.tmp"));
+        app.suggested_files.push(ai("/photos/2020/b.jpg"));
+        let before = app.suggested_files.clone();
+        app.list_state.select(Some(0));
+
+        app.never_delete_selected(ProtectScope::Directory);
+        let _ = request_rx.try_recv();
+        app.undo_last();
+
+        assert_eq!(app.suggested_files, before);
+        assert_eq!(
+            request_rx.try_recv().unwrap(),
+            UiRequest::RemoveNeverDelete { path: path("/photos/a.jpg"), scope: ProtectScope::Directory }
+        );
+    }
+
+    #[test]
+    fn test_undo_reverts_most_recent_first() {
+        let (mut app, _, _request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/a.txt"));
+        app.suggested_files.push(ai("/b.txt"));
+        app.list_state.select(Some(0));
+
+        app.keep_selected();                           // a
+        app.never_delete_selected(ProtectScope::File); // b
+        app.undo_last();
+
+        assert_eq!(app.suggested_files, vec![ai("/b.txt")]);
+        app.undo_last();
+        assert_eq!(app.suggested_files, vec![ai("/a.txt"), ai("/b.txt")]);
+    }
+
+    #[test]
+    fn test_undo_keeps_suggestions_that_arrived_since() {
+        let (mut app, suggest_tx, _request_rx) = make_app_with_channels();
+        app.suggested_files.push(ai("/a.txt"));
+        app.list_state.select(Some(0));
+
+        app.keep_selected();
+        suggest_tx.send(ai("/late.txt")).unwrap();
+        app.poll_suggestions();
+        app.undo_last();
+
+        assert_eq!(app.suggested_files, vec![ai("/a.txt"), ai("/late.txt")]);
+    }
+
+    #[test]
+    fn test_undo_with_empty_stack_updates_status() {
+        let mut app = make_app();
+        app.undo_last();
+        assert_eq!(app.status, "Nothing to undo.");
+    }
+
+    #[test]
+    fn test_undo_stack_is_bounded() {
+        let mut app = make_app();
+        for i in 0..UNDO_LIMIT + 5 {
+            app.suggested_files.push(ai(&format!("/{}.txt", i)));
+            app.list_state.select(Some(0));
+            app.keep_selected();
+        }
+        assert_eq!(app.undo_stack.len(), UNDO_LIMIT);
+        // the oldest decisions fell off the bottom
+        assert_eq!(app.undo_stack[0].path, path("/5.txt"));
+    }
+
+    #[test]
+    fn test_configured_undo_limit() {
+        let mut app = make_app().with_undo_limit(2);
+        for i in 0..4 {
+            app.suggested_files.push(ai(&format!("/{}.txt", i)));
+            app.list_state.select(Some(0));
+            app.keep_selected();
+        }
+        assert_eq!(app.undo_stack.len(), 2);
+        assert_eq!(app.undo_stack[0].path, path("/2.txt"));
     }
 
     // ── poll_suggestions ──────────────────────────────────────────────────────
@@ -493,21 +1135,21 @@ mod tests {
     #[test]
     fn test_poll_suggestions_adds_paths_to_list() {
         let (mut app, suggest_tx, _) = make_app_with_channels();
-        suggest_tx.send(path("/tmp/new1.txt")).unwrap();
-        suggest_tx.send(path("/tmp/new2.txt")).unwrap();
+        suggest_tx.send(ai("/tmp/new1.txt")).unwrap();
+        suggest_tx.send(ai("/tmp/new2.txt")).unwrap();
 
         app.poll_suggestions();
 
         assert_eq!(app.suggested_files.len(), 2);
-        assert_eq!(app.suggested_files[0], path("/tmp/new1.txt"));
-        assert_eq!(app.suggested_files[1], path("/tmp/new2.txt"));
+        assert_eq!(app.suggested_files[0].path(), path("/tmp/new1.txt"));
+        assert_eq!(app.suggested_files[1].path(), path("/tmp/new2.txt"));
     }
 
     #[test]
     fn test_poll_suggestions_sets_selection_when_first_item_arrives() {
         let (mut app, suggest_tx, _) = make_app_with_channels();
         app.list_state.select(None);  // start with no selection
-        suggest_tx.send(path("/tmp/first.txt")).unwrap();
+        suggest_tx.send(ai("/tmp/first.txt")).unwrap();
 
         app.poll_suggestions();
 
@@ -524,15 +1166,170 @@ mod tests {
     #[test]
     fn test_poll_suggestions_does_not_reset_existing_selection() {
         let (mut app, suggest_tx, _) = make_app_with_channels();
-        app.suggested_files.push(path("/existing.txt"));
+        app.suggested_files.push(ai("/existing.txt"));
         app.list_state.select(Some(0));
 
-        suggest_tx.send(path("/new.txt")).unwrap();
+        suggest_tx.send(ai("/new.txt")).unwrap();
         app.poll_suggestions();
 
         // Selection should still be 0 (unchanged) since it was already set
         assert_eq!(app.list_state.selected(), Some(0));
         assert_eq!(app.suggested_files.len(), 2);
     }
-}
 
+    // ── poll_notices ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_poll_notices_shows_latest_in_status() {
+        let (mut app, notice_tx, _) = make_app_with_notices();
+        notice_tx.send(Notice::Status("first".to_string())).unwrap();
+        notice_tx.send(Notice::Status("Not deleted: \"/a\" — changed".to_string())).unwrap();
+
+        app.poll_notices();
+
+        assert!(app.status.starts_with("Not deleted"));
+    }
+
+    #[test]
+    fn test_poll_notices_empty_channel_keeps_status() {
+        let (mut app, _notice_tx, _) = make_app_with_notices();
+        app.poll_notices();
+        assert_eq!(app.status, "Waiting for AI suggestions...");
+    }
+
+    // ── never-delete marks view ───────────────────────────────────────────────
+
+    fn marks() -> Vec<Protection> {
+        vec![Protection::Dir(path("/photos")), Protection::Path(path("/a.txt"))]
+    }
+
+    #[test]
+    fn test_marks_view_asks_db_for_marks() {
+        let (mut app, notice_tx, request_rx) = make_app_with_notices();
+        app.toggle_marks();
+        assert_eq!(request_rx.try_recv().unwrap(), UiRequest::ListMarks);
+        assert_eq!(app.marks, Some(Vec::new()));
+
+        notice_tx.send(Notice::Marks(marks())).unwrap();
+        app.poll_notices();
+
+        assert_eq!(app.marks, Some(marks()));
+        assert_eq!(app.selected_mark(), Some(&Protection::Dir(path("/photos"))));
+        assert_eq!(app.status, "2 never-delete mark(s)");
+    }
+
+    #[test]
+    fn test_marks_arriving_after_view_closed_are_ignored() {
+        let (mut app, notice_tx, _request_rx) = make_app_with_notices();
+        app.toggle_marks();
+        app.toggle_marks();
+        notice_tx.send(Notice::Marks(marks())).unwrap();
+        app.poll_notices();
+        assert_eq!(app.marks, None);
+        assert_eq!(app.status, "Back to suggestions.");
+    }
+
+    #[test]
+    fn test_lift_selected_mark_sends_it_to_db() {
+        let (mut app, notice_tx, request_rx) = make_app_with_notices();
+        app.toggle_marks();
+        let _ = request_rx.try_recv();
+        notice_tx.send(Notice::Marks(marks())).unwrap();
+        app.poll_notices();
+
+        app.mark_down();
+        app.lift_selected_mark();
+
+        assert_eq!(request_rx.try_recv().unwrap(), UiRequest::LiftMark(Protection::Path(path("/a.txt"))));
+        assert_eq!(app.marks, Some(vec![Protection::Dir(path("/photos"))]));
+        assert_eq!(app.marks_state.selected(), Some(0));
+        assert!(app.status.starts_with("Lifted never-delete mark on file /a.txt"));
+
+        app.lift_selected_mark();
+        assert_eq!(app.marks, Some(Vec::new()));
+        assert_eq!(app.selected_mark(), None);
+        app.lift_selected_mark();
+        assert_eq!(app.status, "No mark selected.");
+    }
+
+    // ── duplicate suggestions ─────────────────────────────────────────────────
+
+    fn dup_meta(p: &str, created: i64) -> crate::actor::crawler::FileMeta {
+        crate::actor::crawler::FileMeta {
+            rel_path: path(p),
+            abs_path: path(p),
+            file_name: p.rsplit('/').next().unwrap().to_string(),
+            hash: "h".to_string(),
+            is_file: true,
+            size: 10,
+            modified: created,
+            created,
+            readonly: false,
+            hash_strategy: crate::hashing::HashStrategy::Full,
+            full_hash: Some("h".to_string()),
+            inode: 0,
+        }
+    }
+
+    #[test]
+    fn test_from_duplicates_one_suggestion_per_redundant_copy() {
+        let group = DuplicateGroup {
+            size: 10,
+            content_hash: "h".to_string(),
+            keeper: dup_meta("/keep/a.txt", 1),
+            redundant: vec![dup_meta("/x/a.txt", 2), dup_meta("/y/a.txt", 3)],
+        };
+
+        let suggestions = Verdict::from_duplicates(&group);
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].path(), path("/x/a.txt"));
+        match &suggestions[1].source {
+            VerdictSource::Duplicate { keeper, members } => {
+                assert_eq!(keeper, &path("/keep/a.txt"));
+                assert_eq!(members, &vec![path("/keep/a.txt"), path("/x/a.txt"), path("/y/a.txt")]);
+            }
+            other => panic!("expected duplicate suggestion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_delete_duplicate_sends_only_that_copy() {
+        let (mut app, suggest_tx, request_rx) = make_app_with_channels();
+        let group = DuplicateGroup {
+            size: 10,
+            content_hash: "h".to_string(),
+            keeper: dup_meta("/keep/a.txt", 1),
+            redundant: vec![dup_meta("/x/a.txt", 2)],
+        };
+        for s in Verdict::from_duplicates(&group) {
+            suggest_tx.send(s).unwrap();
+        }
+        app.poll_suggestions();
+
+        app.delete_selected();
+
+        assert_eq!(request_rx.try_recv().unwrap(), UiRequest::Delete(path("/x/a.txt")));
+        assert!(request_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_never_delete_content_drops_other_copies() {
+        let (mut app, suggest_tx, _request_rx) = make_app_with_channels();
+        let group = DuplicateGroup {
+            size: 10,
+            content_hash: "h".to_string(),
+            keeper: dup_meta("/keep/a.txt", 1),
+            redundant: vec![dup_meta("/x/a.txt", 2), dup_meta("/y/a.txt", 3)],
+        };
+        for s in Verdict::from_duplicates(&group) {
+            suggest_tx.send(s).unwrap();
+        }
+        suggest_tx.send(ai("/tmp/unrelated")).unwrap();
+        app.poll_suggestions();
+
+        app.never_delete_selected(ProtectScope::Content);
+
+        assert_eq!(app.suggested_files, vec![ai("/tmp/unrelated")]);
+    }
+}
@@ -0,0 +1,323 @@
+use std::path::PathBuf;
+use steady_state::LogLevel;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+/// Default configuration file, relative to the working directory.
+pub(crate) const CONFIG_FILE: &str = "config.toml";
+
+/// Finds files you no longer need and helps you remove them safely.
+#[derive(Parser, Clone, Debug, PartialEq)]
+#[command(name = "cruft-crawler", version)]
+pub(crate) struct Cli {
+    /// Configuration file [default: config.toml in $XDG_CONFIG_HOME/cruft-crawler or the working directory, if present]
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Database directory; overrides database.path [default: db in $XDG_DATA_HOME/cruft-crawler]
+    #[arg(long, global = true, value_name = "DIR")]
+    pub db: Option<PathBuf>,
+
+    /// GGUF model to load; overrides LLM.model [default: the first .gguf in LLM.models-dir]
+    #[arg(long, global = true, value_name = "FILE")]
+    pub model: Option<PathBuf>,
+
+    /// Override one configuration key, e.g. `--set LLM.temp=0.7`; repeatable
+    #[arg(long, global = true, value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+
+    /// How much to log
+    #[arg(long, global = true, value_enum, default_value = "info")]
+    pub log_level: LogLevel,
+
+    /// What to do; `review` when omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    pub fn subcommand(&self) -> Command {
+        self.command.clone().unwrap_or(Command::Review(ReviewArgs::default()))
+    }
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+pub(crate) enum Command {
+    /// Crawl the scan roots and update the index, without the model or the UI
+    Scan,
+    /// Crawl, ask the model about each file and review its suggestions
+    Review(ReviewArgs),
+    /// Show what was deleted, purged or restored, from the deletion journal
+    Report(ReportArgs),
+    /// Delete what a dry run's plan lists, re-checking every file first
+    Apply(ApplyArgs),
+    /// Put a quarantined file back where it came from
+    Restore(RestoreArgs),
+    /// Permanently delete quarantined files
+    Purge(PurgeArgs),
+    /// List or lift never-delete marks
+    Marks {
+        #[command(subcommand)]
+        command: MarksCommand,
+    },
+    /// Inspect the database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ReviewArgs {
+    /// Delete into the desktop trash instead of the quarantine where possible
+    #[arg(long)]
+    pub trash: bool,
+
+    /// Delete nothing; write what would be deleted to a plan for `apply`
+    #[arg(long, conflicts_with = "trash")]
+    pub dry_run: bool,
+
+    /// Where a dry run writes its plan; a readable copy goes next to it as .txt [default: deletion-plan.json]
+    #[arg(long, value_name = "FILE", requires = "dry_run")]
+    pub plan: Option<PathBuf>,
+
+    /// Run without the terminal UI: report every suggestion and exit once all files are reviewed
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Where --headless writes its report; overrides headless.report [default: stdout]
+    #[arg(long, value_name = "FILE", requires = "headless")]
+    pub report: Option<PathBuf>,
+
+    /// Delete suggestions without asking, within the headless.* limits
+    #[arg(long, value_enum, value_name = "WHAT", requires = "headless", conflicts_with = "dry_run")]
+    pub auto_apply: Option<AutoApply>,
+}
+
+/// Which suggestions a headless review may delete on its own.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AutoApply {
+    /// Only redundant copies of files that are kept
+    Duplicates,
+    /// Duplicates and files the model flagged
+    All,
+}
+
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ApplyArgs {
+    /// Plan written by `review --dry-run`
+    #[arg(value_name = "PLAN")]
+    pub plan: PathBuf,
+
+    /// Delete into the desktop trash instead of the quarantine where possible
+    #[arg(long)]
+    pub trash: bool,
+}
+
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ReportArgs {
+    /// First day to include, as YYYY-MM-DD in local time
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<String>,
+
+    /// Last day to include, as YYYY-MM-DD in local time
+    #[arg(long, value_name = "DATE")]
+    pub until: Option<String>,
+
+    /// Print JSON Lines instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct RestoreArgs {
+    /// Quarantine id, as shown by `restore --list`
+    #[arg(conflicts_with_all = ["path", "list"])]
+    pub id: Option<u64>,
+
+    /// Restore the most recently quarantined file that came from this path
+    #[arg(long, value_name = "PATH", conflicts_with = "list")]
+    pub path: Option<PathBuf>,
+
+    /// List what is in quarantine
+    #[arg(long)]
+    pub list: bool,
+}
+
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PurgeArgs {
+    /// Purge everything in quarantine, not just files past the retention period
+    #[arg(long)]
+    pub all: bool,
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum MarksCommand {
+    /// List every never-delete mark with the number `marks remove` takes
+    List,
+    /// Lift never-delete marks, by their number in `marks list`
+    Remove {
+        #[arg(required = true, value_name = "N")]
+        numbers: Vec<usize>,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DbCommand {
+    /// Count what the database holds
+    Stats,
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ConfigCommand {
+    /// Check the configuration, scan list, exclude patterns and model
+    Check,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("cruft-crawler").chain(args.iter().copied())).unwrap()
+    }
+
+    fn rejects(args: &[&str]) -> bool {
+        Cli::try_parse_from(std::iter::once("cruft-crawler").chain(args.iter().copied())).is_err()
+    }
+
+    // ── global flags ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_defaults() {
+        let cli = parse(&[]);
+        assert_eq!(cli.config, None);
+        assert_eq!(cli.db, None);
+        assert_eq!(cli.model, None);
+        assert!(cli.set.is_empty());
+        assert_eq!(cli.log_level, LogLevel::Info);
+    }
+
+    #[test]
+    fn test_no_subcommand_means_review() {
+        assert_eq!(parse(&[]).subcommand(), Command::Review(ReviewArgs::default()));
+    }
+
+    #[test]
+    fn test_global_flags_after_subcommand() {
+        let cli = parse(&["scan", "--db", "/tmp/db", "--model", "m.gguf", "--log-level", "debug"]);
+        assert_eq!(cli.subcommand(), Command::Scan);
+        assert_eq!(cli.db, Some(PathBuf::from("/tmp/db")));
+        assert_eq!(cli.model, Some(PathBuf::from("m.gguf")));
+        assert_eq!(cli.log_level, LogLevel::Debug);
+    }
+
+    #[test]
+    fn test_set_is_repeatable() {
+        let cli = parse(&["--set", "LLM.temp=0.7", "--set", "database.batch-size=4", "scan"]);
+        assert_eq!(cli.set, vec!["LLM.temp=0.7".to_string(), "database.batch-size=4".to_string()]);
+    }
+
+    #[test]
+    fn test_unknown_log_level_is_rejected() {
+        assert!(rejects(&["--log-level", "loud"]));
+    }
+
+    // ── subcommands ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_review_trash_flag() {
+        assert_eq!(parse(&["review", "--trash"]).subcommand(), Command::Review(ReviewArgs { trash: true, ..Default::default() }));
+    }
+
+    #[test]
+    fn test_review_dry_run_plan() {
+        assert_eq!(
+            parse(&["review", "--dry-run", "--plan", "p.json"]).subcommand(),
+            Command::Review(ReviewArgs { dry_run: true, plan: Some(PathBuf::from("p.json")), ..Default::default() })
+        );
+        assert!(rejects(&["review", "--plan", "p.json"]), "--plan only makes sense for a dry run");
+        assert!(rejects(&["review", "--dry-run", "--trash"]));
+    }
+
+    #[test]
+    fn test_review_headless_report_and_auto_apply() {
+        assert_eq!(
+            parse(&["review", "--headless", "--report", "r.txt", "--auto-apply", "duplicates"]).subcommand(),
+            Command::Review(ReviewArgs {
+                headless: true,
+                report: Some(PathBuf::from("r.txt")),
+                auto_apply: Some(AutoApply::Duplicates),
+                ..Default::default()
+            })
+        );
+        assert!(rejects(&["review", "--report", "r.txt"]), "--report only makes sense headless");
+        assert!(rejects(&["review", "--auto-apply", "all"]), "the UI asks before every deletion");
+        assert!(rejects(&["review", "--headless", "--auto-apply", "everything"]));
+        assert!(rejects(&["review", "--headless", "--dry-run", "--auto-apply", "all"]));
+    }
+
+    #[test]
+    fn test_apply_needs_a_plan() {
+        assert_eq!(parse(&["apply", "p.json"]).subcommand(), Command::Apply(ApplyArgs { plan: PathBuf::from("p.json"), trash: false }));
+        assert!(rejects(&["apply"]));
+    }
+
+    #[test]
+    fn test_report_window_and_json() {
+        let cli = parse(&["report", "--since", "2026-10-13", "--until", "2026-10-14", "--json"]);
+        assert_eq!(cli.subcommand(), Command::Report(ReportArgs {
+            since: Some("2026-10-13".into()),
+            until: Some("2026-10-14".into()),
+            json: true,
+        }));
+    }
+
+    #[test]
+    fn test_restore_by_id_or_path() {
+        assert_eq!(parse(&["restore", "42"]).subcommand(), Command::Restore(RestoreArgs { id: Some(42), ..Default::default() }));
+        assert_eq!(
+            parse(&["restore", "--path", "/a.txt"]).subcommand(),
+            Command::Restore(RestoreArgs { path: Some(PathBuf::from("/a.txt")), ..Default::default() })
+        );
+    }
+
+    #[test]
+    fn test_restore_id_conflicts_with_path() {
+        assert!(rejects(&["restore", "42", "--path", "/a.txt"]));
+        assert!(rejects(&["restore", "42", "--list"]));
+    }
+
+    #[test]
+    fn test_nested_subcommands() {
+        assert_eq!(parse(&["db", "stats"]).subcommand(), Command::Db { command: DbCommand::Stats });
+        assert_eq!(parse(&["config", "check"]).subcommand(), Command::Config { command: ConfigCommand::Check });
+        assert!(rejects(&["db"]));
+    }
+
+    #[test]
+    fn test_marks_list_and_remove() {
+        assert_eq!(parse(&["marks", "list"]).subcommand(), Command::Marks { command: MarksCommand::List });
+        assert_eq!(parse(&["marks", "remove", "2", "5"]).subcommand(), Command::Marks { command: MarksCommand::Remove { numbers: vec![2, 5] } });
+        assert!(rejects(&["marks", "remove"]), "say which marks to lift");
+        assert!(rejects(&["marks", "remove", "all"]));
+    }
+
+    #[test]
+    fn test_purge_all_flag() {
+        assert_eq!(parse(&["purge"]).subcommand(), Command::Purge(PurgeArgs { all: false }));
+        assert_eq!(parse(&["purge", "--all"]).subcommand(), Command::Purge(PurgeArgs { all: true }));
+    }
+
+    #[test]
+    fn test_cli_definition_is_consistent() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,856 @@
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::actor::{ai_model, crawler, db_manager};
+use crate::actor::crawler::{FileWalker, Walked};
+use crate::cli::{Cli, MarksCommand, PurgeArgs, ReportArgs, RestoreArgs};
+use crate::config::{Config, ConfigError, Engine};
+use crate::dirs::Dirs;
+use crate::exclude;
+use crate::file_index::{FileIndex, IndexEntry};
+use crate::hashing::HashConfig;
+use crate::journal::{Journal, JournalEntry, Outcome, UserAction};
+use crate::plan::{DeletionPlan, PlanEntry, PlanReason};
+use crate::protection::Protections;
+use crate::quarantine::{self, Quarantine};
+use crate::scan_roots;
+use crate::trash::{self, Trash};
+use crate::verify::{self, Refusal};
+
+// The subcommands that work on the database directly. None of them start the
+// actor graph, so each is the only writer while it runs.
+
+/// Walks every scan root and brings the index up to date, like the crawler and
+/// DB actors do together, but without asking the model about anything.
+pub(crate) fn scan(db: &sled::Db, config: &Config, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let index = FileIndex::open(db)?;
+    let scan_id = index.begin_scan()?;
+    let mut walker = FileWalker::new(crawler::load_scan_roots(&config.directory), config.hashing.clone())
+        .with_global_excludes(exclude::load_global_patterns(config.directory.scan_list_dir()))
+        .with_index(index.clone());
+
+    let (mut changed, mut unchanged) = (0usize, 0usize);
+    while let Some(walked) = walker.next() {
+        match walked {
+            Walked::Changed(meta) => {
+                index.upsert_meta(meta, scan_id)?;
+                changed += 1;
+            }
+            Walked::Unchanged(_) => unchanged += 1,
+        }
+        while let Some(updated) = walker.take_revisit() {
+            index.upsert_meta(updated, scan_id)?;
+        }
+    }
+    let vanished = walker.vanished();
+    for path in &vanished {
+        index.remove(path)?;
+    }
+
+    let groups = db_manager::find_duplicates(&index).groups(&config.duplicates.keeper());
+    let reclaimable: u64 = groups.iter().map(|g| g.reclaimable()).sum();
+    writeln!(out, "Scanned {} entries: {} new or changed, {} unchanged, {} vanished",
+             changed + unchanged, changed, unchanged, vanished.len())?;
+    writeln!(out, "{} duplicate group(s), {} reclaimable", groups.len(), human_size(reclaimable))?;
+    Ok(())
+}
+
+/// Prints journal entries between `--since` and `--until`, as a table or JSON Lines.
+pub(crate) fn report(journal: &Journal, args: &ReportArgs, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let from = match &args.since {
+        Some(day) => parse_day(day, 0)?,
+        None => 0,
+    };
+    let to = match &args.until {
+        Some(day) => parse_day(day, 1)?,
+        None => i64::MAX,
+    };
+    let entries = journal.between(from, to);
+
+    if args.json {
+        return Journal::export_jsonl(&entries, out);
+    }
+
+    for entry in &entries {
+        writeln!(out, "{}", report_line(entry))?;
+        if let Some(reasoning) = entry.reasoning.as_deref().filter(|r| !r.is_empty()) {
+            writeln!(out, "    why:   {}", reasoning)?;
+        }
+        if let Some(error) = &entry.error {
+            writeln!(out, "    error: {}", error)?;
+        }
+    }
+    let deleted: Vec<_> = entries.iter()
+        .filter(|e| matches!(e.outcome, Outcome::Quarantined | Outcome::Trashed))
+        .collect();
+    let freed: u64 = deleted.iter().filter_map(|e| e.size).sum();
+    writeln!(out, "{} journal entries, {} file(s) deleted ({})", entries.len(), deleted.len(), human_size(freed))?;
+    Ok(())
+}
+
+fn report_line(entry: &JournalEntry) -> String {
+    let action = match entry.action {
+        UserAction::Delete => "delete",
+        UserAction::Purge => "purge",
+        UserAction::Restore => "restore",
+        UserAction::Apply => "apply",
+    };
+    let outcome = match entry.outcome {
+        Outcome::Quarantined => "quarantined",
+        Outcome::Trashed => "trashed",
+        Outcome::Purged => "purged",
+        Outcome::Restored => "restored",
+        Outcome::Refused => "refused",
+        Outcome::Failed => "failed",
+    };
+    let size = entry.size.map(human_size).unwrap_or_else(|| "-".to_string());
+    format!("{}  {:<7}  {:<11}  {:>10}  {}",
+            trash::local_timestamp(entry.at), action, outcome, size, entry.path.display())
+}
+
+/// Deletes what a dry run's plan lists. Each file is checked again first: it
+/// must still be the file that was planned, not marked never-delete since, and
+/// for a duplicate the copy being kept must still hold the same content.
+/// Every attempt is journaled; files that fail a check are left alone.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply(plan: &DeletionPlan, index: &FileIndex, protections: &Protections, quarantine: &Quarantine,
+                    trash: Option<&Trash>, journal: &Journal, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let (mut deleted, mut freed) = (0usize, 0u64);
+    for entry in &plan.entries {
+        // a file the index has lost is put back as planned if it is ever restored
+        let indexed = index.get(&entry.path).ok().flatten()
+            .unwrap_or_else(|| IndexEntry::new(entry.scanned.clone(), None));
+        let result = check_planned(entry, protections)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|()| db_manager::move_aside(&entry.path, Some(indexed.clone()), quarantine, trash));
+        journal.record(JournalEntry {
+            action: UserAction::Apply,
+            ..db_manager::journal_entry(&entry.path, Some(&indexed), &result)
+        })?;
+
+        match result {
+            Ok(_) => {
+                index.remove(&entry.path)?;
+                deleted += 1;
+                freed += entry.size;
+                writeln!(out, "✓ {}", entry.path.display())?;
+            }
+            Err(e) => writeln!(out, "✗ {} — {}", entry.path.display(), e)?,
+        }
+    }
+    writeln!(out, "Deleted {} of {} file(s), {} freed", deleted, plan.entries.len(), human_size(freed))?;
+    Ok(())
+}
+
+fn check_planned(entry: &PlanEntry, protections: &Protections) -> Result<(), Refusal> {
+    if protections.is_protected(&entry.scanned) {
+        return Err(Refusal::Protected);
+    }
+    if let PlanReason::Duplicate { keeper } = &entry.reason {
+        verify::verify_keeper(keeper, entry.scanned.content_hash())?;
+    }
+    verify::verify_before_delete(&entry.scanned)
+}
+
+/// Lists the quarantine, or restores one file from it by id or by original path.
+pub(crate) fn restore(index: &FileIndex, quarantine: &Quarantine, journal: &Journal, args: &RestoreArgs, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let record = match (args.id, &args.path) {
+        (Some(id), _) => quarantine.get(id)?.ok_or_else(|| format!("nothing in quarantine has id {}", id))?,
+        (None, Some(path)) => quarantine.latest_for(path)
+            .ok_or_else(|| format!("{} is not in quarantine", path.display()))?,
+        (None, None) => {
+            for record in quarantine.list() {
+                writeln!(out, "{:>20}  {}  {:>10}  {}", record.id,
+                         trash::local_timestamp(record.quarantined_at),
+                         human_size(record.original.size), record.original_path.display())?;
+            }
+            return Ok(());
+        }
+    };
+
+    let result = db_manager::restore_record(record.id, index, quarantine);
+    journal.record(db_manager::restore_entry(&record.original_path, &result))?;
+    let restored = result?;
+    writeln!(out, "Restored {}", restored.original_path.display())?;
+    Ok(())
+}
+
+/// Permanently deletes quarantined files past the retention period, or all of them.
+pub(crate) fn purge(quarantine: &Quarantine, journal: &Journal, args: &PurgeArgs, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let now = if args.all { i64::MAX } else { quarantine::unix_now() };
+
+    let purged = db_manager::purge_quarantine(quarantine, journal, now)?;
+    let freed: u64 = purged.iter().map(|r| r.original.size).sum();
+    writeln!(out, "Purged {} file(s), {} freed", purged.len(), human_size(freed))?;
+    Ok(())
+}
+
+/// Lists the never-delete marks, numbered from 1, or lifts the ones given by
+/// number. Every number is checked before any mark is lifted.
+pub(crate) fn marks(protections: &Protections, command: &MarksCommand, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let marks = protections.list();
+    match command {
+        MarksCommand::List => {
+            if marks.is_empty() {
+                writeln!(out, "No never-delete marks")?;
+            }
+            for (i, mark) in marks.iter().enumerate() {
+                writeln!(out, "{:>4}  {}", i + 1, mark)?;
+            }
+        }
+        MarksCommand::Remove { numbers } => {
+            let chosen = numbers
+                .iter()
+                .map(|&n| n.checked_sub(1).and_then(|i| marks.get(i))
+                    .ok_or_else(|| format!("no never-delete mark is numbered {}; see `marks list`", n)))
+                .collect::<Result<Vec<_>, _>>()?;
+            for mark in chosen {
+                if protections.remove(mark)? {
+                    writeln!(out, "Lifted never-delete mark on {}", mark)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What the database holds.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DbStats {
+    pub files:            usize,
+    pub directories:      usize,
+    pub delete_verdicts:  usize,
+    pub keep_verdicts:    usize,
+    /// Id and start time of the most recent scan.
+    pub latest_scan:      Option<(u64, i64)>,
+    pub never_delete:     usize,
+    pub quarantined:      usize,
+    pub quarantined_size: u64,
+    pub journal_entries:  usize,
+    pub size_on_disk:     u64,
+}
+
+pub(crate) fn db_stats(db: &sled::Db, config: &Config) -> Result<DbStats, Box<dyn Error>> {
+    let index = FileIndex::open(db)?;
+    let quarantine = config.quarantine.open(db)?;
+    let held = quarantine.list();
+
+    let mut stats = DbStats {
+        latest_scan:      index.latest_scan()?,
+        never_delete:     Protections::open(db)?.list().len(),
+        quarantined:      held.len(),
+        quarantined_size: held.iter().map(|r| r.original.size).sum(),
+        journal_entries:  Journal::open(db, &config.database.journal)?.entries().len(),
+        size_on_disk:     db.size_on_disk()?,
+        ..DbStats::default()
+    };
+    for entry in index.entries() {
+        if entry.meta.is_file {
+            stats.files += 1;
+        } else {
+            stats.directories += 1;
+        }
+        match entry.verdict {
+            Some(v) if v.is_delete() => stats.delete_verdicts += 1,
+            Some(_) => stats.keep_verdicts += 1,
+            None => {}
+        }
+    }
+    Ok(stats)
+}
+
+pub(crate) fn print_db_stats(stats: &DbStats, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    writeln!(out, "Indexed files:        {}", stats.files)?;
+    writeln!(out, "Indexed directories:  {}", stats.directories)?;
+    writeln!(out, "Verdicts:             {} delete, {} keep", stats.delete_verdicts, stats.keep_verdicts)?;
+    match stats.latest_scan {
+        Some((id, started)) => writeln!(out, "Latest scan:          #{} at {}", id, trash::local_timestamp(started))?,
+        None => writeln!(out, "Latest scan:          never")?,
+    }
+    writeln!(out, "Never-delete marks:   {}", stats.never_delete)?;
+    writeln!(out, "Quarantined files:    {} ({})", stats.quarantined, human_size(stats.quarantined_size))?;
+    writeln!(out, "Journal entries:      {}", stats.journal_entries)?;
+    writeln!(out, "Database size:        {}", human_size(stats.size_on_disk))?;
+    Ok(())
+}
+
+/// Checks everything a run depends on and prints one line per check. Fails
+/// when anything would stop a run from working.
+pub(crate) fn config_check(cli: &Cli, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let mut problems = 0usize;
+    let mut report = |ok: bool, line: String| -> std::io::Result<()> {
+        if !ok {
+            problems += 1;
+        }
+        writeln!(out, "{} {}", if ok { "✓" } else { "✗" }, line)
+    };
+
+    // Whatever is wrong with the configuration, the rest is still checked with the defaults
+    let dirs = Dirs::from_env();
+    let file = Config::file(cli, &dirs);
+    let config = match Config::from_cli(cli) {
+        Ok(config) => {
+            if file.exists() {
+                report(true, format!("{} is valid", file.display()))?;
+            } else {
+                report(true, format!("no {}; using the defaults", file.display()))?;
+            }
+            config
+        }
+        Err(e) => {
+            match e {
+                ConfigError::Invalid { problems, .. } => {
+                    for problem in problems {
+                        report(false, format!("{}: {}", file.display(), problem))?;
+                    }
+                }
+                e => report(false, e.to_string())?,
+            }
+            let mut config = Config::default();
+            config.place(&dirs);
+            config.llm.model = cli.model.clone();
+            config
+        }
+    };
+
+    report(true, format!("database in {}", config.database.path.display()))?;
+    report(true, format!("quarantine in {}", config.quarantine.dir.display()))?;
+
+    let extra = &config.directory.path;
+    if !extra.as_os_str().is_empty() {
+        report(extra.is_dir(), format!("scan root {} (from {})", extra.display(), file.display()))?;
+    }
+
+    let lists = config.directory.scan_list_dir();
+    match scan_roots::find_scan_file(lists) {
+        Some(file) => match scan_roots::load_scan_roots(&file) {
+            Ok((roots, errors)) => {
+                for err in &errors {
+                    report(false, format!("{} {}", file.display(), err))?;
+                }
+                for root in &roots {
+                    report(root.path.is_dir(), format!("scan root {}", root.path.display()))?;
+                }
+                if roots.is_empty() {
+                    report(false, format!("{} lists no usable directories", file.display()))?;
+                }
+            }
+            Err(e) => report(false, e.to_string())?,
+        },
+        None if !extra.as_os_str().is_empty() => {}
+        None => report(false, format!("no {} in {}", scan_roots::SCAN_FILE, lists.display()))?,
+    }
+
+    for pattern in exclude::load_global_patterns(lists) {
+        if let Err(e) = exclude::validate_pattern(&pattern) {
+            report(false, format!("exclude pattern {:?}: {}", pattern, e))?;
+        }
+    }
+
+    // Only the llama engine needs a model; the replay engine's recording is checked with the configuration
+    match config.llm.engine {
+        Engine::Llama => {
+            let model = match &config.llm.model {
+                Some(path) if path.is_file() => Ok(path.clone()),
+                Some(path) => Err(format!("model {} does not exist", path.display())),
+                None => ai_model::find_model_file(&config.llm.models_dir).map_err(|e| e.to_string()),
+            };
+            match model {
+                Ok(path) => report(true, format!("model {}", path.display()))?,
+                Err(e) => report(false, e)?,
+            }
+        }
+        Engine::Mock => report(true, "mock engine; no model needed".to_string())?,
+        Engine::Replay => report(true, format!("replay engine, answers from {}", config.llm.recording.display()))?,
+    }
+
+    if problems > 0 {
+        return Err(format!("{} problem(s) found", problems).into());
+    }
+    Ok(())
+}
+
+/// `YYYY-MM-DD` → unix time of local midnight, `days_after` days later.
+pub(crate) fn parse_day(day: &str, days_after: i32) -> Result<i64, String> {
+    let invalid = || format!("{:?} is not a date like 2026-10-13", day);
+    let mut parts = day.splitn(3, '-').map(|p| p.parse::<i32>().map_err(|_| invalid()));
+    let (Some(year), Some(month), Some(mday)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let (year, month, mday) = (year?, month?, mday?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&mday) {
+        return Err(invalid());
+    }
+
+    local_midnight(year, month, mday + days_after).ok_or_else(invalid)
+}
+
+#[cfg(unix)]
+fn local_midnight(year: i32, month: i32, mday: i32) -> Option<i64> {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = year - 1900;
+    tm.tm_mon = month - 1;
+    // mktime normalizes, so the day after the 31st is the 1st of the next month
+    tm.tm_mday = mday;
+    // let the C library work out whether daylight saving applies
+    tm.tm_isdst = -1;
+    let secs = unsafe { libc::mktime(&mut tm) };
+    (secs != -1).then_some(secs as i64)
+}
+
+/// Without mktime the day boundaries are taken in UTC.
+#[cfg(not(unix))]
+fn local_midnight(year: i32, month: i32, mday: i32) -> Option<i64> {
+    Some((days_from_civil(year, month) + mday as i64 - 1) * 86_400)
+}
+
+/// Days from 1970-01-01 to the first of `month` in `year` (proleptic Gregorian).
+#[cfg(not(unix))]
+fn days_from_civil(year: i32, month: i32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year } as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub(crate) fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::file_index::IndexEntry;
+    use crate::protection::Protection;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn open_temp_db(name: &str) -> (sled::Db, PathBuf) {
+        let base = std::env::temp_dir().join(format!("cruft_commands_{}", name));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        (sled::open(base.join("db")).unwrap(), base)
+    }
+
+    fn journal_entry(path: &str, at: i64, outcome: Outcome, size: u64) -> JournalEntry {
+        JournalEntry {
+            at,
+            size: Some(size),
+            ..JournalEntry::new(Path::new(path), None, UserAction::Delete, outcome)
+        }
+    }
+
+    fn output(run: impl FnOnce(&mut Vec<u8>) -> Result<(), Box<dyn Error>>) -> String {
+        let mut out = Vec::new();
+        run(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // ── report ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_report_selects_days_inclusively() {
+        let (db, base) = open_temp_db("report_days");
+        let journal = Journal::open(&db, base.join("j.jsonl")).unwrap();
+        let tuesday = parse_day("2026-10-13", 0).unwrap();
+        journal.record(journal_entry("/monday", tuesday - 60, Outcome::Quarantined, 1)).unwrap();
+        journal.record(journal_entry("/tuesday", tuesday + 3600, Outcome::Quarantined, 1)).unwrap();
+        journal.record(journal_entry("/tuesday_night", tuesday + 86399, Outcome::Trashed, 1)).unwrap();
+        journal.record(journal_entry("/wednesday", tuesday + 86400 + 60, Outcome::Quarantined, 1)).unwrap();
+
+        let args = ReportArgs { since: Some("2026-10-13".into()), until: Some("2026-10-13".into()), json: false };
+        let text = output(|out| report(&journal, &args, out));
+
+        assert!(text.contains("/tuesday\n"));
+        assert!(text.contains("/tuesday_night"));
+        assert!(!text.contains("/monday"));
+        assert!(!text.contains("/wednesday"));
+        assert!(text.contains("2 file(s) deleted"));
+    }
+
+    #[test]
+    fn test_report_shows_reason_and_error() {
+        let (db, base) = open_temp_db("report_error");
+        let journal = Journal::open(&db, base.join("j.jsonl")).unwrap();
+        journal.record(JournalEntry {
+            reasoning: Some("old installer".into()),
+            error: Some("the file changed since it was scanned".into()),
+            ..journal_entry("/a.iso", 100, Outcome::Refused, 5)
+        }).unwrap();
+
+        let text = output(|out| report(&journal, &ReportArgs::default(), out));
+
+        assert!(text.contains("refused"));
+        assert!(text.contains("why:   old installer"));
+        assert!(text.contains("error: the file changed"));
+        assert!(text.contains("0 file(s) deleted"));
+    }
+
+    #[test]
+    fn test_report_json_is_one_entry_per_line() {
+        let (db, base) = open_temp_db("report_json");
+        let journal = Journal::open(&db, base.join("j.jsonl")).unwrap();
+        journal.record(journal_entry("/a", 1, Outcome::Quarantined, 1)).unwrap();
+        journal.record(journal_entry("/b", 2, Outcome::Trashed, 1)).unwrap();
+
+        let text = output(|out| report(&journal, &ReportArgs { json: true, ..Default::default() }, out));
+
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.lines().all(|l| l.starts_with('{')));
+    }
+
+    #[test]
+    fn test_report_rejects_bad_date() {
+        let (db, base) = open_temp_db("report_bad_date");
+        let journal = Journal::open(&db, base.join("j.jsonl")).unwrap();
+        let args = ReportArgs { since: Some("last tuesday".into()), ..Default::default() };
+        assert!(report(&journal, &args, &mut Vec::new()).is_err());
+    }
+
+    // ── apply ─────────────────────────────────────────────────────────────────
+
+    /// Everything `apply` works on, sharing one database, plus a directory for files.
+    struct ApplySetup {
+        index:       FileIndex,
+        protections: Protections,
+        quarantine:  Quarantine,
+        journal:     Journal,
+        base:        PathBuf,
+    }
+
+    fn apply_setup(name: &str) -> ApplySetup {
+        let (db, base) = open_temp_db(name);
+        ApplySetup {
+            index:       FileIndex::open(&db).unwrap(),
+            protections: Protections::open(&db).unwrap(),
+            quarantine:  Quarantine::open(&db, base.join("held"), quarantine::DEFAULT_RETENTION).unwrap(),
+            journal:     Journal::open(&db, base.join("j.jsonl")).unwrap(),
+            base,
+        }
+    }
+
+    /// A plan entry for a freshly written file, as a dry run would have listed it.
+    fn planned(s: &ApplySetup, name: &str, contents: &[u8], reason: PlanReason) -> PlanEntry {
+        let file = s.base.join(name);
+        fs::write(&file, contents).unwrap();
+        let config = HashConfig { strategy: crate::hashing::HashStrategy::Full, ..HashConfig::default() };
+        let meta = crawler::file_meta_from_path(&file, &fs::metadata(&file).unwrap(), &config).unwrap();
+        s.index.put(&IndexEntry::new(meta.clone(), None)).unwrap();
+        PlanEntry { path: file, size: meta.size, reason, verdict: None, reasoning: None, scanned: meta }
+    }
+
+    fn run_apply(s: &ApplySetup, entries: Vec<PlanEntry>) -> String {
+        let plan = DeletionPlan { created_at: 0, reclaimable: entries.iter().map(|e| e.size).sum(), entries };
+        output(|out| apply(&plan, &s.index, &s.protections, &s.quarantine, None, &s.journal, out))
+    }
+
+    #[test]
+    fn test_apply_quarantines_unchanged_files_and_journals() {
+        let s = apply_setup("apply_ok");
+        let entry = planned(&s, "junk.o", b"object", PlanReason::Ai);
+
+        let text = run_apply(&s, vec![entry.clone()]);
+
+        assert!(text.contains("Deleted 1 of 1 file(s), 6 B freed"));
+        assert!(!entry.path.exists());
+        assert_eq!(s.quarantine.list().len(), 1);
+        assert!(s.index.get(&entry.path).unwrap().is_none());
+        let journaled = &s.journal.entries()[0];
+        assert_eq!((journaled.action, journaled.outcome), (UserAction::Apply, Outcome::Quarantined));
+    }
+
+    #[test]
+    fn test_apply_leaves_changed_file_alone() {
+        let s = apply_setup("apply_changed");
+        let entry = planned(&s, "junk.o", b"object", PlanReason::Ai);
+        fs::write(&entry.path, b"rewritten since").unwrap();
+
+        let text = run_apply(&s, vec![entry.clone()]);
+
+        assert!(text.contains("✗"));
+        assert!(text.contains("Deleted 0 of 1"));
+        assert!(entry.path.exists());
+        assert_eq!(s.journal.entries()[0].outcome, Outcome::Refused);
+    }
+
+    #[test]
+    fn test_apply_respects_later_never_delete_mark() {
+        let s = apply_setup("apply_protected");
+        let entry = planned(&s, "junk.o", b"object", PlanReason::Ai);
+        s.protections.add(&Protection::Path(entry.path.clone())).unwrap();
+
+        let text = run_apply(&s, vec![entry.clone()]);
+
+        assert!(text.contains("never-delete"));
+        assert!(entry.path.exists());
+    }
+
+    #[test]
+    fn test_apply_keeps_duplicate_whose_keeper_is_gone() {
+        let s = apply_setup("apply_keeper");
+        let keeper = planned(&s, "keep.iso", b"same", PlanReason::Ai).path;
+        let copy = planned(&s, "copy.iso", b"same", PlanReason::Duplicate { keeper: keeper.clone() });
+        let other = planned(&s, "other.iso", b"same", PlanReason::Duplicate { keeper: keeper.clone() });
+
+        // the first copy goes while the keeper is intact, the second after it vanished
+        run_apply(&s, vec![copy.clone()]);
+        fs::remove_file(&keeper).unwrap();
+        let text = run_apply(&s, vec![other.clone()]);
+
+        assert!(!copy.path.exists());
+        assert!(other.path.exists());
+        assert!(text.contains("is gone or changed"));
+    }
+
+    // ── restore / purge ───────────────────────────────────────────────────────
+
+    /// Index, quarantine and journal sharing one database, plus one indexed
+    /// file already moved into quarantine.
+    fn quarantined_file(name: &str) -> (FileIndex, Quarantine, Journal, PathBuf) {
+        let (db, base) = open_temp_db(name);
+        let index = FileIndex::open(&db).unwrap();
+        let quarantine = Quarantine::open(&db, base.join("held"), quarantine::DEFAULT_RETENTION).unwrap();
+        let journal = Journal::open(&db, base.join("j.jsonl")).unwrap();
+        let file = base.join("a.txt");
+        fs::write(&file, b"x").unwrap();
+        let meta = crawler::file_meta_from_path(&file, &fs::metadata(&file).unwrap(), &HashConfig::default()).unwrap();
+        let entry = IndexEntry::new(meta, None);
+        quarantine.quarantine(&file, Some(entry)).unwrap();
+        (index, quarantine, journal, file)
+    }
+
+    #[test]
+    fn test_restore_list_shows_quarantine() {
+        let (index, quarantine, journal, file) = quarantined_file("restore_list");
+        let text = output(|out| restore(&index, &quarantine, &journal, &RestoreArgs::default(), out));
+        assert!(text.contains(&file.display().to_string()));
+        assert!(file.metadata().is_err());
+    }
+
+    #[test]
+    fn test_restore_by_path_puts_file_back_and_journals() {
+        let (index, quarantine, journal, file) = quarantined_file("restore_path");
+        let args = RestoreArgs { path: Some(file.clone()), ..Default::default() };
+
+        let text = output(|out| restore(&index, &quarantine, &journal, &args, out));
+
+        assert!(text.starts_with("Restored"));
+        assert_eq!(fs::read(&file).unwrap(), b"x");
+        assert!(index.get(&file).unwrap().is_some());
+        assert_eq!(journal.entries()[0].outcome, Outcome::Restored);
+    }
+
+    #[test]
+    fn test_restore_unknown_id_is_error() {
+        let (index, quarantine, journal, _) = quarantined_file("restore_unknown");
+        let args = RestoreArgs { id: Some(u64::MAX), ..Default::default() };
+        assert!(restore(&index, &quarantine, &journal, &args, &mut Vec::new()).is_err());
+        assert!(journal.entries().is_empty());
+    }
+
+    #[test]
+    fn test_purge_keeps_recent_files_unless_all() {
+        let (_, quarantine, journal, _) = quarantined_file("purge");
+
+        let text = output(|out| purge(&quarantine, &journal, &PurgeArgs { all: false }, out));
+        assert!(text.starts_with("Purged 0 file(s)"));
+        assert_eq!(quarantine.list().len(), 1);
+
+        let text = output(|out| purge(&quarantine, &journal, &PurgeArgs { all: true }, out));
+        assert!(text.starts_with("Purged 1 file(s)"));
+        assert!(quarantine.list().is_empty());
+        assert_eq!(journal.entries()[0].outcome, Outcome::Purged);
+    }
+
+    // ── marks ─────────────────────────────────────────────────────────────────
+
+    fn marked(name: &str) -> Protections {
+        let (db, _) = open_temp_db(name);
+        let protections = Protections::open(&db).unwrap();
+        protections.add(&Protection::Path(PathBuf::from("/a.txt"))).unwrap();
+        protections.add(&Protection::Dir(PathBuf::from("/photos"))).unwrap();
+        protections
+    }
+
+    #[test]
+    fn test_marks_list_numbers_every_mark() {
+        let protections = marked("marks_list");
+        let text = output(|out| marks(&protections, &MarksCommand::List, out));
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.contains("   1  directory /photos") && text.contains("   2  file /a.txt"), "{}", text);
+
+        let (db, _) = open_temp_db("marks_none");
+        let text = output(|out| marks(&Protections::open(&db).unwrap(), &MarksCommand::List, out));
+        assert_eq!(text, "No never-delete marks\n");
+    }
+
+    #[test]
+    fn test_marks_remove_lifts_by_number() {
+        let protections = marked("marks_remove");
+        let text = output(|out| marks(&protections, &MarksCommand::Remove { numbers: vec![2] }, out));
+        assert_eq!(text, "Lifted never-delete mark on file /a.txt\n");
+        assert_eq!(protections.list(), vec![Protection::Dir(PathBuf::from("/photos"))]);
+    }
+
+    #[test]
+    fn test_marks_remove_checks_every_number_first() {
+        let protections = marked("marks_remove_bad");
+        for numbers in [vec![1, 3], vec![0]] {
+            let err = marks(&protections, &MarksCommand::Remove { numbers }, &mut Vec::new()).unwrap_err();
+            assert!(err.to_string().starts_with("no never-delete mark is numbered"), "{}", err);
+        }
+        assert_eq!(protections.list().len(), 2, "nothing was lifted");
+    }
+
+    // ── parse_day ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_day_is_local_midnight() {
+        let midnight = parse_day("2026-10-13", 0).unwrap();
+        assert_eq!(trash::local_timestamp(midnight), "2026-10-13T00:00:00");
+        assert_eq!(trash::local_timestamp(parse_day("2026-10-31", 1).unwrap()), "2026-11-01T00:00:00");
+    }
+
+    #[test]
+    fn test_parse_day_rejects_garbage() {
+        for bad in ["", "2026", "2026-13-01", "2026-10-00", "2026-10-x", "yesterday"] {
+            assert!(parse_day(bad, 0).is_err(), "{:?} should not parse", bad);
+        }
+    }
+
+    // ── db_stats ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_db_stats_counts_everything() {
+        let (db, base) = open_temp_db("stats");
+        let index = FileIndex::open(&db).unwrap();
+        let file = base.join("a.txt");
+        fs::write(&file, b"x").unwrap();
+        let meta = crawler::file_meta_from_path(&file, &fs::metadata(&file).unwrap(), &HashConfig::default()).unwrap();
+        let verdict = ai_model::VerdictRecord::from_output("Decision: delete", "m.gguf");
+        index.put(&IndexEntry::new(meta, Some(verdict))).unwrap();
+        index.begin_scan().unwrap();
+        Protections::open(&db).unwrap().add(&Protection::Path(PathBuf::from("/keep"))).unwrap();
+
+        let stats = db_stats(&db, &Config::default()).unwrap();
+
+        assert_eq!(stats.files, 1);
+        assert_eq!(stats.delete_verdicts, 1);
+        assert_eq!(stats.keep_verdicts, 0);
+        assert_eq!(stats.never_delete, 1);
+        assert!(stats.latest_scan.is_some());
+        let text = output(|out| print_db_stats(&stats, out));
+        assert!(text.contains("Verdicts:             1 delete, 0 keep"));
+    }
+
+    // ── config_check ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_config_check_reports_bad_toml_and_missing_model() {
+        let (_, base) = open_temp_db("config_check");
+        let config = base.join("config.toml");
+        fs::write(&config, "[database\nbatch-size = 1").unwrap();
+        let cli = Cli {
+            config: Some(config.clone()),
+            db: Some(base.join("db")),
+            model: Some(base.join("missing.gguf")),
+            set: Vec::new(),
+            log_level: steady_state::LogLevel::Info,
+            command: None,
+        };
+
+        let mut out = Vec::new();
+        let result = config_check(&cli, &mut out);
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(result.is_err());
+        assert!(text.contains(&format!("✗ {} is not valid TOML", config.display())));
+        assert!(text.contains("✗ model"));
+    }
+
+    #[test]
+    fn test_config_check_accepts_given_model() {
+        let (_, base) = open_temp_db("config_model");
+        let model = base.join("m.gguf");
+        fs::write(&model, b"gguf").unwrap();
+        let cli = Cli {
+            config: Some(base.join("config.toml")),
+            db: Some(base.join("db")),
+            model: Some(model.clone()),
+            set: Vec::new(),
+            log_level: steady_state::LogLevel::Info,
+            command: None,
+        };
+
+        let text = output(|out| { let _ = config_check(&cli, out); Ok(()) });
+
+        assert!(text.contains(&format!("✓ model {}", model.display())));
+    }
+
+    #[test]
+    fn test_config_check_mock_engine_needs_no_model() {
+        let (_, base) = open_temp_db("config_mock");
+        fs::write(base.join("config.toml"), "").unwrap();
+        let cli = Cli {
+            config: Some(base.join("config.toml")),
+            db: Some(base.join("db")),
+            model: None,
+            set: vec!["LLM.engine=mock".into(), format!("LLM.models-dir={:?}", base.join("empty").display().to_string())],
+            log_level: steady_state::LogLevel::Info,
+            command: None,
+        };
+
+        let text = output(|out| { let _ = config_check(&cli, out); Ok(()) });
+
+        assert!(text.contains("✓ mock engine; no model needed"));
+        assert!(!text.contains("✗ No .gguf"));
+    }
+
+    #[test]
+    fn test_config_check_lists_each_invalid_value() {
+        let (_, base) = open_temp_db("config_values");
+        let config = base.join("config.toml");
+        fs::write(&config, "[database]\nbatch-size = 0").unwrap();
+        let cli = Cli {
+            config: Some(config.clone()),
+            db: None,
+            model: None,
+            set: vec!["ui.undo-limit=0".into()],
+            log_level: steady_state::LogLevel::Info,
+            command: None,
+        };
+
+        let mut out = Vec::new();
+        let result = config_check(&cli, &mut out);
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(result.is_err());
+        assert!(text.contains(&format!("✗ {}: database.batch-size", config.display())));
+        assert!(text.contains(&format!("✗ {}: ui.undo-limit", config.display())));
+    }
+
+    // ── human_size ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_human_size_units() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
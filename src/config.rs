@@ -0,0 +1,761 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+use crate::actor::ai_model;
+use crate::actor::db_manager;
+use crate::actor::user_interface;
+use crate::cli::{Cli, CONFIG_FILE};
+use crate::dirs::Dirs;
+use crate::duplicates::KeeperPolicy;
+use crate::hashing::HashConfig;
+use crate::governor;
+use crate::quarantine::{self, Quarantine};
+
+/// Most scan events the DB actor can wait for at once: the capacity of its
+/// channel from the crawler. Waiting for more would never finish.
+pub(crate) const MAX_BATCH_SIZE: usize = 64;
+
+/// Everything `config.toml` can set. Missing keys take their defaults, so an
+/// empty or absent file gives the same behavior as before it existed. Paths
+/// left empty are filled in from the XDG directories by `place`.
+///
+/// ```toml
+/// [database]
+/// batch-size = 15
+///
+/// [LLM]
+/// temp = 0.0
+/// max-tokens = 64
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    pub directory:  DirectoryConfig,
+    pub hashing:    HashConfig,
+    pub database:   DatabaseConfig,
+    #[serde(rename = "LLM")]
+    pub llm:        LlmConfig,
+    pub quarantine: QuarantineConfig,
+    pub duplicates: DuplicatesConfig,
+    pub ui:         UiConfig,
+    pub headless:   HeadlessConfig,
+}
+
+/// `[directory]`: read by the crawler.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct DirectoryConfig {
+    /// One more directory to crawl besides those in the scan list; empty for none.
+    pub path:          PathBuf,
+    /// Where `scan_paths.txt` and the global `.cruftignore` are read from;
+    /// empty for the working directory.
+    pub scan_list_dir: PathBuf,
+}
+
+impl DirectoryConfig {
+    /// The directory holding the scan list, `.` unless configured.
+    pub fn scan_list_dir(&self) -> &Path {
+        if self.scan_list_dir.as_os_str().is_empty() { Path::new(".") } else { &self.scan_list_dir }
+    }
+}
+
+/// `[database]`: read at startup and by the DB actor.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct DatabaseConfig {
+    /// Empty for `db` in the data directory.
+    pub path:       PathBuf,
+    /// JSON Lines copy of the deletion journal; empty for `deletions.jsonl` in the data directory.
+    pub journal:    PathBuf,
+    /// How many scan events the DB actor waits for before writing them.
+    pub batch_size: usize,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self { path: PathBuf::new(), journal: PathBuf::new(), batch_size: db_manager::BATCH_SIZE }
+    }
+}
+
+/// `[LLM]`: read by the AI model actor and the inference engine.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct LlmConfig {
+    /// What answers the AI actor's questions.
+    pub engine:         Engine,
+    /// JSON Lines file of answers: read by the replay engine, and written by
+    /// the others when set. Empty for none.
+    pub recording:      PathBuf,
+    /// GGUF model to load; the first one in `models-dir` when unset.
+    pub model:          Option<PathBuf>,
+    /// Empty for `models` in the data directory.
+    pub models_dir:     PathBuf,
+    /// Where raw answers are appended; empty for `LLM_responses.txt` in the cache directory.
+    pub responses:      PathBuf,
+    /// Where the decoded prompt prefix is kept between runs, one file per
+    /// model; empty for `prompt-cache` in the cache directory.
+    pub prompt_cache:   PathBuf,
+    /// Sampling temperature; 0 always picks the likeliest token.
+    pub temp:           f32,
+    /// Seed for sampling when `temp` is above 0.
+    pub seed:           u32,
+    /// Context window in tokens.
+    pub n_ctx:          u32,
+    /// Threads llama.cpp decodes with.
+    pub threads:        i32,
+    /// Cores inference may run on; empty for any.
+    pub cores:          Vec<usize>,
+    /// CPU time inference may use, as a percentage of one core; 200 is two
+    /// whole cores. Less is allowed while the machine is busy with other work.
+    pub cpu_budget:     u32,
+    /// Longest answer, in tokens.
+    pub max_tokens:     usize,
+    /// Most files from one directory asked about in a single prompt; 1 asks
+    /// about each file on its own.
+    pub batch_size:     usize,
+    /// Prompt tokens decoded at a time.
+    pub chunk_size:     usize,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            engine:         Engine::Llama,
+            recording:      PathBuf::new(),
+            model:          None,
+            models_dir:     PathBuf::new(),
+            responses:      PathBuf::new(),
+            prompt_cache:   PathBuf::new(),
+            temp:           0.0,
+            seed:           0,
+            n_ctx:          2048,
+            threads:        1,
+            cores:          vec![0],
+            cpu_budget:     25,
+            max_tokens:     64,
+            batch_size:     1,
+            chunk_size:     1,
+        }
+    }
+}
+
+/// `LLM.engine`: which backend the AI actor asks about each file.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Engine {
+    /// A GGUF model run with llama.cpp.
+    #[default]
+    Llama,
+    /// Fixed rules, without a model; for tests and machines that can't run one.
+    Mock,
+    /// The answers in `LLM.recording`, by file path.
+    Replay,
+}
+
+/// `[quarantine]`: where deleted files wait, and for how long.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct QuarantineConfig {
+    /// Empty for `quarantine` in the data directory.
+    pub dir:            PathBuf,
+    pub retention_days: u64,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::new(),
+            retention_days: quarantine::DEFAULT_RETENTION.as_secs() / 86400,
+        }
+    }
+}
+
+impl QuarantineConfig {
+    pub fn retention(&self) -> Duration {
+        Duration::from_secs(self.retention_days.saturating_mul(86400))
+    }
+
+    pub fn open(&self, db: &sled::Db) -> sled::Result<Quarantine> {
+        Quarantine::open(db, &self.dir, self.retention())
+    }
+}
+
+/// `[duplicates]`: which copy in a group of identical files is kept.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct DuplicatesConfig {
+    pub keeper:         Keeper,
+    /// The directory `keeper = "preferred-root"` keeps copies under.
+    pub preferred_root: PathBuf,
+}
+
+impl DuplicatesConfig {
+    pub fn keeper(&self) -> KeeperPolicy {
+        match self.keeper {
+            Keeper::Oldest => KeeperPolicy::Oldest,
+            Keeper::ShortestPath => KeeperPolicy::ShortestPath,
+            Keeper::PreferredRoot => KeeperPolicy::PreferredRoot(self.preferred_root.clone()),
+        }
+    }
+}
+
+/// `duplicates.keeper`: the rule that picks the copy to keep.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Keeper {
+    /// The earliest created copy.
+    #[default]
+    Oldest,
+    /// The least nested copy.
+    ShortestPath,
+    /// A copy under `duplicates.preferred-root`, else the oldest.
+    PreferredRoot,
+}
+
+/// `[ui]`: read by the review UI.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct UiConfig {
+    /// How often the UI actor checks for decisions made in the terminal.
+    pub poll_ms:    u64,
+    /// How many review decisions `u` can take back.
+    pub undo_limit: usize,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            poll_ms: user_interface::TUI_POLL_INTERVAL.as_millis() as u64,
+            undo_limit: user_interface::UNDO_LIMIT,
+        }
+    }
+}
+
+impl UiConfig {
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_ms)
+    }
+}
+
+/// `[headless]`: read by the headless sink that stands in for the UI.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct HeadlessConfig {
+    /// Where the report is written; empty for standard output.
+    pub report:       PathBuf,
+    /// Most files `--auto-apply` deletes in one run.
+    pub max_files:    usize,
+    /// Most space `--auto-apply` frees in one run, in MiB.
+    pub max_mb:       u64,
+    /// Files modified more recently than this are only reported.
+    pub min_age_days: u64,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self { report: PathBuf::new(), max_files: 20, max_mb: 1024, min_age_days: 30 }
+    }
+}
+
+impl HeadlessConfig {
+    pub fn max_bytes(&self) -> u64 {
+        self.max_mb.saturating_mul(1024 * 1024)
+    }
+
+    pub fn min_age(&self) -> Duration {
+        Duration::from_secs(self.min_age_days.saturating_mul(86400))
+    }
+}
+
+/// Why the configuration could not be used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ConfigError {
+    Unreadable { file: PathBuf, error: String },
+    Syntax { file: PathBuf, error: String },
+    /// A `--set` argument that is not `section.key=value`.
+    BadOverride { arg: String, reason: String },
+    /// The file parsed, but some values are unusable. Every one is listed.
+    Invalid { file: PathBuf, problems: Vec<String> },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Unreadable { file, error } => write!(f, "{} could not be read: {}", file.display(), error),
+            ConfigError::Syntax { file, error } => write!(f, "{} is not valid TOML: {}", file.display(), error),
+            ConfigError::BadOverride { arg, reason } => write!(f, "--set {}: {}", arg, reason),
+            ConfigError::Invalid { file, problems } => {
+                let lines: Vec<String> = problems.iter().map(|p| format!("{}: {}", file.display(), p)).collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl Config {
+    /// The configuration a run uses: `--config` (or `config.toml` from the
+    /// config directory or the working directory, which may be absent), then
+    /// every `--set`, then `--db` and `--model`. Paths not set anywhere are
+    /// placed in the XDG directories.
+    pub fn from_cli(cli: &Cli) -> Result<Self, ConfigError> {
+        let dirs = Dirs::from_env();
+        let file = Self::file(cli, &dirs);
+        let mut config = Self::load(&file, cli.config.is_some(), &cli.set)?;
+        config.place(&dirs);
+
+        if let Some(db) = &cli.db {
+            config.database.path = db.clone();
+        }
+        if let Some(model) = &cli.model {
+            config.llm.model = Some(model.clone());
+        }
+        config.check(&file)?;
+        Ok(config)
+    }
+
+    /// The file `from_cli` reads, whether or not it exists.
+    pub fn file(cli: &Cli, dirs: &Dirs) -> PathBuf {
+        cli.config.clone()
+            .or_else(|| dirs.find_config_file())
+            .unwrap_or_else(|| dirs.config.join(CONFIG_FILE))
+    }
+
+    /// Fills every path left empty with its place under `dirs`.
+    pub fn place(&mut self, dirs: &Dirs) {
+        let unset = |path: &mut PathBuf, default: PathBuf| {
+            if path.as_os_str().is_empty() {
+                *path = default;
+            }
+        };
+        unset(&mut self.database.path, dirs.db());
+        unset(&mut self.database.journal, dirs.journal_file());
+        unset(&mut self.llm.models_dir, dirs.models());
+        unset(&mut self.llm.responses, dirs.responses_file());
+        unset(&mut self.llm.prompt_cache, dirs.prompt_cache());
+        unset(&mut self.quarantine.dir, dirs.quarantine());
+    }
+
+    /// Reads and validates `file`. A missing file means defaults unless `required`.
+    pub fn load(file: &Path, required: bool, overrides: &[String]) -> Result<Self, ConfigError> {
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => String::new(),
+            Err(e) => return Err(ConfigError::Unreadable { file: file.to_path_buf(), error: e.to_string() }),
+        };
+        Self::from_toml(&text, file, overrides)
+    }
+
+    /// Parses `text`, applies `overrides` (`section.key=value`) and validates the
+    /// result. `file` is only used in messages.
+    pub fn from_toml(text: &str, file: &Path, overrides: &[String]) -> Result<Self, ConfigError> {
+        let mut table: toml::Table = text.parse()
+            .map_err(|e: toml::de::Error| ConfigError::Syntax { file: file.to_path_buf(), error: e.to_string() })?;
+
+        for arg in overrides {
+            apply_override(&mut table, arg)?;
+        }
+
+        // Read back from text so an error can say which key it is about
+        let merged = toml::to_string(&table)
+            .map_err(|e| ConfigError::Invalid { file: file.to_path_buf(), problems: vec![e.to_string()] })?;
+        let config: Config = toml::from_str(&merged)
+            .map_err(|e| ConfigError::Invalid { file: file.to_path_buf(), problems: vec![describe(&merged, &e)] })?;
+        config.check(file)?;
+        Ok(config)
+    }
+
+    fn check(&self, file: &Path) -> Result<(), ConfigError> {
+        let problems = self.validate();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid { file: file.to_path_buf(), problems })
+        }
+    }
+
+    /// Every value that would stop a run from working, as `section.key` and why.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut require = |ok: bool, problem: String| {
+            if !ok {
+                problems.push(problem);
+            }
+        };
+
+        let dir = &self.directory.path;
+        require(dir.as_os_str().is_empty() || dir.is_dir(),
+                format!("directory.path {} is not a directory", dir.display()));
+        let lists = &self.directory.scan_list_dir;
+        require(lists.as_os_str().is_empty() || lists.is_dir(),
+                format!("directory.scan-list-dir {} is not a directory", lists.display()));
+
+        require(self.hashing.quick_bytes >= 1, "hashing.quick-bytes must be at least 1".into());
+        require(self.hashing.sample_bytes >= 1, "hashing.sample-bytes must be at least 1".into());
+
+        require(self.quarantine.retention_days <= quarantine::MAX_RETENTION_DAYS,
+                format!("quarantine.retention-days must be at most {}, not {}",
+                        quarantine::MAX_RETENTION_DAYS, self.quarantine.retention_days));
+
+        let duplicates = &self.duplicates;
+        let root = &duplicates.preferred_root;
+        if duplicates.keeper == Keeper::PreferredRoot {
+            require(!root.as_os_str().is_empty(), "duplicates.preferred-root must be set for keeper = \"preferred-root\"".into());
+            require(root.as_os_str().is_empty() || root.is_dir(),
+                    format!("duplicates.preferred-root {} is not a directory", root.display()));
+        } else {
+            require(root.as_os_str().is_empty(),
+                    "duplicates.preferred-root is only used with keeper = \"preferred-root\"".into());
+        }
+
+        require((1..=MAX_BATCH_SIZE).contains(&self.database.batch_size),
+                format!("database.batch-size must be between 1 and {}, not {}", MAX_BATCH_SIZE, self.database.batch_size));
+
+        let llm = &self.llm;
+        require(llm.temp.is_finite() && llm.temp >= 0.0, format!("LLM.temp must be 0 or more, not {}", llm.temp));
+        require(llm.n_ctx >= 64, format!("LLM.n-ctx must be at least 64, not {}", llm.n_ctx));
+        require(llm.threads >= 1, format!("LLM.threads must be at least 1, not {}", llm.threads));
+        require(llm.cpu_budget >= 1, "LLM.cpu-budget must be at least 1 (percent of one core)".into());
+        if let Some(&core) = llm.cores.iter().max() {
+            require(core < governor::MAX_CORES, format!("LLM.cores must be below {}, not {}", governor::MAX_CORES, core));
+        }
+        require(llm.max_tokens >= 1, format!("LLM.max-tokens must be at least 1, not {}", llm.max_tokens));
+        require((1..=ai_model::MAX_BATCH_FILES).contains(&llm.batch_size),
+                format!("LLM.batch-size must be between 1 and {}, not {}", ai_model::MAX_BATCH_FILES, llm.batch_size));
+        require((1..=llm.n_ctx as usize).contains(&llm.chunk_size),
+                format!("LLM.chunk-size must be between 1 and n-ctx ({}), not {}", llm.n_ctx, llm.chunk_size));
+        if llm.engine == Engine::Replay {
+            require(llm.recording.is_file(),
+                    format!("LLM.recording {} must be a file for the replay engine", llm.recording.display()));
+        }
+        if let Some(model) = &llm.model {
+            require(model.extension().is_some_and(|e| e == "gguf"),
+                    format!("LLM.model {} is not a .gguf file", model.display()));
+        }
+
+        require(self.ui.poll_ms >= 1, "ui.poll-ms must be at least 1".into());
+        require(self.ui.undo_limit >= 1, "ui.undo-limit must be at least 1".into());
+
+        problems
+    }
+}
+
+/// A deserialization error as `section.key: message`, when its span points at a key.
+fn describe(text: &str, e: &toml::de::Error) -> String {
+    let Some(span) = e.span() else {
+        return e.message().to_string();
+    };
+    let before = &text[..span.start];
+    let line = text[before.rfind('\n').map_or(0, |i| i + 1)..].lines().next().unwrap_or("");
+    let key = line.split('=').next().unwrap_or("").trim();
+    if key.is_empty() || key.starts_with('[') {
+        return e.message().to_string();
+    }
+    let section = before.lines().rev().find_map(|l| l.trim().strip_prefix('[')?.strip_suffix(']'));
+    match section {
+        Some(section) => format!("{}.{}: {}", section, key, e.message()),
+        None => format!("{}: {}", key, e.message()),
+    }
+}
+
+/// Sets one dotted key, e.g. `LLM.temp=0.7`. The value is read as TOML, so
+/// numbers and booleans keep their type; anything else is taken as a string.
+fn apply_override(table: &mut toml::Table, arg: &str) -> Result<(), ConfigError> {
+    let bad = |reason: &str| ConfigError::BadOverride { arg: arg.to_string(), reason: reason.to_string() };
+
+    let (key, raw) = arg.split_once('=').ok_or_else(|| bad("expected KEY=VALUE"))?;
+    let mut parts: Vec<&str> = key.trim().split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(bad("the key must look like section.key"));
+    }
+    let value = format!("v = {}", raw.trim())
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.trim().to_string()));
+
+    let last = parts.pop().unwrap_or_default();
+    let mut section = table;
+    for part in parts {
+        section = section
+            .entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| bad(&format!("{} is not a section", part)))?;
+    }
+    section.insert(last.to_string(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn parse(text: &str, overrides: &[&str]) -> Result<Config, ConfigError> {
+        let overrides: Vec<String> = overrides.iter().map(|s| s.to_string()).collect();
+        Config::from_toml(text, Path::new("config.toml"), &overrides)
+    }
+
+    fn problems(text: &str) -> Vec<String> {
+        match parse(text, &[]) {
+            Err(ConfigError::Invalid { problems, .. }) => problems,
+            other => panic!("expected invalid config, got {:?}", other),
+        }
+    }
+
+    // ── loading ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_empty_file_gives_defaults() {
+        assert_eq!(parse("", &[]).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_defaults_match_previous_behavior() {
+        let mut config = Config::default();
+        config.place(&Dirs::working_dir());
+        assert_eq!(config.database.path, PathBuf::from("./src/db"));
+        assert_eq!(config.quarantine.dir, PathBuf::from("./src/quarantine"));
+        assert_eq!(config.llm.models_dir, PathBuf::from("./src/models"));
+        assert_eq!(config.database.batch_size, 1);
+        assert_eq!(config.llm.temp, 0.0);
+        assert_eq!(config.llm.max_tokens, 64, "room for a whole structured answer");
+        assert_eq!(config.llm.batch_size, 1, "one file per prompt");
+        assert_eq!((config.llm.cores.as_slice(), config.llm.threads), (&[0][..], 1), "one thread on core 0");
+        assert_eq!(config.quarantine.retention(), quarantine::DEFAULT_RETENTION);
+        assert_eq!(config.ui.undo_limit, 50);
+        assert_eq!(config.headless.report, PathBuf::new(), "reports go to stdout");
+    }
+
+    #[test]
+    fn test_reads_every_section() {
+        let config = parse(r#"
+            [directory]
+            path = ""
+
+            [database]
+            batch-size = 15
+
+            [LLM]
+            temp = 0.7
+            max-tokens = 40
+            cpu-budget = 150
+            cores = [0, 1]
+
+            [quarantine]
+            retention-days = 7
+
+            [ui]
+            undo-limit = 5
+
+            [headless]
+            max-files = 3
+            max-mb = 10
+        "#, &[]).unwrap();
+
+        assert_eq!(config.database.batch_size, 15);
+        assert_eq!(config.llm.temp, 0.7);
+        assert_eq!(config.llm.max_tokens, 40);
+        assert_eq!(config.llm.cpu_budget, 150);
+        assert_eq!(config.llm.cores, vec![0, 1]);
+        assert_eq!(config.llm.n_ctx, 2048, "unset keys keep their default");
+        assert_eq!(config.quarantine.retention(), Duration::from_secs(7 * 86400));
+        assert_eq!(config.directory.scan_list_dir(), Path::new("."));
+        assert_eq!(config.ui.undo_limit, 5);
+        assert_eq!(config.headless.max_files, 3);
+        assert_eq!(config.headless.max_bytes(), 10 * 1024 * 1024);
+        assert_eq!(config.headless.min_age(), Duration::from_secs(30 * 86400));
+    }
+
+    #[test]
+    fn test_place_keeps_configured_paths() {
+        let mut config = parse("[database]\npath = \"/srv/db\"", &[]).unwrap();
+        let dirs = Dirs { config: "/c".into(), data: "/d".into(), cache: "/k".into() };
+        config.place(&dirs);
+
+        assert_eq!(config.database.path, PathBuf::from("/srv/db"));
+        assert_eq!(config.database.journal, PathBuf::from("/d/deletions.jsonl"));
+        assert_eq!(config.llm.responses, PathBuf::from("/k/LLM_responses.txt"));
+        assert_eq!(config.llm.prompt_cache, PathBuf::from("/k/prompt-cache"));
+    }
+
+    #[test]
+    fn test_repo_config_file_is_valid() {
+        parse(include_str!("../config.toml"), &[]).unwrap();
+    }
+
+    #[test]
+    fn test_missing_optional_file_gives_defaults() {
+        let missing = std::env::temp_dir().join("cruft_config_missing.toml");
+        let _ = fs::remove_file(&missing);
+        assert_eq!(Config::load(&missing, false, &[]).unwrap(), Config::default());
+        assert!(matches!(Config::load(&missing, true, &[]), Err(ConfigError::Unreadable { .. })));
+    }
+
+    // ── errors ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_bad_toml_is_a_syntax_error() {
+        let err = parse("[database\nbatch-size = 1", &[]).unwrap_err();
+        assert!(matches!(err, ConfigError::Syntax { .. }));
+        assert!(err.to_string().starts_with("config.toml is not valid TOML"));
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let problems = problems("[database]\ncopies = 1");
+        assert!(problems[0].starts_with("database.copies: unknown field"), "{:?}", problems);
+    }
+
+    #[test]
+    fn test_wrong_type_is_rejected() {
+        let problems = problems("[LLM]\nmax-tokens = \"many\"");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("LLM.max-tokens: invalid type"), "{:?}", problems);
+    }
+
+    #[test]
+    fn test_every_bad_value_is_listed() {
+        let problems = problems("[database]\nbatch-size = 0\n[LLM]\ntemp = -1.0\nthreads = 0\n[ui]\nundo-limit = 0");
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems.iter().any(|p| p.starts_with("database.batch-size")));
+        assert!(problems.iter().any(|p| p.starts_with("LLM.temp")));
+    }
+
+    #[test]
+    fn test_cpu_budget_and_cores_are_checked() {
+        let problems = problems(&format!("[LLM]\ncpu-budget = 0\ncores = [0, {}]", governor::MAX_CORES));
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].starts_with("LLM.cpu-budget"));
+        assert!(problems[1].starts_with("LLM.cores must be below 1024"));
+        assert!(parse("", &["LLM.cores=[]"]).unwrap().llm.cores.is_empty(), "any core");
+    }
+
+    #[test]
+    fn test_old_delay_keys_are_gone() {
+        let problems = problems("[LLM]\nchunk-delay-ms = 0");
+        assert!(problems[0].starts_with("LLM.chunk-delay-ms: unknown field"), "{:?}", problems);
+    }
+
+    #[test]
+    fn test_llm_batch_size_is_bounded() {
+        assert_eq!(parse("", &["LLM.batch-size=10"]).unwrap().llm.batch_size, 10);
+        for size in [0, ai_model::MAX_BATCH_FILES + 1] {
+            let problems = problems(&format!("[LLM]\nbatch-size = {}", size));
+            assert!(problems[0].starts_with("LLM.batch-size must be between 1 and 10"), "{:?}", problems);
+        }
+    }
+
+    #[test]
+    fn test_retention_days_is_bounded() {
+        let days = quarantine::MAX_RETENTION_DAYS;
+        let config = parse("", &[&format!("quarantine.retention-days={}", days)]).unwrap();
+        assert_eq!(config.quarantine.retention(), Duration::from_secs(days * 86400));
+        let problems = problems(&format!("[quarantine]\nretention-days = {}", i64::MAX));
+        assert!(problems[0].starts_with("quarantine.retention-days must be at most 36500"), "{:?}", problems);
+        let unchecked = QuarantineConfig { retention_days: u64::MAX, ..Default::default() };
+        assert_eq!(unchecked.retention(), Duration::from_secs(u64::MAX));
+    }
+
+    #[test]
+    fn test_hashing_strategy_is_chosen_by_name() {
+        use crate::hashing::HashStrategy;
+        assert_eq!(Config::default().hashing, HashConfig::default());
+        let config = parse("[hashing]\nstrategy = \"full\"\nsample-bytes = 4096", &["hashing.quick-bytes=512"]).unwrap();
+        assert_eq!(config.hashing, HashConfig { strategy: HashStrategy::Full, quick_bytes: 512, sample_bytes: 4096 });
+        assert!(problems("[hashing]\nstrategy = \"md5\"")[0].starts_with("hashing.strategy: unknown variant"));
+        assert!(problems("[hashing]\nquick-bytes = 0")[0].starts_with("hashing.quick-bytes"));
+    }
+
+    #[test]
+    fn test_engine_is_chosen_by_name() {
+        assert_eq!(Config::default().llm.engine, Engine::Llama);
+        assert_eq!(parse("", &["LLM.engine=mock"]).unwrap().llm.engine, Engine::Mock);
+        assert!(problems("[LLM]\nengine = \"gpt\"")[0].starts_with("LLM.engine: unknown variant"));
+    }
+
+    #[test]
+    fn test_duplicate_keeper_is_chosen_by_name() {
+        use crate::duplicates::KeeperPolicy;
+        assert_eq!(Config::default().duplicates.keeper(), KeeperPolicy::Oldest);
+        let config = parse("[duplicates]\nkeeper = \"shortest-path\"", &[]).unwrap();
+        assert_eq!(config.duplicates.keeper(), KeeperPolicy::ShortestPath);
+        assert!(problems("[duplicates]\nkeeper = \"newest\"")[0].starts_with("duplicates.keeper: unknown variant"));
+
+        let root = std::env::temp_dir();
+        let set = format!("duplicates.preferred-root={:?}", root.display().to_string());
+        let config = parse("[duplicates]\nkeeper = \"preferred-root\"", &[&set]).unwrap();
+        assert_eq!(config.duplicates.keeper(), KeeperPolicy::PreferredRoot(root));
+    }
+
+    #[test]
+    fn test_preferred_root_goes_with_its_keeper() {
+        let unset = problems("[duplicates]\nkeeper = \"preferred-root\"");
+        assert!(unset[0].starts_with("duplicates.preferred-root must be set"), "{:?}", unset);
+        let missing = problems("[duplicates]\nkeeper = \"preferred-root\"\npreferred-root = \"/no/such/dir\"");
+        assert!(missing[0].starts_with("duplicates.preferred-root /no/such/dir is not a directory"), "{:?}", missing);
+        let unused = problems("[duplicates]\npreferred-root = \"/\"");
+        assert!(unused[0].starts_with("duplicates.preferred-root is only used"), "{:?}", unused);
+    }
+
+    #[test]
+    fn test_replay_engine_needs_a_recording() {
+        let problems = problems("[LLM]\nengine = \"replay\"");
+        assert!(problems[0].starts_with("LLM.recording"), "{:?}", problems);
+
+        let recording = std::env::temp_dir().join("cruft_config_recording.jsonl");
+        fs::write(&recording, "").unwrap();
+        let set = format!("LLM.recording={:?}", recording.display().to_string());
+        assert!(parse("[LLM]\nengine = \"replay\"", &[&set]).is_ok());
+        let _ = fs::remove_file(&recording);
+    }
+
+    #[test]
+    fn test_batch_size_cannot_exceed_channel_capacity() {
+        let problems = problems(&format!("[database]\nbatch-size = {}", MAX_BATCH_SIZE + 1));
+        assert!(problems[0].contains("batch-size"));
+    }
+
+    #[test]
+    fn test_invalid_error_names_the_file_on_each_line() {
+        let err = parse("[ui]\npoll-ms = 0\nundo-limit = 0", &[]).unwrap_err();
+        let text = err.to_string();
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.lines().all(|l| l.starts_with("config.toml: ui.")));
+    }
+
+    // ── overrides ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_override_replaces_file_value() {
+        let config = parse("[LLM]\ntemp = 0.2", &["LLM.temp=0.9"]).unwrap();
+        assert_eq!(config.llm.temp, 0.9);
+    }
+
+    #[test]
+    fn test_override_creates_missing_section() {
+        let config = parse("", &["database.batch-size=8", "quarantine.dir=/tmp/held"]).unwrap();
+        assert_eq!(config.database.batch_size, 8);
+        assert_eq!(config.quarantine.dir, PathBuf::from("/tmp/held"));
+    }
+
+    #[test]
+    fn test_override_is_validated() {
+        assert!(matches!(parse("", &["ui.poll-ms=0"]), Err(ConfigError::Invalid { .. })));
+        assert!(matches!(parse("", &["LLM.tmp=1"]), Err(ConfigError::Invalid { .. })));
+    }
+
+    #[test]
+    fn test_malformed_override_is_rejected() {
+        assert!(matches!(parse("", &["LLM.temp"]), Err(ConfigError::BadOverride { .. })));
+        assert!(matches!(parse("", &["LLM..temp=1"]), Err(ConfigError::BadOverride { .. })));
+        assert!(matches!(parse("[ui]\npoll-ms = 5", &["ui.poll-ms.x=1"]), Err(ConfigError::BadOverride { .. })));
+    }
+}
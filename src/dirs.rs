@@ -0,0 +1,181 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::cli::CONFIG_FILE;
+
+/// Directory under each XDG base directory that holds this tool's files.
+pub(crate) const APP_DIR: &str = "cruft-crawler";
+
+/// Where CruftCrawler keeps its files, per the XDG Base Directory specification:
+/// settings under `$XDG_CONFIG_HOME`, the database, quarantine, journal and
+/// models under `$XDG_DATA_HOME`, and model output under `$XDG_CACHE_HOME`.
+/// Nothing here is created until it is needed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Dirs {
+    pub config: PathBuf,
+    pub data:   PathBuf,
+    pub cache:  PathBuf,
+}
+
+impl Dirs {
+    /// `cruft-crawler` under each base directory. Without a home directory to
+    /// resolve them from, the old layout in the working directory is used.
+    pub fn from_env() -> Self {
+        Self::from_vars(|var| std::env::var_os(var))
+    }
+
+    /// `from_env` with the environment supplied by `var`.
+    pub fn from_vars(var: impl Fn(&str) -> Option<OsString>) -> Self {
+        let base = |name: &str, under_home: &str| base_dir(&var, name, under_home);
+        match (base("XDG_CONFIG_HOME", ".config"), base("XDG_DATA_HOME", ".local/share"), base("XDG_CACHE_HOME", ".cache")) {
+            (Some(config), Some(data), Some(cache)) => Self {
+                config: config.join(APP_DIR),
+                data:   data.join(APP_DIR),
+                cache:  cache.join(APP_DIR),
+            },
+            _ => Self::working_dir(),
+        }
+    }
+
+    /// The layout used before XDG directories: everything under `./src`,
+    /// with the configuration and model output next to it.
+    pub fn working_dir() -> Self {
+        Self {
+            config: PathBuf::from("."),
+            data:   PathBuf::from("./src"),
+            cache:  PathBuf::from("."),
+        }
+    }
+
+    /// The configuration file to read when none is given: the one in the
+    /// config directory, else a `config.toml` in the working directory.
+    pub fn find_config_file(&self) -> Option<PathBuf> {
+        [self.config.join(CONFIG_FILE), PathBuf::from(CONFIG_FILE)]
+            .into_iter()
+            .find(|file| file.is_file())
+    }
+
+    pub fn db(&self) -> PathBuf {
+        self.data.join("db")
+    }
+
+    pub fn quarantine(&self) -> PathBuf {
+        self.data.join("quarantine")
+    }
+
+    pub fn journal_file(&self) -> PathBuf {
+        self.data.join("deletions.jsonl")
+    }
+
+    pub fn models(&self) -> PathBuf {
+        self.data.join("models")
+    }
+
+    pub fn responses_file(&self) -> PathBuf {
+        self.cache.join("LLM_responses.txt")
+    }
+
+    pub fn prompt_cache(&self) -> PathBuf {
+        self.cache.join("prompt-cache")
+    }
+}
+
+/// `$XDG_DATA_HOME`, or `~/.local/share`. `None` when neither is set.
+pub(crate) fn data_home() -> Option<PathBuf> {
+    base_dir(&|var: &str| std::env::var_os(var), "XDG_DATA_HOME", ".local/share")
+}
+
+/// The base directory named by `name`, else `under_home` in `$HOME`. The
+/// specification says a relative value must be ignored.
+fn base_dir(var: &impl Fn(&str) -> Option<OsString>, name: &str, under_home: &str) -> Option<PathBuf> {
+    var(name)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| var("HOME").filter(|h| !h.is_empty()).map(|h| PathBuf::from(h).join(under_home)))
+}
+
+/// Creates the parent directory of `file` if it is missing.
+pub(crate) fn create_parent(file: &Path) -> io::Result<()> {
+    match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn dirs_with(vars: &[(&str, &str)]) -> Dirs {
+        let vars: HashMap<String, OsString> = vars.iter().map(|(k, v)| (k.to_string(), OsString::from(v))).collect();
+        Dirs::from_vars(|name| vars.get(name).cloned())
+    }
+
+    // ── Dirs::from_vars ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_defaults_under_home() {
+        let dirs = dirs_with(&[("HOME", "/home/me")]);
+        assert_eq!(dirs.config, PathBuf::from("/home/me/.config/cruft-crawler"));
+        assert_eq!(dirs.data, PathBuf::from("/home/me/.local/share/cruft-crawler"));
+        assert_eq!(dirs.cache, PathBuf::from("/home/me/.cache/cruft-crawler"));
+    }
+
+    #[test]
+    fn test_xdg_variables_win_over_home() {
+        let dirs = dirs_with(&[("HOME", "/home/me"), ("XDG_DATA_HOME", "/data"), ("XDG_CACHE_HOME", "/tmp/cache")]);
+        assert_eq!(dirs.db(), PathBuf::from("/data/cruft-crawler/db"));
+        assert_eq!(dirs.responses_file(), PathBuf::from("/tmp/cache/cruft-crawler/LLM_responses.txt"));
+        assert_eq!(dirs.prompt_cache(), PathBuf::from("/tmp/cache/cruft-crawler/prompt-cache"));
+        assert_eq!(dirs.config, PathBuf::from("/home/me/.config/cruft-crawler"));
+    }
+
+    #[test]
+    fn test_relative_xdg_variable_is_ignored() {
+        let dirs = dirs_with(&[("HOME", "/home/me"), ("XDG_DATA_HOME", "data")]);
+        assert_eq!(dirs.data, PathBuf::from("/home/me/.local/share/cruft-crawler"));
+    }
+
+    #[test]
+    fn test_no_home_falls_back_to_working_dir() {
+        let dirs = dirs_with(&[]);
+        assert_eq!(dirs, Dirs::working_dir());
+        assert_eq!(dirs.db(), PathBuf::from("./src/db"));
+        assert_eq!(dirs.quarantine(), PathBuf::from("./src/quarantine"));
+        assert_eq!(dirs.models(), PathBuf::from("./src/models"));
+    }
+
+    #[test]
+    fn test_all_base_directories_given_without_home() {
+        let dirs = dirs_with(&[("XDG_CONFIG_HOME", "/c"), ("XDG_DATA_HOME", "/d"), ("XDG_CACHE_HOME", "/k")]);
+        assert_eq!(dirs.journal_file(), PathBuf::from("/d/cruft-crawler/deletions.jsonl"));
+    }
+
+    // ── find_config_file ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_config_dir_file_is_preferred() {
+        let base = std::env::temp_dir().join("cruft_dirs_config");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        fs::write(base.join(CONFIG_FILE), "").unwrap();
+        let dirs = Dirs { config: base.clone(), ..Dirs::working_dir() };
+
+        assert_eq!(dirs.find_config_file(), Some(base.join(CONFIG_FILE)));
+    }
+
+    // ── create_parent ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_create_parent_makes_missing_directories() {
+        let base = std::env::temp_dir().join("cruft_dirs_parent");
+        let _ = fs::remove_dir_all(&base);
+        create_parent(&base.join("a/b/file.txt")).unwrap();
+        assert!(base.join("a/b").is_dir());
+        create_parent(Path::new("file.txt")).unwrap();
+    }
+}
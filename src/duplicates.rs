@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::actor::crawler::FileMeta;
+
+/// Rule for choosing which copy in a duplicate group is kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum KeeperPolicy {
+    /// Earliest created (then modified) copy.
+    #[default]
+    Oldest,
+    /// Copy with the fewest path components, i.e. the least nested one.
+    ShortestPath,
+    /// First copy found under this directory; falls back to `Oldest` when none is.
+    PreferredRoot(PathBuf),
+}
+
+/// Files with identical size and full-content hash. `keeper` stays on disk,
+/// `redundant` are the copies suggested for deletion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DuplicateGroup {
+    pub size:         u64,
+    pub content_hash: String,
+    pub keeper:       FileMeta,
+    pub redundant:    Vec<FileMeta>,
+}
+
+impl DuplicateGroup {
+    /// Bytes freed if every redundant copy is removed.
+    pub fn reclaimable(&self) -> u64 {
+        self.size * self.redundant.len() as u64
+    }
+
+    /// Paths of every copy, keeper first.
+    pub fn member_paths(&self) -> Vec<PathBuf> {
+        std::iter::once(&self.keeper)
+            .chain(self.redundant.iter())
+            .map(|m| m.abs_path.clone())
+            .collect()
+    }
+}
+
+/// Collects file records and groups the ones sharing size and content hash.
+/// Only records with a full-content hash take part; empty files are ignored
+/// since they are all "identical" and often load-bearing (`__init__.py`, `.keep`).
+#[derive(Default)]
+pub(crate) struct DuplicateFinder {
+    buckets: HashMap<(u64, String), Vec<FileMeta>>,
+}
+
+impl DuplicateFinder {
+    pub fn add(&mut self, meta: FileMeta) {
+        if !meta.is_file || meta.size == 0 {
+            return;
+        }
+        let Some(hash) = meta.content_hash() else {
+            return;
+        };
+
+        let bucket = self.buckets.entry((meta.size, hash.to_string())).or_default();
+        // a rescanned or revisited file replaces its earlier record
+        bucket.retain(|m| m.abs_path != meta.abs_path);
+        bucket.push(meta);
+    }
+
+    /// Every bucket with more than one member, largest reclaimable space first.
+    pub fn groups(&self, policy: &KeeperPolicy) -> Vec<DuplicateGroup> {
+        let mut groups: Vec<DuplicateGroup> = self.buckets
+            .iter()
+            .filter(|(_, members)| members.len() > 1)
+            .map(|((size, hash), members)| {
+                let keeper_idx = elect_keeper(members, policy);
+                let mut redundant = members.clone();
+                let keeper = redundant.remove(keeper_idx);
+                DuplicateGroup { size: *size, content_hash: hash.clone(), keeper, redundant }
+            })
+            .collect();
+
+        groups.sort_by(|a, b| b.reclaimable().cmp(&a.reclaimable()).then(a.content_hash.cmp(&b.content_hash)));
+        groups
+    }
+}
+
+/// Index of the member to keep. Ties fall through to the next rule so the
+/// choice is stable regardless of crawl order.
+pub(crate) fn elect_keeper(members: &[FileMeta], policy: &KeeperPolicy) -> usize {
+    members
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, m)| {
+            let depth = m.abs_path.components().count();
+            // false sorts first, so copies under the preferred root win
+            let outside_preferred = match policy {
+                KeeperPolicy::PreferredRoot(root) => !m.abs_path.starts_with(root),
+                _ => false,
+            };
+            let order = match policy {
+                KeeperPolicy::ShortestPath => (depth as i64, m.created, m.modified),
+                _ => (m.created, m.modified, depth as i64),
+            };
+            (outside_preferred, order, m.abs_path.clone())
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::HashStrategy;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn make_meta(abs_path: &str, size: u64, full_hash: Option<&str>, created: i64) -> FileMeta {
+        let path = PathBuf::from(abs_path);
+        FileMeta {
+            rel_path: path.clone(),
+            abs_path: path.clone(),
+            file_name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            hash: "sampled".to_string(),
+            is_file: true,
+            size,
+            modified: created,
+            created,
+            readonly: false,
+            hash_strategy: HashStrategy::Sampled,
+            full_hash: full_hash.map(str::to_string),
+            inode: 0,
+        }
+    }
+
+    fn paths(metas: &[FileMeta]) -> Vec<&str> {
+        metas.iter().map(|m| m.abs_path.to_str().unwrap()).collect()
+    }
+
+    // ── DuplicateFinder ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_groups_by_size_and_hash() {
+        let mut finder = DuplicateFinder::default();
+        finder.add(make_meta("/a/one.txt", 10, Some("h1"), 100));
+        finder.add(make_meta("/b/one.txt", 10, Some("h1"), 200));
+        finder.add(make_meta("/c/other.txt", 10, Some("h2"), 100));
+        finder.add(make_meta("/d/bigger.txt", 11, Some("h1"), 100));
+
+        let groups = finder.groups(&KeeperPolicy::Oldest);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].keeper.abs_path, PathBuf::from("/a/one.txt"));
+        assert_eq!(paths(&groups[0].redundant), vec!["/b/one.txt"]);
+        assert_eq!(groups[0].reclaimable(), 10);
+    }
+
+    #[test]
+    fn test_records_without_full_hash_are_ignored() {
+        let mut finder = DuplicateFinder::default();
+        finder.add(make_meta("/a", 10, None, 1));
+        finder.add(make_meta("/b", 10, None, 2));
+        assert!(finder.groups(&KeeperPolicy::Oldest).is_empty());
+    }
+
+    #[test]
+    fn test_empty_files_and_directories_are_ignored() {
+        let mut finder = DuplicateFinder::default();
+        finder.add(make_meta("/a/__init__.py", 0, Some("e"), 1));
+        finder.add(make_meta("/b/__init__.py", 0, Some("e"), 2));
+        let mut dir = make_meta("/dir1", 4096, Some("d"), 1);
+        dir.is_file = false;
+        finder.add(dir.clone());
+        dir.abs_path = PathBuf::from("/dir2");
+        finder.add(dir);
+
+        assert!(finder.groups(&KeeperPolicy::Oldest).is_empty());
+    }
+
+    #[test]
+    fn test_re_added_path_replaces_previous_record() {
+        let mut finder = DuplicateFinder::default();
+        finder.add(make_meta("/a", 10, Some("h"), 1));
+        finder.add(make_meta("/a", 10, Some("h"), 5));
+        assert!(finder.groups(&KeeperPolicy::Oldest).is_empty());
+    }
+
+    #[test]
+    fn test_groups_sorted_by_reclaimable_space() {
+        let mut finder = DuplicateFinder::default();
+        finder.add(make_meta("/s1", 10, Some("small"), 1));
+        finder.add(make_meta("/s2", 10, Some("small"), 2));
+        finder.add(make_meta("/b1", 500, Some("big"), 1));
+        finder.add(make_meta("/b2", 500, Some("big"), 2));
+
+        let groups = finder.groups(&KeeperPolicy::Oldest);
+        assert_eq!(groups[0].content_hash, "big");
+        assert_eq!(groups[1].content_hash, "small");
+    }
+
+    #[test]
+    fn test_member_paths_lists_keeper_first() {
+        let mut finder = DuplicateFinder::default();
+        finder.add(make_meta("/new", 10, Some("h"), 9));
+        finder.add(make_meta("/old", 10, Some("h"), 1));
+        let group = &finder.groups(&KeeperPolicy::Oldest)[0];
+        assert_eq!(group.member_paths(), vec![PathBuf::from("/old"), PathBuf::from("/new")]);
+    }
+
+    // ── elect_keeper ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_keeper_oldest() {
+        let members = vec![
+            make_meta("/x/new.txt", 1, Some("h"), 300),
+            make_meta("/x/old.txt", 1, Some("h"), 100),
+        ];
+        assert_eq!(elect_keeper(&members, &KeeperPolicy::Oldest), 1);
+    }
+
+    #[test]
+    fn test_keeper_shortest_path() {
+        let members = vec![
+            make_meta("/home/me/a/b/c/file.txt", 1, Some("h"), 1),
+            make_meta("/home/me/file.txt", 1, Some("h"), 2),
+        ];
+        assert_eq!(elect_keeper(&members, &KeeperPolicy::ShortestPath), 1);
+    }
+
+    #[test]
+    fn test_keeper_preferred_root() {
+        let members = vec![
+            make_meta("/downloads/photo.jpg", 1, Some("h"), 1),
+            make_meta("/pictures/2020/photo.jpg", 1, Some("h"), 2),
+        ];
+        let policy = KeeperPolicy::PreferredRoot(PathBuf::from("/pictures"));
+        assert_eq!(elect_keeper(&members, &policy), 1);
+    }
+
+    #[test]
+    fn test_keeper_preferred_root_falls_back_to_oldest() {
+        let members = vec![
+            make_meta("/a/photo.jpg", 1, Some("h"), 5),
+            make_meta("/b/photo.jpg", 1, Some("h"), 2),
+        ];
+        let policy = KeeperPolicy::PreferredRoot(PathBuf::from("/pictures"));
+        assert_eq!(elect_keeper(&members, &policy), 1);
+    }
+
+    #[test]
+    fn test_keeper_tie_is_stable_by_path() {
+        let members = vec![
+            make_meta("/b.txt", 1, Some("h"), 1),
+            make_meta("/a.txt", 1, Some("h"), 1),
+        ];
+        assert_eq!(elect_keeper(&members, &KeeperPolicy::Oldest), 1);
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use crate::scan_roots::ScanRoot;
+
+/// Ignore files honored in any directory the crawler walks into.
+pub(crate) const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".cruftignore"];
+/// Patterns in this file, read from the working directory, apply to every root.
+pub(crate) const GLOBAL_IGNORE_FILE: &str = ".cruftignore";
+
+/// Reads the global ignore file from `dir`. A missing file means no global rules.
+pub(crate) fn load_global_patterns(dir: &Path) -> Vec<String> {
+    std::fs::read_to_string(dir.join(GLOBAL_IGNORE_FILE))
+        .map(|contents| contents.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Checks that `pattern` is valid gitignore syntax.
+pub(crate) fn validate_pattern(pattern: &str) -> Result<(), String> {
+    GitignoreBuilder::new("")
+        .add_line(None, pattern)
+        .map(|_| ())
+        .map_err(|e| format!("bad pattern '{}': {}", pattern, e))
+}
+
+/// Gitignore-style exclusion rules for one scan root. A path is checked against,
+/// in order, the root's own `exclude=`/`include=` options, the `.gitignore` and
+/// `.cruftignore` files of the directories above it (deepest first), and the
+/// global patterns. The first source with an opinion decides; `!pattern` and
+/// `include=` re-include what a less specific source excluded.
+pub(crate) struct ExcludeRules {
+    root_path:         PathBuf,
+    root:              Gitignore,
+    global:            Gitignore,
+    // only directories that actually contain an ignore file
+    nested:            HashMap<PathBuf, Gitignore>,
+    read_ignore_files: bool,
+}
+
+impl ExcludeRules {
+    pub fn new(scan_root: &ScanRoot, global_patterns: &[String]) -> Self {
+        let includes: Vec<String> = scan_root.includes.iter().map(|p| format!("!{}", p)).collect();
+        let root_lines = scan_root.excludes.iter().chain(includes.iter());
+
+        Self {
+            root_path: scan_root.path.clone(),
+            root: build_matcher(&scan_root.path, root_lines),
+            global: build_matcher(&scan_root.path, global_patterns.iter()),
+            nested: HashMap::new(),
+            read_ignore_files: scan_root.ignore_files,
+        }
+    }
+
+    /// True if `path` should be skipped, along with everything below it.
+    ///
+    /// Meant to be called for every entry in walk order: the ignore files of an
+    /// accepted directory are read here, before any of its children are checked.
+    pub fn is_excluded(&mut self, path: &Path, is_dir: bool) -> bool {
+        // the root itself is never excluded by its own rules
+        let excluded = path != self.root_path && self.decide(path, is_dir);
+        if !excluded && is_dir && self.read_ignore_files {
+            self.load_ignore_files(path);
+        }
+        excluded
+    }
+
+    fn decide(&self, path: &Path, is_dir: bool) -> bool {
+        if let Some(excluded) = verdict(self.root.matched(path, is_dir)) {
+            return excluded;
+        }
+        for dir in path.ancestors().skip(1) {
+            if let Some(excluded) = self.nested.get(dir).and_then(|gi| verdict(gi.matched(path, is_dir))) {
+                return excluded;
+            }
+            if dir == self.root_path {
+                break;
+            }
+        }
+        self.global.matched(path, is_dir).is_ignore()
+    }
+
+    fn load_ignore_files(&mut self, dir: &Path) {
+        let mut builder = GitignoreBuilder::new(dir);
+        for name in IGNORE_FILE_NAMES {
+            let file = dir.join(name);
+            if file.is_file() && let Some(e) = builder.add(&file) {
+                eprintln!("  ⚠ Ignoring bad lines in {}: {}", file.display(), e);
+            }
+        }
+        match builder.build() {
+            Ok(gi) if !gi.is_empty() => {
+                self.nested.insert(dir.to_path_buf(), gi);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("  ⚠ Could not use ignore files in {}: {}", dir.display(), e),
+        }
+    }
+}
+
+/// `Some(true)` to exclude, `Some(false)` to keep, `None` if no pattern matched.
+fn verdict<T>(m: Match<T>) -> Option<bool> {
+    match m {
+        Match::None => None,
+        Match::Ignore(_) => Some(true),
+        Match::Whitelist(_) => Some(false),
+    }
+}
+
+/// Matcher for gitignore lines anchored at `root`. Bad lines are reported and skipped.
+fn build_matcher<'a>(root: &Path, lines: impl Iterator<Item = &'a String>) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for line in lines {
+        if let Err(e) = builder.add_line(None, line) {
+            eprintln!("  ⚠ Skipping exclude pattern '{}': {}", line, e);
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        eprintln!("  ⚠ Could not build exclude rules for {}: {}", root.display(), e);
+        Gitignore::empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cruft_exclude_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn root_with(path: &Path, excludes: &[&str], includes: &[&str]) -> ScanRoot {
+        let mut root = ScanRoot::new(path.to_path_buf());
+        root.excludes = excludes.iter().map(|s| s.to_string()).collect();
+        root.includes = includes.iter().map(|s| s.to_string()).collect();
+        root
+    }
+
+    // ── per-root patterns ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_name_pattern_matches_at_any_depth() {
+        let root = PathBuf::from("/home/me");
+        let mut rules = ExcludeRules::new(&root_with(&root, &["node_modules", "*.o"], &[]), &[]);
+
+        assert!(rules.is_excluded(Path::new("/home/me/app/node_modules"), true));
+        assert!(rules.is_excluded(Path::new("/home/me/build/main.o"), false));
+        assert!(!rules.is_excluded(Path::new("/home/me/app/src/main.rs"), false));
+    }
+
+    #[test]
+    fn test_anchored_pattern_only_matches_at_root() {
+        let root = PathBuf::from("/home/me");
+        let mut rules = ExcludeRules::new(&root_with(&root, &["/build"], &[]), &[]);
+
+        assert!(rules.is_excluded(Path::new("/home/me/build"), true));
+        assert!(!rules.is_excluded(Path::new("/home/me/app/build"), true));
+    }
+
+    #[test]
+    fn test_directory_only_pattern_skips_files() {
+        let root = PathBuf::from("/home/me");
+        let mut rules = ExcludeRules::new(&root_with(&root, &["cache/"], &[]), &[]);
+
+        assert!(rules.is_excluded(Path::new("/home/me/cache"), true));
+        assert!(!rules.is_excluded(Path::new("/home/me/cache"), false));
+    }
+
+    #[test]
+    fn test_include_overrides_exclude() {
+        let root = PathBuf::from("/home/me");
+        let mut rules = ExcludeRules::new(&root_with(&root, &["*.log"], &["keep.log"]), &[]);
+
+        assert!(rules.is_excluded(Path::new("/home/me/a.log"), false));
+        assert!(!rules.is_excluded(Path::new("/home/me/keep.log"), false));
+    }
+
+    #[test]
+    fn test_root_itself_is_never_excluded() {
+        let root = PathBuf::from("/srv/node_modules");
+        let mut rules = ExcludeRules::new(&root_with(&root, &["node_modules"], &[]), &[]);
+        assert!(!rules.is_excluded(&root, true));
+    }
+
+    #[test]
+    fn test_folder_named_target_is_not_special() {
+        let root = PathBuf::from("/home/me");
+        let mut rules = ExcludeRules::new(&ScanRoot::new(root), &[]);
+        assert!(!rules.is_excluded(Path::new("/home/me/target"), true));
+    }
+
+    // ── global patterns ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_global_patterns_apply_to_root() {
+        let root = PathBuf::from("/home/me");
+        let global = vec!["*.tmp".to_string()];
+        let mut rules = ExcludeRules::new(&ScanRoot::new(root), &global);
+        assert!(rules.is_excluded(Path::new("/home/me/x/y.tmp"), false));
+    }
+
+    #[test]
+    fn test_root_include_overrides_global_exclude() {
+        let root = PathBuf::from("/home/me");
+        let global = vec!["*.tmp".to_string()];
+        let mut rules = ExcludeRules::new(&root_with(&root, &[], &["important.tmp"]), &global);
+        assert!(!rules.is_excluded(Path::new("/home/me/important.tmp"), false));
+    }
+
+    #[test]
+    fn test_load_global_patterns_missing_file_is_empty() {
+        let dir = temp_dir("global_missing");
+        assert!(load_global_patterns(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_load_global_patterns_reads_lines() {
+        let dir = temp_dir("global_lines");
+        fs::write(dir.join(GLOBAL_IGNORE_FILE), "# comment\n*.tmp\n!keep.tmp\n").unwrap();
+        assert_eq!(load_global_patterns(&dir), vec!["# comment", "*.tmp", "!keep.tmp"]);
+    }
+
+    // ── ignore files in the tree ──────────────────────────────────────────────
+
+    #[test]
+    fn test_gitignore_applies_below_its_directory() {
+        let root = temp_dir("gitignore");
+        fs::create_dir_all(root.join("proj")).unwrap();
+        fs::write(root.join("proj/.gitignore"), "/target\n*.log\n").unwrap();
+        let mut rules = ExcludeRules::new(&ScanRoot::new(root.clone()), &[]);
+
+        assert!(!rules.is_excluded(&root, true));
+        assert!(!rules.is_excluded(&root.join("proj"), true));
+        assert!(rules.is_excluded(&root.join("proj/target"), true));
+        assert!(rules.is_excluded(&root.join("proj/x.log"), false));
+        // siblings of proj/ are unaffected
+        assert!(!rules.is_excluded(&root.join("other.log"), false));
+    }
+
+    #[test]
+    fn test_cruftignore_is_honored() {
+        let root = temp_dir("cruftignore");
+        fs::write(root.join(".cruftignore"), "photos/\n").unwrap();
+        let mut rules = ExcludeRules::new(&ScanRoot::new(root.clone()), &[]);
+
+        rules.is_excluded(&root, true);
+        assert!(rules.is_excluded(&root.join("photos"), true));
+    }
+
+    #[test]
+    fn test_deeper_ignore_file_can_reinclude() {
+        let root = temp_dir("reinclude");
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("a/b/.gitignore"), "!wanted.log\n").unwrap();
+        let mut rules = ExcludeRules::new(&ScanRoot::new(root.clone()), &[]);
+
+        for dir in [root.clone(), root.join("a"), root.join("a/b")] {
+            assert!(!rules.is_excluded(&dir, true));
+        }
+        assert!(!rules.is_excluded(&root.join("a/b/wanted.log"), false));
+        assert!(rules.is_excluded(&root.join("a/b/other.log"), false));
+    }
+
+    #[test]
+    fn test_ignore_files_can_be_turned_off() {
+        let root = temp_dir("no_ignore_files");
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        let mut scan_root = ScanRoot::new(root.clone());
+        scan_root.ignore_files = false;
+        let mut rules = ExcludeRules::new(&scan_root, &[]);
+
+        rules.is_excluded(&root, true);
+        assert!(!rules.is_excluded(&root.join("x.log"), false));
+    }
+
+    // ── validate_pattern ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_validate_pattern() {
+        assert!(validate_pattern("*.o").is_ok());
+        assert!(validate_pattern("!keep/**").is_ok());
+        assert!(validate_pattern("[z-a]").is_err());
+    }
+}
@@ -0,0 +1,417 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use sled::Transactional;
+use sled::transaction::ConflictableTransactionError;
+use crate::actor::crawler::FileMeta;
+use crate::actor::ai_model::VerdictRecord;
+use crate::hashing::HashStrategy;
+
+/// One `IndexEntry` per absolute path, keyed by the path's bytes.
+pub(crate) const RECORDS_TREE: &str = "file_index";
+/// Secondary index: `<content hash>\0<path>` → path, for every record with a full hash.
+pub(crate) const BY_HASH_TREE: &str = "by_hash";
+/// Scan history: big-endian scan id → start time (unix seconds).
+pub(crate) const SCANS_TREE: &str = "scans";
+
+/// What the last scan knew about a path: its metadata and the AI's verdict, if any.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct IndexEntry {
+    pub meta:    FileMeta,
+    pub verdict: Option<VerdictRecord>,
+    /// Scan that last wrote this record; 0 if written outside a scan.
+    #[serde(default)]
+    pub scan_id: u64,
+}
+
+impl IndexEntry {
+    pub fn new(meta: FileMeta, verdict: Option<VerdictRecord>) -> Self {
+        Self { meta, verdict, scan_id: 0 }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(serde_cbor::to_vec(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_cbor::from_slice(bytes)?)
+    }
+}
+
+/// Key for a path: its raw OS bytes, so non-UTF-8 names round-trip exactly.
+pub(crate) fn path_key(path: &Path) -> &[u8] {
+    path.as_os_str().as_encoded_bytes()
+}
+
+/// Key in the hash tree. Hex hashes never contain `\0`, so `<hash>\0` is a
+/// prefix shared by exactly the records with that hash.
+fn hash_key(hash: &str, path: &Path) -> Vec<u8> {
+    let mut key = hash_prefix(hash);
+    key.extend_from_slice(path_key(path));
+    key
+}
+
+fn hash_prefix(hash: &str) -> Vec<u8> {
+    let mut prefix = hash.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+/// Path-keyed file records with a content-hash secondary index, kept in step
+/// inside sled transactions. Cheap to clone; every clone sees the same trees.
+#[derive(Clone)]
+pub(crate) struct FileIndex {
+    db:      sled::Db,
+    records: sled::Tree,
+    by_hash: sled::Tree,
+    scans:   sled::Tree,
+}
+
+impl FileIndex {
+    pub fn open(db: &sled::Db) -> sled::Result<Self> {
+        Ok(Self {
+            db:      db.clone(),
+            records: db.open_tree(RECORDS_TREE)?,
+            by_hash: db.open_tree(BY_HASH_TREE)?,
+            scans:   db.open_tree(SCANS_TREE)?,
+        })
+    }
+
+    /// Records the start of a scan and returns its id. Ids only ever grow,
+    /// including across restarts.
+    pub fn begin_scan(&self) -> Result<u64, Box<dyn Error>> {
+        let id = self.db.generate_id()?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        self.scans.insert(id.to_be_bytes(), &now.to_be_bytes())?;
+        Ok(id)
+    }
+
+    /// Id and start time of the most recent scan, if any.
+    pub fn latest_scan(&self) -> Result<Option<(u64, i64)>, Box<dyn Error>> {
+        let Some((id, started)) = self.scans.last()? else {
+            return Ok(None);
+        };
+        let id = u64::from_be_bytes(id.as_ref().try_into()?);
+        let started = i64::from_be_bytes(started.as_ref().try_into()?);
+        Ok(Some((id, started)))
+    }
+
+    pub fn get(&self, path: &Path) -> Result<Option<IndexEntry>, Box<dyn Error>> {
+        match self.records.get(path_key(path))? {
+            Some(raw) => Ok(Some(IndexEntry::from_bytes(&raw)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Inserts or replaces the record for `entry.meta.abs_path`, moving its
+    /// hash-index entry if the content hash changed.
+    pub fn put(&self, entry: &IndexEntry) -> Result<(), Box<dyn Error>> {
+        let key = path_key(&entry.meta.abs_path);
+        let value = entry.to_bytes()?;
+        let new_hash = entry.meta.content_hash().map(|h| hash_key(h, &entry.meta.abs_path));
+        let path_value = serde_cbor::to_vec(&entry.meta.abs_path)?;
+
+        (&self.records, &self.by_hash)
+            .transaction(|(records, by_hash)| {
+                if let Some(old) = records.insert(key, value.as_slice())? {
+                    unlink_hash(by_hash, &old)?;
+                }
+                if let Some(hk) = &new_hash {
+                    by_hash.insert(hk.as_slice(), path_value.as_slice())?;
+                }
+                Ok::<(), ConflictableTransactionError<()>>(())
+            })
+            .map_err(|e| format!("could not store {:?}: {:?}", entry.meta.abs_path, e))?;
+        Ok(())
+    }
+
+    pub fn remove(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let key = path_key(path);
+        (&self.records, &self.by_hash)
+            .transaction(|(records, by_hash)| {
+                if let Some(old) = records.remove(key)? {
+                    unlink_hash(by_hash, &old)?;
+                }
+                Ok::<(), ConflictableTransactionError<()>>(())
+            })
+            .map_err(|e| format!("could not remove {:?}: {:?}", path, e))?;
+        Ok(())
+    }
+
+    /// Stores fresh metadata for a path. The previous verdict survives only if the
+    /// file is the same one it was given for; otherwise it is stale and dropped.
+    pub fn upsert_meta(&self, meta: FileMeta, scan_id: u64) -> Result<(), Box<dyn Error>> {
+        let verdict = self.get(&meta.abs_path)?
+            .filter(|prev| same_file_version(&prev.meta, &meta) && prev.meta.hash == meta.hash)
+            .and_then(|prev| prev.verdict);
+        self.put(&IndexEntry { meta, verdict, scan_id })
+    }
+
+    /// Every indexed entry.
+    pub fn entries(&self) -> impl Iterator<Item = IndexEntry> + use<> {
+        self.records.iter()
+            .values()
+            .filter_map(|raw| raw.ok())
+            .filter_map(|raw| IndexEntry::from_bytes(&raw).ok())
+    }
+
+    /// Every indexed entry at or below `root`.
+    pub fn entries_under(&self, root: &Path) -> impl Iterator<Item = IndexEntry> + use<> {
+        let root = root.to_path_buf();
+        self.records.scan_prefix(path_key(&root))
+            .values()
+            .filter_map(|raw| raw.ok())
+            .filter_map(|raw| IndexEntry::from_bytes(&raw).ok())
+            // the byte prefix also matches siblings like `/home/me2` for `/home/me`
+            .filter(move |e| e.meta.abs_path.starts_with(&root))
+    }
+
+    /// Paths grouped by content hash, for every hash shared by two or more
+    /// records. Walks the hash tree in key order, so only one group is held
+    /// in memory at a time besides the result.
+    pub fn shared_hashes(&self) -> Vec<Vec<PathBuf>> {
+        let mut groups = Vec::new();
+        let mut current_hash: Vec<u8> = Vec::new();
+        let mut current: Vec<PathBuf> = Vec::new();
+
+        for (key, raw) in self.by_hash.iter().filter_map(|kv| kv.ok()) {
+            let hash = key.split(|b| *b == 0).next().unwrap_or_default();
+            if hash != current_hash.as_slice() {
+                if current.len() > 1 {
+                    groups.push(std::mem::take(&mut current));
+                }
+                current.clear();
+                current_hash = hash.to_vec();
+            }
+            if let Ok(path) = serde_cbor::from_slice(&raw) {
+                current.push(path);
+            }
+        }
+        if current.len() > 1 {
+            groups.push(current);
+        }
+        groups
+    }
+}
+
+/// Drops the hash-index entry belonging to a serialized record, if it had one.
+fn unlink_hash(by_hash: &sled::transaction::TransactionalTree, old: &[u8]) -> Result<(), ConflictableTransactionError<()>> {
+    if let Ok(old) = IndexEntry::from_bytes(old) && let Some(h) = old.meta.content_hash() {
+        by_hash.remove(hash_key(h, &old.meta.abs_path))?;
+    }
+    Ok(())
+}
+
+/// True when size, mtime and inode all match, i.e. the file has not been
+/// touched since `prev` was recorded and its hash can be reused.
+pub(crate) fn same_file_version(prev: &FileMeta, current: &FileMeta) -> bool {
+    prev.size == current.size
+        && prev.modified == current.modified
+        && prev.inode == current.inode
+        && prev.is_file == current.is_file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn make_meta(abs_path: &str, size: u64, modified: i64, inode: u64) -> FileMeta {
+        FileMeta {
+            rel_path: PathBuf::from(abs_path),
+            abs_path: PathBuf::from(abs_path),
+            file_name: abs_path.rsplit('/').next().unwrap().to_string(),
+            hash: "h".to_string(),
+            is_file: true,
+            size,
+            modified,
+            created: 0,
+            readonly: false,
+            hash_strategy: HashStrategy::Sampled,
+            full_hash: None,
+            inode,
+        }
+    }
+
+    fn with_hash(abs_path: &str, full_hash: &str) -> IndexEntry {
+        let mut meta = make_meta(abs_path, 10, 1, 1);
+        meta.full_hash = Some(full_hash.to_string());
+        IndexEntry::new(meta, None)
+    }
+
+    fn verdict(v: &str) -> VerdictRecord {
+        VerdictRecord::from_output(&format!("Decision: {}", v), "test.gguf")
+    }
+
+    fn open_temp_index(test_name: &str) -> FileIndex {
+        let path = std::env::temp_dir().join(format!("cruft_test_index_{}", test_name));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = sled::open(&path).expect("failed to open temp sled db");
+        FileIndex::open(&db).unwrap()
+    }
+
+    /// Paths the hash index lists under `hash`.
+    fn paths_with_hash(index: &FileIndex, hash: &str) -> Vec<PathBuf> {
+        index.by_hash.scan_prefix(hash_prefix(hash))
+            .values()
+            .filter_map(|raw| raw.ok())
+            .filter_map(|raw| serde_cbor::from_slice(&raw).ok())
+            .collect()
+    }
+
+    fn sorted(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        paths.sort();
+        paths
+    }
+
+    // ── get / put / remove ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_put_then_get_roundtrip() {
+        let index = open_temp_index("roundtrip");
+        let entry = IndexEntry::new(make_meta("/a/b.txt", 10, 100, 7), Some(verdict("keep")));
+        index.put(&entry).unwrap();
+        assert_eq!(index.get(Path::new("/a/b.txt")).unwrap(), Some(entry));
+    }
+
+    #[test]
+    fn test_get_missing_is_none() {
+        let index = open_temp_index("missing");
+        assert_eq!(index.get(Path::new("/nope")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_remove_deletes_entry() {
+        let index = open_temp_index("remove");
+        index.put(&IndexEntry::new(make_meta("/a", 1, 1, 1), None)).unwrap();
+        index.remove(Path::new("/a")).unwrap();
+        assert_eq!(index.get(Path::new("/a")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_distinct_paths_are_distinct_records() {
+        let index = open_temp_index("distinct");
+        for p in ["/a", "/b", "/c"] {
+            index.put(&IndexEntry::new(make_meta(p, 1, 1, 1), None)).unwrap();
+        }
+        assert_eq!(index.entries().count(), 3);
+    }
+
+    // ── upsert_meta ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_upsert_keeps_verdict_for_same_version() {
+        let index = open_temp_index("upsert_same");
+        let original = IndexEntry::new(make_meta("/a", 10, 100, 1), Some(verdict("delete")));
+        index.put(&original.clone()).unwrap();
+        index.upsert_meta(make_meta("/a", 10, 100, 1), 3).unwrap();
+
+        let entry = index.get(Path::new("/a")).unwrap().unwrap();
+        assert_eq!(entry.verdict, original.verdict);
+        assert_eq!(entry.scan_id, 3);
+    }
+
+    #[test]
+    fn test_upsert_drops_verdict_when_file_changed() {
+        let index = open_temp_index("upsert_changed");
+        index.put(&IndexEntry::new(make_meta("/a", 10, 100, 1), Some(verdict("delete")))).unwrap();
+        index.upsert_meta(make_meta("/a", 12, 200, 1), 1).unwrap();
+        assert_eq!(index.get(Path::new("/a")).unwrap().unwrap().verdict, None);
+    }
+
+    // ── hash index ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_paths_with_hash() {
+        let index = open_temp_index("with_hash");
+        index.put(&with_hash("/a", "h1")).unwrap();
+        index.put(&with_hash("/b", "h1")).unwrap();
+        index.put(&with_hash("/c", "h2")).unwrap();
+        // no full hash, so not in the hash index
+        index.put(&IndexEntry::new(make_meta("/d", 10, 1, 1), None)).unwrap();
+
+        assert_eq!(sorted(paths_with_hash(&index, "h1")), vec![PathBuf::from("/a"), PathBuf::from("/b")]);
+        assert_eq!(paths_with_hash(&index, "h2"), vec![PathBuf::from("/c")]);
+        assert!(paths_with_hash(&index, "h").is_empty());
+    }
+
+    #[test]
+    fn test_hash_index_follows_content_change() {
+        let index = open_temp_index("hash_moves");
+        index.put(&with_hash("/a", "old")).unwrap();
+        index.put(&with_hash("/a", "new")).unwrap();
+
+        assert!(paths_with_hash(&index, "old").is_empty());
+        assert_eq!(paths_with_hash(&index, "new"), vec![PathBuf::from("/a")]);
+    }
+
+    #[test]
+    fn test_remove_drops_hash_entry() {
+        let index = open_temp_index("hash_remove");
+        index.put(&with_hash("/a", "h")).unwrap();
+        index.remove(Path::new("/a")).unwrap();
+        assert!(paths_with_hash(&index, "h").is_empty());
+    }
+
+    #[test]
+    fn test_shared_hashes_only_returns_groups() {
+        let index = open_temp_index("shared");
+        index.put(&with_hash("/a", "h1")).unwrap();
+        index.put(&with_hash("/b", "h1")).unwrap();
+        index.put(&with_hash("/c", "h2")).unwrap();
+        index.put(&with_hash("/d", "h3")).unwrap();
+        index.put(&with_hash("/e", "h3")).unwrap();
+        index.put(&with_hash("/f", "h3")).unwrap();
+
+        let mut groups: Vec<Vec<PathBuf>> = index.shared_hashes().into_iter().map(sorted).collect();
+        groups.sort();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0], vec![PathBuf::from("/a"), PathBuf::from("/b")]);
+        assert_eq!(groups[1].len(), 3);
+    }
+
+    // ── scans ─────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_scan_ids_increase() {
+        let index = open_temp_index("scan_ids");
+        assert_eq!(index.latest_scan().unwrap(), None);
+
+        let first = index.begin_scan().unwrap();
+        let second = index.begin_scan().unwrap();
+
+        assert!(second > first);
+        assert_eq!(index.latest_scan().unwrap().map(|(id, _)| id), Some(second));
+    }
+
+    // ── entries_under ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_entries_under_excludes_prefix_siblings() {
+        let index = open_temp_index("under");
+        for p in ["/home/me/a.txt", "/home/me/sub/b.txt", "/home/me2/c.txt", "/other/d.txt"] {
+            index.put(&IndexEntry::new(make_meta(p, 1, 1, 1), None)).unwrap();
+        }
+
+        let found: Vec<PathBuf> = index.entries_under(Path::new("/home/me"))
+            .map(|e| e.meta.abs_path)
+            .collect();
+
+        assert_eq!(sorted(found), vec![PathBuf::from("/home/me/a.txt"), PathBuf::from("/home/me/sub/b.txt")]);
+    }
+
+    // ── same_file_version ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_same_file_version() {
+        let a = make_meta("/a", 10, 100, 5);
+        assert!(same_file_version(&a, &make_meta("/a", 10, 100, 5)));
+        assert!(!same_file_version(&a, &make_meta("/a", 11, 100, 5)));
+        assert!(!same_file_version(&a, &make_meta("/a", 10, 101, 5)));
+        assert!(!same_file_version(&a, &make_meta("/a", 10, 100, 6)));
+    }
+}
@@ -0,0 +1,254 @@
+use std::fs;
+use std::io;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Highest core number `LLM.cores` can name: the size of the kernel's CPU set.
+pub(crate) const MAX_CORES: usize = 1024;
+
+/// However busy the machine is, inference still gets this share of its
+/// budget, so a review on a loaded machine finishes eventually.
+const MIN_SHARE: f64 = 0.1;
+
+/// Longest single pause; whatever is left over is made up after the next step.
+const MAX_PAUSE: Duration = Duration::from_secs(30);
+
+/// Keeps inference within `LLM.cpu-budget`. The engine starts a `Pace` for
+/// each answer and calls `Pace::step` after every batch it decodes; each step
+/// measures how much CPU time inference has used since the answer began and
+/// pauses until its average use is back within the budget. When other work
+/// keeps the machine busy, going by the 1-minute load average, the budget
+/// shrinks to what the idle cores can spare.
+///
+/// Only the thread that decodes is measured, not the whole process, so the
+/// crawler hashing files meanwhile doesn't count against inference. llama.cpp
+/// splits each batch evenly over `threads` threads with the decoding thread
+/// as one of them, so inference as a whole uses that thread's CPU time
+/// `threads` times over.
+pub(crate) struct Governor {
+    /// In cores: 0.25 is a quarter of one core's time.
+    budget:  f64,
+    /// Cores online, for reading the load average against.
+    cores:   f64,
+    /// Inference threads, each using about as much CPU time as the decoding thread.
+    threads: usize,
+}
+
+impl Governor {
+    /// A governor for `budget_percent` of one core's time, for inference on `threads` threads.
+    pub fn new(budget_percent: u32, threads: usize) -> Self {
+        Self { budget: budget_percent as f64 / 100.0, cores: cores_online(), threads: threads.max(1) }
+    }
+
+    /// Starts pacing one answer.
+    pub fn start(&self) -> Pace<'_> {
+        Pace { governor: self, started: Instant::now(), cpu_at_start: thread_cpu_time() }
+    }
+}
+
+/// Pacing for one answer: CPU time is counted from when it began. Stepped on
+/// the thread that started it, which is the one measured.
+pub(crate) struct Pace<'a> {
+    governor:     &'a Governor,
+    started:      Instant,
+    cpu_at_start: Option<Duration>,
+}
+
+impl Pace<'_> {
+    /// Pauses for as long as the CPU time used so far takes to fit the budget.
+    pub fn step(&mut self) {
+        let wall = self.started.elapsed();
+        let threads = self.governor.threads as u32;
+        let cpu = match (thread_cpu_time(), self.cpu_at_start) {
+            (Some(now), Some(start)) => now.saturating_sub(start).saturating_mul(threads),
+            // Assume every thread was busy the whole time
+            _ => wall.saturating_mul(threads),
+        };
+        let budget = budget_under_load(self.governor.budget, load_average(), self.governor.cores);
+        let pause = pause_for(cpu, wall, budget);
+        if !pause.is_zero() {
+            sleep(pause);
+        }
+    }
+}
+
+/// How long to pause after using `cpu` time over `wall` time so the average,
+/// pause included, comes to `budget` cores; nothing if it is already within.
+fn pause_for(cpu: Duration, wall: Duration, budget: f64) -> Duration {
+    let needed = cpu.as_secs_f64() / budget - wall.as_secs_f64();
+    if needed > 0.0 {
+        Duration::from_secs_f64(needed).min(MAX_PAUSE)
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// `budget`, cut down to the cores that other work leaves idle when the load
+/// average is `load` on `cores` cores. Inference's own share of the load is
+/// taken to be its budget.
+fn budget_under_load(budget: f64, load: Option<f64>, cores: f64) -> f64 {
+    let Some(load) = load else {
+        return budget;
+    };
+    let others = (load - budget).max(0.0);
+    let idle = (cores - others).max(0.0);
+    budget.min(idle).max(budget * MIN_SHARE)
+}
+
+/// The 1-minute load average, where the system reports one.
+fn load_average() -> Option<f64> {
+    parse_loadavg(&fs::read_to_string("/proc/loadavg").ok()?)
+}
+
+/// The first field of `/proc/loadavg`, e.g. `0.52 0.58 0.59 1/467 12345`.
+fn parse_loadavg(text: &str) -> Option<f64> {
+    text.split_whitespace().next()?.parse().ok().filter(|load: &f64| load.is_finite() && *load >= 0.0)
+}
+
+/// CPU time used by the calling thread so far.
+#[cfg(unix)]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid timespec for the call to fill in
+    let ok = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) } == 0;
+    ok.then(|| Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+fn cores_online() -> f64 {
+    #[cfg(unix)]
+    {
+        // SAFETY: sysconf only reads a system setting
+        let n = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+        if n > 0 {
+            return n as f64;
+        }
+    }
+    std::thread::available_parallelism().map_or(1, |n| n.get()) as f64
+}
+
+/// Restricts the calling thread, and the threads it starts afterwards, to
+/// `cores`; no restriction when it is empty. Does nothing off Linux.
+pub(crate) fn pin_to_cores(cores: &[usize]) -> io::Result<()> {
+    if cores.is_empty() {
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: the set is zeroed before use and every core is below CPU_SETSIZE, checked by config validation
+        unsafe {
+            let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+            for &core in cores.iter().filter(|&&c| c < MAX_CORES) {
+                libc::CPU_SET(core, &mut cpu_set);
+            }
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: f64) -> Duration {
+        Duration::from_secs_f64(s)
+    }
+
+    // ── pause_for ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_pause_brings_use_down_to_the_budget() {
+        // 1 s of CPU at a quarter core needs 4 s in all
+        assert_eq!(pause_for(secs(1.0), secs(1.0), 0.25), secs(3.0));
+        assert_eq!(pause_for(secs(1.0), secs(3.0), 0.5), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_pause_counts_every_thread() {
+        // Two busy threads for 1 s use 2 s of CPU
+        assert_eq!(pause_for(secs(2.0), secs(1.0), 1.0), secs(1.0));
+        assert_eq!(pause_for(secs(2.0), secs(1.0), 2.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_pause_is_capped() {
+        assert_eq!(pause_for(secs(60.0), secs(1.0), 0.01), MAX_PAUSE);
+    }
+
+    // ── budget_under_load ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_idle_machine_keeps_the_whole_budget() {
+        assert_eq!(budget_under_load(0.5, Some(0.3), 4.0), 0.5);
+        assert_eq!(budget_under_load(0.5, None, 4.0), 0.5, "no load average to go by");
+    }
+
+    #[test]
+    fn test_busy_machine_shrinks_the_budget_to_idle_cores() {
+        // Other work keeps 3.75 of 4 cores busy
+        assert_eq!(budget_under_load(0.5, Some(4.25), 4.0), 0.25);
+        assert_eq!(budget_under_load(0.5, Some(20.0), 4.0), 0.5 * MIN_SHARE, "never below the minimum share");
+    }
+
+    // ── parse_loadavg ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_loadavg_takes_the_one_minute_average() {
+        assert_eq!(parse_loadavg("0.52 0.58 0.59 1/467 12345\n"), Some(0.52));
+        assert_eq!(parse_loadavg(""), None);
+        assert_eq!(parse_loadavg("busy 0.58"), None);
+        assert_eq!(parse_loadavg("-1.0 0 0"), None);
+    }
+
+    // ── measuring ─────────────────────────────────────────────────────────────
+
+    fn spin(how_long: Duration) {
+        let mut x = 0u64;
+        let started = Instant::now();
+        while started.elapsed() < how_long {
+            x = std::hint::black_box(x.wrapping_add(1));
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_thread_cpu_time_grows_with_work() {
+        let before = thread_cpu_time().unwrap();
+        spin(Duration::from_millis(20));
+        assert!(thread_cpu_time().unwrap() > before);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_other_threads_work_is_not_counted() {
+        let before = thread_cpu_time().unwrap();
+        std::thread::spawn(|| spin(Duration::from_millis(200))).join().unwrap();
+        let used = thread_cpu_time().unwrap().saturating_sub(before);
+        assert!(used < Duration::from_millis(100), "{:?}", used);
+    }
+
+    #[test]
+    fn test_step_within_budget_does_not_pause() {
+        let governor = Governor::new(100_000, 1);
+        let mut pace = governor.start();
+        let started = Instant::now();
+        pace.step();
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_no_cores_means_no_pinning() {
+        assert!(pin_to_cores(&[]).is_ok());
+    }
+}
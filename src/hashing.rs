@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+
+// Read buffer for streaming full-content hashes
+const READ_CHUNK: usize = 64 * 1024;
+
+/// How the crawler computes `FileMeta::hash`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum HashStrategy {
+    /// SHA-256 of the first `quick_bytes` bytes.
+    Quick,
+    /// SHA-256 over `sample_bytes` taken from the head, middle and tail.
+    #[default]
+    Sampled,
+    /// SHA-256 of the whole file.
+    Full,
+}
+
+impl HashStrategy {
+    /// What records written before strategies existed used, with 1024 bytes.
+    pub fn legacy() -> Self {
+        HashStrategy::Quick
+    }
+}
+
+/// `[hashing]` in `config.toml`: read by the crawler and `scan`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct HashConfig {
+    pub strategy:     HashStrategy,
+    pub quick_bytes:  u64,
+    pub sample_bytes: u64,
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        Self {
+            strategy:     HashStrategy::default(),
+            quick_bytes:  1024,
+            sample_bytes: 16 * 1024,
+        }
+    }
+}
+
+/// Result of hashing a file with a strategy. `complete` is true when every byte
+/// of the file went into the digest, i.e. `hash` is also the full-content hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FileHash {
+    pub hash:     String,
+    pub complete: bool,
+}
+
+/// Hashes `path` according to `config.strategy`.
+pub(crate) fn hash_file(path: &Path, size: u64, config: &HashConfig) -> io::Result<FileHash> {
+    match config.strategy {
+        HashStrategy::Full => Ok(FileHash { hash: full_hash(path)?, complete: true }),
+        HashStrategy::Quick => {
+            let hash = quick_hash(path, config.quick_bytes)?;
+            Ok(FileHash { hash, complete: size <= config.quick_bytes })
+        }
+        HashStrategy::Sampled => {
+            // Small files are read whole; that digest doubles as the full hash
+            if size <= config.sample_bytes * 3 {
+                return Ok(FileHash { hash: full_hash(path)?, complete: true });
+            }
+            Ok(FileHash { hash: sampled_hash(path, size, config.sample_bytes)?, complete: false })
+        }
+    }
+}
+
+/// SHA-256 of the entire file, streamed in fixed-size chunks.
+pub(crate) fn full_hash(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; READ_CHUNK];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// SHA-256 of at most the first `bytes` bytes.
+pub(crate) fn quick_hash(path: &Path, bytes: u64) -> io::Result<String> {
+    let mut buffer = Vec::new();
+    File::open(path)?.take(bytes).read_to_end(&mut buffer)?;
+    Ok(hex::encode(Sha256::digest(&buffer)))
+}
+
+/// SHA-256 over three `block`-sized samples: head, middle and tail.
+pub(crate) fn sampled_hash(path: &Path, size: u64, block: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = Vec::with_capacity(block as usize);
+
+    let middle = (size / 2).saturating_sub(block / 2);
+    let tail   = size.saturating_sub(block);
+
+    for offset in [0, middle, tail] {
+        buffer.clear();
+        file.seek(SeekFrom::Start(offset))?;
+        (&mut file).take(block).read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// What the crawler should do after registering a file's (size, hash) pair.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Candidate {
+    /// No other file seen so far shares size and hash.
+    Unique,
+    /// Another file shares size and hash; this one needs a full hash.
+    /// `first` is the earlier file the first time its pair collides, so its
+    /// full hash can be computed too; later collisions carry `None`.
+    Collides { first: Option<PathBuf> },
+}
+
+/// Remembers (size, candidate hash) pairs seen during a crawl so full hashes
+/// are only computed for files that could actually be duplicates.
+#[derive(Default)]
+pub(crate) struct CandidateIndex {
+    // `Some(path)` until the pair first collides, then `None`
+    seen: HashMap<(u64, String), Option<PathBuf>>,
+}
+
+impl CandidateIndex {
+    pub fn observe(&mut self, size: u64, hash: &str, path: &Path) -> Candidate {
+        match self.seen.get_mut(&(size, hash.to_string())) {
+            None => {
+                self.seen.insert((size, hash.to_string()), Some(path.to_path_buf()));
+                Candidate::Unique
+            }
+            Some(first) => Candidate::Collides { first: first.take() },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn write_temp(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("cruft_hashing_{}", name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn config(strategy: HashStrategy) -> HashConfig {
+        HashConfig { strategy, quick_bytes: 8, sample_bytes: 4 }
+    }
+
+    // ── full_hash ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_full_hash_matches_known_digest() {
+        let path = write_temp("known", b"abc");
+        assert_eq!(
+            full_hash(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_full_hash_sees_differences_past_the_header() {
+        let mut a = vec![0u8; 200_000];
+        let mut b = a.clone();
+        a[150_000] = 1;
+        b[150_000] = 2;
+        let pa = write_temp("deep_a", &a);
+        let pb = write_temp("deep_b", &b);
+
+        assert_eq!(quick_hash(&pa, 1024).unwrap(), quick_hash(&pb, 1024).unwrap());
+        assert_ne!(full_hash(&pa).unwrap(), full_hash(&pb).unwrap());
+    }
+
+    // ── hash_file ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_quick_strategy_only_reads_prefix() {
+        let a = write_temp("quick_a", b"12345678-tail-a");
+        let b = write_temp("quick_b", b"12345678-tail-b");
+        let cfg = config(HashStrategy::Quick);
+
+        let ha = hash_file(&a, 15, &cfg).unwrap();
+        let hb = hash_file(&b, 15, &cfg).unwrap();
+        assert_eq!(ha.hash, hb.hash);
+        assert!(!ha.complete);
+    }
+
+    #[test]
+    fn test_quick_strategy_small_file_is_complete() {
+        let a = write_temp("quick_small", b"1234");
+        let h = hash_file(&a, 4, &config(HashStrategy::Quick)).unwrap();
+        assert!(h.complete);
+        assert_eq!(h.hash, full_hash(&a).unwrap());
+    }
+
+    #[test]
+    fn test_sampled_strategy_detects_tail_change() {
+        let a = write_temp("sampled_a", b"HEAD----middle----TAILa");
+        let b = write_temp("sampled_b", b"HEAD----middle----TAILb");
+        let cfg = config(HashStrategy::Sampled);
+
+        let ha = hash_file(&a, 23, &cfg).unwrap();
+        let hb = hash_file(&b, 23, &cfg).unwrap();
+        assert_ne!(ha.hash, hb.hash);
+        assert!(!ha.complete);
+    }
+
+    #[test]
+    fn test_sampled_strategy_small_file_uses_full_hash() {
+        let a = write_temp("sampled_small", b"0123456789");
+        let h = hash_file(&a, 10, &config(HashStrategy::Sampled)).unwrap();
+        assert!(h.complete);
+        assert_eq!(h.hash, full_hash(&a).unwrap());
+    }
+
+    #[test]
+    fn test_full_strategy_is_complete() {
+        let a = write_temp("full_strategy", b"0123456789abcdef0123456789");
+        let h = hash_file(&a, 26, &config(HashStrategy::Full)).unwrap();
+        assert!(h.complete);
+        assert_eq!(h.hash, full_hash(&a).unwrap());
+    }
+
+    #[test]
+    fn test_hash_file_missing_file_is_error() {
+        let cfg = HashConfig::default();
+        assert!(hash_file(Path::new("/nonexistent_dir_xyz/f"), 10, &cfg).is_err());
+    }
+
+    // ── CandidateIndex ────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_candidate_index_reports_first_collision_once() {
+        let mut index = CandidateIndex::default();
+        let a = PathBuf::from("/a");
+
+        assert_eq!(index.observe(10, "h", &a), Candidate::Unique);
+        assert_eq!(
+            index.observe(10, "h", Path::new("/b")),
+            Candidate::Collides { first: Some(a) }
+        );
+        assert_eq!(
+            index.observe(10, "h", Path::new("/c")),
+            Candidate::Collides { first: None }
+        );
+    }
+
+    #[test]
+    fn test_candidate_index_size_is_part_of_key() {
+        let mut index = CandidateIndex::default();
+        assert_eq!(index.observe(10, "h", Path::new("/a")), Candidate::Unique);
+        assert_eq!(index.observe(11, "h", Path::new("/b")), Candidate::Unique);
+    }
+
+    // ── HashStrategy serde ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_strategy_default_matches_config() {
+        assert_eq!(HashStrategy::default(), HashConfig::default().strategy);
+        assert_eq!(HashStrategy::legacy(), HashStrategy::Quick);
+    }
+}
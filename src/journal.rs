@@ -0,0 +1,335 @@
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::file_index::IndexEntry;
+use crate::quarantine::unix_now;
+
+/// Deletion journal: key is the big-endian timestamp followed by the big-endian
+/// entry id, so entries sort by time; value is the cbor `JournalEntry`.
+pub(crate) const JOURNAL_TREE: &str = "deletion_journal";
+
+/// What was done to the file, and at whose request.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum UserAction {
+    /// The user confirmed the deletion in the UI.
+    Delete,
+    /// A quarantined file outlived the retention period.
+    Purge,
+    /// The user undid a deletion.
+    Restore,
+    /// The user applied a deletion plan made by a dry run.
+    Apply,
+}
+
+/// What became of the file.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Outcome {
+    Quarantined,
+    Trashed,
+    /// Removed for good.
+    Purged,
+    /// Put back where it was.
+    Restored,
+    /// Not deleted because the file no longer matched what was reviewed.
+    Refused,
+    /// Not deleted because something went wrong.
+    Failed,
+}
+
+/// One deletion attempt, or the undoing of one.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct JournalEntry {
+    /// Assigned when the entry is recorded.
+    pub id:          u64,
+    /// When the attempt was made (unix seconds).
+    pub at:          i64,
+    pub path:        PathBuf,
+    pub size:        Option<u64>,
+    /// Full-content hash when one was recorded, otherwise the scan's hash.
+    pub hash:        Option<String>,
+    pub verdict:     Option<String>,
+    pub reasoning:   Option<String>,
+    pub model:       Option<String>,
+    pub action:      UserAction,
+    /// Login of whoever ran the tool.
+    pub user:        Option<String>,
+    pub outcome:     Outcome,
+    /// Where the file went, for quarantined and trashed files; where it came
+    /// back from, for restored ones.
+    pub destination: Option<PathBuf>,
+    pub error:       Option<String>,
+}
+
+impl JournalEntry {
+    /// An entry stamped with the current time and user, filled in from what
+    /// the index knew about the file.
+    pub fn new(path: &Path, indexed: Option<&IndexEntry>, action: UserAction, outcome: Outcome) -> Self {
+        let meta = indexed.map(|e| &e.meta);
+        let verdict = indexed.and_then(|e| e.verdict.as_ref());
+        Self {
+            id:          0,
+            at:          unix_now(),
+            path:        path.to_path_buf(),
+            size:        meta.map(|m| m.size),
+            hash:        meta.map(|m| m.content_hash().unwrap_or(&m.hash).to_string()),
+            verdict:     verdict.map(|v| v.verdict.clone()),
+            reasoning:   verdict.map(|v| v.reasoning.clone()),
+            model:       verdict.map(|v| v.model.clone()),
+            action,
+            user:        current_user(),
+            outcome,
+            destination: None,
+            error:       None,
+        }
+    }
+
+    fn key(&self) -> [u8; 16] {
+        let mut key = [0u8; 16];
+        key[..8].copy_from_slice(&(self.at as u64).to_be_bytes());
+        key[8..].copy_from_slice(&self.id.to_be_bytes());
+        key
+    }
+}
+
+/// Append-only record of every deletion attempt, kept in sled and mirrored
+/// line by line to a JSON Lines file. Entries are never changed or removed.
+/// Cheap to clone; every clone shares the tree.
+#[derive(Clone)]
+pub(crate) struct Journal {
+    db:      sled::Db,
+    entries: sled::Tree,
+    file:    PathBuf,
+}
+
+impl Journal {
+    pub fn open(db: &sled::Db, file: impl Into<PathBuf>) -> sled::Result<Self> {
+        Ok(Self {
+            db:      db.clone(),
+            entries: db.open_tree(JOURNAL_TREE)?,
+            file:    file.into(),
+        })
+    }
+
+    /// Stores `entry` under a fresh id and appends it to the JSON Lines file.
+    /// The sled tree is the record of truth, so a failed file write is only reported.
+    pub fn record(&self, mut entry: JournalEntry) -> Result<JournalEntry, Box<dyn Error>> {
+        entry.id = self.db.generate_id()?;
+        let inserted = self.entries.compare_and_swap(
+            entry.key(),
+            None as Option<&[u8]>,
+            Some(serde_cbor::to_vec(&entry)?),
+        )?;
+        if inserted.is_err() {
+            return Err(format!("journal entry {} already exists", entry.id).into());
+        }
+        self.entries.flush()?;
+
+        if let Err(e) = append_line(&self.file, &entry) {
+            eprintln!("  ⚠ Could not append to {}: {}", self.file.display(), e);
+        }
+        Ok(entry)
+    }
+
+    /// Every entry, oldest first.
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        decode(self.entries.iter())
+    }
+
+    /// Entries made from `from` up to but not including `to` (unix seconds), oldest first.
+    pub fn between(&self, from: i64, to: i64) -> Vec<JournalEntry> {
+        if to <= from {
+            return Vec::new();
+        }
+        let start = (from.max(0) as u64).to_be_bytes();
+        let end = (to.max(0) as u64).to_be_bytes();
+        decode(self.entries.range(start..end))
+    }
+
+    /// Writes `entries` as JSON Lines to `out`, e.g. to rebuild the file or
+    /// hand over one day's deletions.
+    pub fn export_jsonl(entries: &[JournalEntry], out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+        for entry in entries {
+            serde_json::to_writer(&mut *out, entry)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+fn decode(iter: impl Iterator<Item = sled::Result<(sled::IVec, sled::IVec)>>) -> Vec<JournalEntry> {
+    iter.filter_map(|kv| kv.ok())
+        .filter_map(|(_, raw)| serde_cbor::from_slice(&raw).ok())
+        .collect()
+}
+
+fn append_line(file: &Path, entry: &JournalEntry) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    // a single write, so concurrent appenders never interleave within a line
+    OpenOptions::new().create(true).append(true).open(file)?.write_all(&line)?;
+    Ok(())
+}
+
+fn current_user() -> Option<String> {
+    ["USER", "LOGNAME", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::ai_model::VerdictRecord;
+    use crate::actor::crawler::FileMeta;
+    use crate::hashing::HashStrategy;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    /// Fresh journal plus the path of its JSON Lines file.
+    fn setup(name: &str) -> (Journal, PathBuf) {
+        let base = std::env::temp_dir().join(format!("cruft_journal_{}", name));
+        let _ = fs::remove_dir_all(&base);
+        let db = sled::open(base.join("db")).unwrap();
+        let file = base.join("deletions.jsonl");
+        (Journal::open(&db, &file).unwrap(), file)
+    }
+
+    fn indexed(path: &str, full_hash: Option<&str>) -> IndexEntry {
+        let path = PathBuf::from(path);
+        let meta = FileMeta {
+            rel_path: path.clone(),
+            abs_path: path.clone(),
+            file_name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            hash: "sampled".to_string(),
+            is_file: true,
+            size: 12,
+            modified: 1,
+            created: 1,
+            readonly: false,
+            hash_strategy: HashStrategy::Sampled,
+            full_hash: full_hash.map(str::to_string),
+            inode: 0,
+        };
+        let verdict = VerdictRecord::from_output("Reasoning: stale build output\nDecision: delete", "test.gguf");
+        IndexEntry::new(meta, Some(verdict))
+    }
+
+    fn entry_at(path: &str, at: i64) -> JournalEntry {
+        JournalEntry { at, ..JournalEntry::new(Path::new(path), None, UserAction::Delete, Outcome::Quarantined) }
+    }
+
+    // ── JournalEntry::new ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_entry_takes_details_from_index() {
+        let entry = JournalEntry::new(
+            Path::new("/home/me/a.o"),
+            Some(&indexed("/home/me/a.o", Some("full"))),
+            UserAction::Delete,
+            Outcome::Trashed,
+        );
+        assert_eq!(entry.size, Some(12));
+        assert_eq!(entry.hash.as_deref(), Some("full"));
+        assert_eq!(entry.verdict.as_deref(), Some("delete"));
+        assert_eq!(entry.reasoning.as_deref(), Some("stale build output"));
+        assert_eq!(entry.model.as_deref(), Some("test.gguf"));
+    }
+
+    #[test]
+    fn test_entry_falls_back_to_scan_hash() {
+        let entry = JournalEntry::new(Path::new("/a"), Some(&indexed("/a", None)), UserAction::Delete, Outcome::Refused);
+        assert_eq!(entry.hash.as_deref(), Some("sampled"));
+    }
+
+    #[test]
+    fn test_entry_without_index_has_no_details() {
+        let entry = JournalEntry::new(Path::new("/a"), None, UserAction::Delete, Outcome::Failed);
+        assert_eq!(entry.size, None);
+        assert_eq!(entry.verdict, None);
+    }
+
+    // ── Journal ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_record_assigns_ids_and_keeps_every_attempt() {
+        let (journal, _) = setup("record");
+        let first = journal.record(entry_at("/a", 100)).unwrap();
+        let second = journal.record(entry_at("/a", 100)).unwrap();
+
+        assert_ne!(first.id, second.id);
+        assert_eq!(journal.entries(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_entries_sorted_by_time() {
+        let (journal, _) = setup("sorted");
+        journal.record(entry_at("/late", 300)).unwrap();
+        journal.record(entry_at("/early", 100)).unwrap();
+
+        let paths: Vec<_> = journal.entries().into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, vec![PathBuf::from("/early"), PathBuf::from("/late")]);
+    }
+
+    #[test]
+    fn test_between_selects_time_window() {
+        let (journal, _) = setup("between");
+        for (path, at) in [("/mon", 100), ("/tue", 200), ("/tue2", 299), ("/wed", 300)] {
+            journal.record(entry_at(path, at)).unwrap();
+        }
+
+        let paths: Vec<_> = journal.between(200, 300).into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, vec![PathBuf::from("/tue"), PathBuf::from("/tue2")]);
+        assert!(journal.between(300, 200).is_empty());
+    }
+
+    #[test]
+    fn test_record_appends_json_line() {
+        let (journal, file) = setup("jsonl");
+        let failed = JournalEntry {
+            error: Some("disk full".into()),
+            ..JournalEntry::new(Path::new("/a b.txt"), None, UserAction::Delete, Outcome::Failed)
+        };
+        journal.record(entry_at("/x", 1)).unwrap();
+        let recorded = journal.record(failed).unwrap();
+
+        let text = fs::read_to_string(&file).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let parsed: JournalEntry = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(parsed, recorded);
+        assert!(lines[1].contains("\"outcome\":\"failed\""));
+        assert!(lines[1].contains("\"action\":\"delete\""));
+    }
+
+    #[test]
+    fn test_journal_survives_reopen() {
+        let base = std::env::temp_dir().join("cruft_journal_reopen");
+        let _ = fs::remove_dir_all(&base);
+        let db = sled::open(base.join("db")).unwrap();
+        Journal::open(&db, base.join("j.jsonl")).unwrap().record(entry_at("/a", 1)).unwrap();
+
+        assert_eq!(Journal::open(&db, base.join("j.jsonl")).unwrap().entries().len(), 1);
+    }
+
+    #[test]
+    fn test_export_jsonl_one_line_per_entry() {
+        let (journal, _) = setup("export");
+        journal.record(entry_at("/a", 1)).unwrap();
+        journal.record(entry_at("/b", 2)).unwrap();
+
+        let mut out = Vec::new();
+        Journal::export_jsonl(&journal.entries(), &mut out).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.ends_with('\n'));
+        assert!(text.lines().all(|l| serde_json::from_str::<JournalEntry>(l).is_ok()));
+    }
+}
@@ -7,21 +7,69 @@ use llama_cpp_2::model::LlamaModel;
 use llama_cpp_2::model::params::LlamaModelParams;
 use llama_cpp_2::model::{AddBos, Special};
 use llama_cpp_2::sampling::LlamaSampler;
+use llama_cpp_2::token::LlamaToken;
 use std::io::Write;
 use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::{any, fs};
 use llama_cpp_2::{send_logs_to_tracing,LogOptions};
+use crate::config::LlmConfig;
+use crate::actor::crawler::FileMeta;
+use crate::verdict_engine::{self, VerdictEngine};
+use crate::governor::{self, Governor, Pace};
+
+/// GBNF rules for one `ai_model::Answer` on one line, as `answer`. The decision
+/// comes first, so it is settled before a long reason can run into
+/// `LLM.max-tokens`; the reason is kept short for the same purpose.
+macro_rules! answer_rules {
+    () => { r#"
+answer     ::= "{" ws "\"decision\":" ws decision "," ws "\"confidence\":" ws confidence "," ws "\"reason\":" ws reason ws "}"
+decision   ::= "\"keep\"" | "\"delete\"" | "\"unsure\""
+confidence ::= "100" | [1-9] [0-9] | [0-9]
+reason     ::= "\"" char{0,120} "\""
+char       ::= [^"\\\x00-\x1F]
+ws         ::= " "?
+"# };
+}
+
+/// GBNF grammar the sampler is held to when asking about one file, so every
+/// answer is an `ai_model::Answer` on one line.
+pub(crate) const VERDICT_GRAMMAR: &str = concat!("\nroot       ::= answer", answer_rules!());
+
+/// Grammar for a prompt about `count` files: exactly that many answers, each
+/// on its own line after its file's number, so none can be skipped or swapped.
+pub(crate) fn batch_grammar(count: usize) -> String {
+    let lines: Vec<String> = (1..=count).map(|n| format!(r#""{}: " answer "\n""#, n)).collect();
+    format!("\nroot       ::= {}{}", lines.join(" "), answer_rules!())
+}
 
-use std::thread::sleep;
-use std::time::Duration;
+/// A context whose KV cache holds the decoded prompt prefix at positions
+/// `0..tokens.len()`. Each file's tail and answer go after it and are dropped
+/// again before the next file.
+struct PrefixCache {
+    ctx:    LlamaContext<'static>,
+    tokens: Vec<LlamaToken>,
+}
 
 pub struct LlmEngine {
     backend: LlamaBackend,
-    model: LlamaModel,
+    /// Leaked so the cached context can borrow it for the rest of the run;
+    /// there is only ever one engine per process.
+    model: &'static LlamaModel,
+    config: LlmConfig,
+    /// File name of the GGUF model, stored with every verdict it gives.
+    name: String,
+    /// Text every prompt starts with; empty when prompts share nothing.
+    prefix: String,
+    /// Built on the first prompt that starts with `prefix`.
+    cache: Mutex<Option<PrefixCache>>,
+    /// Paces decoding to `LLM.cpu-budget`.
+    governor: Governor,
 }
 
 impl LlmEngine {
-    pub fn load_new_model(model_path: &str) -> anyhow::Result<Self> {
+    pub fn load_new_model(model_path: &str, config: LlmConfig) -> anyhow::Result<Self> {
         let backend = LlamaBackend::init()?;
         let model_params = LlamaModelParams::default();
         let log_options = LogOptions::default().with_logs_enabled(true);
@@ -29,41 +77,117 @@ impl LlmEngine {
 
         let model = LlamaModel::load_from_file(&backend, model_path, &model_params)?;
 
-        #[cfg(target_os = "linux")]
-        {
-            unsafe {
-                let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
-                libc::CPU_SET(0, &mut cpu_set); //pin to core 0, seems to overfill to cores 1, and 2
-                libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set);
-            }
+        // llama.cpp's threads are started later, from this one, and stay on the same cores
+        if let Err(e) = governor::pin_to_cores(&config.cores) {
+            eprintln!("  ⚠ Could not keep inference to cores {:?}: {}", config.cores, e);
         }
+        let governor = Governor::new(config.cpu_budget, config.threads as usize);
+
+        let name = Path::new(model_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Ok(Self {
+            backend,
+            model: Box::leak(Box::new(model)),
+            config,
+            name,
+            prefix: String::new(),
+            cache: Mutex::new(None),
+            governor,
+        })
+    }
 
-        Ok(Self { backend, model })
+    /// Decodes `prefix` once and reuses it for every prompt that starts with it,
+    /// keeping it on disk in `LLM.prompt-cache` so later runs can load it.
+    pub fn with_prompt_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self.cache = Mutex::new(None);
+        self
     }
-    
-    fn create_context(&self) -> anyhow::Result<LlamaContext<'_>> {
+
+    fn create_context(&self) -> anyhow::Result<LlamaContext<'static>> {
         let ctx_params = LlamaContextParams::default()
-            .with_n_ctx(Some(NonZeroU32::new(2048).unwrap())) //IT CANNOT HANDLE 128 CONTEXT SIZE
-            .with_n_threads(1)
-            .with_n_threads_batch(1); //attempt to keep it to one thread
+            .with_n_ctx(NonZeroU32::new(self.config.n_ctx)) //IT CANNOT HANDLE 128 CONTEXT SIZE
+            .with_n_threads(self.config.threads)
+            .with_n_threads_batch(self.config.threads); //attempt to keep it to one thread by default
 
         let ctx = self.model.new_context(&self.backend, ctx_params)?;
         Ok(ctx)
     }
 
-    pub fn infer_model(&self, prompt: &str) -> anyhow::Result<String> {
+    /// Where the decoded prefix is saved, one file per model.
+    fn session_file(&self) -> PathBuf {
+        self.config.prompt_cache.join(session_file_name(&self.name))
+    }
+
+    /// A context holding the decoded prefix: loaded from the session file when
+    /// it holds exactly this prefix, otherwise decoded and saved there.
+    fn prefix_cache(&self) -> anyhow::Result<PrefixCache> {
         let mut ctx = self.create_context()?;
-        let tokens = self.model.str_to_token(prompt, AddBos::Always)?;
+        let tokens = self.model.str_to_token(&self.prefix, AddBos::Always)?;
+        let file = self.session_file();
+
+        if file.is_file() {
+            match ctx.load_session_file(&file, self.config.n_ctx as usize) {
+                Ok(saved) if saved == tokens => return Ok(PrefixCache { ctx, tokens }),
+                // Another prompt version or tokenizer; decoded again below
+                Ok(_) => ctx.clear_kv_cache(),
+                Err(e) => {
+                    eprintln!("  ⚠ Ignoring prompt cache {}: {}", file.display(), e);
+                    ctx.clear_kv_cache();
+                }
+            }
+        }
 
-        // --- tunable knobs ---
-        let chunk_size: usize = 1;
-        let chunk_delay = Duration::from_millis(1250);
-        let gen_delay = Duration::from_millis(30000);
-        let max_tokens = 20;
-        // ----------------------
-        
+        self.decode(&mut ctx, &tokens, 0, false, &mut self.governor.start())?;
+        let saved = crate::dirs::create_parent(&file)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(ctx.save_session_file(&file, &tokens)?));
+        if let Err(e) = saved {
+            eprintln!("  ⚠ Could not save prompt cache {}: {}", file.display(), e);
+        }
+        Ok(PrefixCache { ctx, tokens })
+    }
 
-        let mut batch = LlamaBatch::new(64, 1);
+    pub fn infer_model(&self, prompt: &str) -> anyhow::Result<String> {
+        self.answer(prompt, VERDICT_GRAMMAR, self.config.max_tokens)
+    }
+
+    /// The answer to `prompt`, held to `grammar` and at most `max_tokens` long.
+    fn answer(&self, prompt: &str, grammar: &str, max_tokens: usize) -> anyhow::Result<String> {
+        let tail = prompt.strip_prefix(self.prefix.as_str()).filter(|_| !self.prefix.is_empty());
+        let response = match tail {
+            Some(tail) => {
+                let mut cache = self.cache.lock().map_err(|_| anyhow::anyhow!("prompt cache lock poisoned"))?;
+                let cache = match &mut *cache {
+                    Some(cache) => cache,
+                    None => cache.insert(self.prefix_cache()?),
+                };
+                // Only the prefix stays; the last file's tail and answer are dropped
+                let start = cache.tokens.len();
+                cache.ctx.clear_kv_cache_seq(Some(0), Some(start as u32), None)?;
+                let tokens = self.model.str_to_token(tail, AddBos::Never)?;
+                self.generate(&mut cache.ctx, &tokens, start, grammar, max_tokens)?
+            }
+            None => {
+                let mut ctx = self.create_context()?;
+                let tokens = self.model.str_to_token(prompt, AddBos::Always)?;
+                self.generate(&mut ctx, &tokens, 0, grammar, max_tokens)?
+            }
+        };
+        self.write_response_to_file(&response)?;
+        Ok(response)
+    }
+
+    /// Decodes `tokens` at positions from `start`, `LLM.chunk-size` at a time,
+    /// pacing after each chunk. With `logits`, the last token's logits are kept
+    /// and their index in the final batch returned.
+    fn decode(&self, ctx: &mut LlamaContext<'static>, tokens: &[LlamaToken], start: usize, logits: bool, pace: &mut Pace) -> anyhow::Result<i32> {
+        let chunk_size = self.config.chunk_size;
+
+        let mut batch = LlamaBatch::new(chunk_size.max(64), 1);
         let total = tokens.len();
         let chunks = tokens.chunks(chunk_size);
         let num_chunks = (total + chunk_size - 1) / chunk_size;
@@ -74,8 +198,8 @@ impl LlmEngine {
             let is_last_chunk = chunk_idx == num_chunks - 1;
 
             for (i, &token) in chunk.iter().enumerate() {
-                let pos = (chunk_idx * chunk_size + i) as i32;
-                let needs_logits = is_last_chunk && i == chunk.len() - 1;
+                let pos = (start + chunk_idx * chunk_size + i) as i32;
+                let needs_logits = logits && is_last_chunk && i == chunk.len() - 1;
                 batch.add(token, pos, &[0], needs_logits)?;
             }
 
@@ -84,27 +208,44 @@ impl LlmEngine {
             batch.clear();
 
             if !is_last_chunk {
-                sleep(chunk_delay);
+                pace.step();
             }
         }
 
-        // n_cur should now reflect total prompt tokens processed
-        let mut n_cur = total as i32;
+        // Logits index = last token of final chunk
+        Ok(last_chunk_len - 1)
+    }
 
-        // Minimal sampler (lower CPU than chain_simple)
-        let mut sampler = LlamaSampler::greedy();
+    /// Decodes `tokens` after the `start` already in `ctx` and samples the answer.
+    fn generate(&self, ctx: &mut LlamaContext<'static>, tokens: &[LlamaToken], start: usize, grammar: &str, max_tokens: usize) -> anyhow::Result<String> {
+        let mut pace = self.governor.start();
+        let mut logits_idx = self.decode(ctx, tokens, start, true, &mut pace)?;
+
+        // n_cur should now reflect total prompt tokens processed
+        let mut n_cur = (start + tokens.len()) as i32;
+
+        // The grammar rules out every token that would break the answer's JSON, then
+        // the likeliest allowed token is picked, or one is sampled if a temperature is set
+        let grammar = LlamaSampler::grammar(self.model, grammar, "root")?;
+        let mut sampler = if self.config.temp > 0.0 {
+            LlamaSampler::chain_simple([
+                grammar,
+                LlamaSampler::temp(self.config.temp),
+                LlamaSampler::dist(self.config.seed),
+            ])
+        } else {
+            LlamaSampler::chain_simple([grammar, LlamaSampler::greedy()])
+        };
 
         // UTF-8 decoder
         let mut decoder = encoding_rs::UTF_8.new_decoder();
 
-        // Logits index = last token of final chunk
-        let mut logits_idx = last_chunk_len - 1;
-
+        let mut batch = LlamaBatch::new(1, 1);
         let mut response = String::new();
 
         for _ in 0..max_tokens {
-            let token = sampler.sample(&ctx, logits_idx);
-            sampler.accept(token);
+            // Sampling also accepts the token; accepting it again would push the grammar past it
+            let token = sampler.sample(ctx, logits_idx);
 
             if self.model.is_eog_token(token) {
                 break;
@@ -114,9 +255,6 @@ impl LlmEngine {
             let mut output_string = String::with_capacity(32);
             decoder.decode_to_string(&output_bytes, &mut output_string, false);
 
-            //print!("{}", output_string);
-            //std::io::stdout().flush()?;
-
             response.push_str(&output_string);
 
             // Prepare next iteration
@@ -128,28 +266,48 @@ impl LlmEngine {
 
             ctx.decode(&mut batch)?;
 
-            // throttle generation
-            if !gen_delay.is_zero() {
-                sleep(gen_delay);
-            }
+            pace.step();
         }
 
         decoder.decode_to_string(b"", &mut response, true);
-        self.write_response_to_file(&response)?;
         Ok(response)
     }
 
     fn write_response_to_file(&self, response: &str) -> anyhow::Result<()> {
-        //Write responses to output file
+        //Write responses to output file, LLM.responses in the cache directory by default
+        crate::dirs::create_parent(&self.config.responses)?;
         let mut file = fs::OpenOptions::new()
             .append(true) // append mode
             .create(true) // create if it doesn't exist
-            .open("./LLM_responses.txt")?;
+            .open(&self.config.responses)?;
         writeln!(file, "{}", response)?;
         Ok(())
     }
 }
 
+/// Session file for the model named `model`: its file name with `.session` in
+/// place of `.gguf`.
+fn session_file_name(model: &str) -> String {
+    let stem = model.strip_suffix(".gguf").unwrap_or(model);
+    format!("{}.session", stem)
+}
+
+impl VerdictEngine for LlmEngine {
+    fn infer(&self, _meta: &FileMeta, prompt: &str) -> anyhow::Result<String> {
+        self.infer_model(prompt)
+    }
+
+    fn infer_batch(&self, metas: &[FileMeta], prompt: &str) -> anyhow::Result<Vec<Option<String>>> {
+        // Every file gets an answer as long as a single one
+        let raw = self.answer(prompt, &batch_grammar(metas.len()), self.config.max_tokens * metas.len())?;
+        Ok(verdict_engine::split_numbered(&raw, metas.len()))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,12 +454,58 @@ mod tests {
         assert!(result.is_err());
     }
 
+    // ── VERDICT_GRAMMAR ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_grammar_allows_every_decision() {
+        use crate::actor::ai_model::Decision;
+        for decision in [Decision::Keep, Decision::Delete, Decision::Unsure] {
+            let name = serde_json::to_string(&decision).unwrap();
+            assert!(VERDICT_GRAMMAR.contains(&name.replace('"', "\\\"")), "{} missing", name);
+        }
+    }
+
+    #[test]
+    fn test_grammar_fields_follow_the_answer() {
+        use crate::actor::ai_model::{Answer, Decision};
+        let answer = serde_json::to_string(&Answer { decision: Decision::Keep, confidence: 1, reason: String::new() }).unwrap();
+        let fields = ["decision", "confidence", "reason"];
+        let in_answer: Vec<usize> = fields.iter().map(|f| answer.find(&format!("\"{}\"", f)).unwrap()).collect();
+        let in_grammar: Vec<usize> = fields.iter().map(|f| VERDICT_GRAMMAR.find(&format!("\\\"{}\\\"", f)).unwrap()).collect();
+        assert!(in_answer.is_sorted() && in_grammar.is_sorted(), "{} / {}", answer, VERDICT_GRAMMAR);
+    }
+
+    #[test]
+    fn test_batch_grammar_numbers_every_answer() {
+        let grammar = batch_grammar(3);
+        assert!(grammar.contains(r#"root       ::= "1: " answer "\n" "2: " answer "\n" "3: " answer "\n""#), "{}", grammar);
+        assert!(!grammar.contains("\"4: \""));
+        assert!(grammar.contains("answer     ::= \"{\""));
+    }
+
+    #[test]
+    fn test_batched_answers_split_back_into_answers() {
+        use crate::actor::ai_model::Answer;
+        let one = r#"{"decision":"keep","confidence":95,"reason":"Recent."}"#;
+        let two = r#"{"decision":"delete","confidence":90,"reason":"Old cache."}"#;
+        let answers = verdict_engine::split_numbered(&format!("1: {}\n2: {}\n", one, two), 2);
+        assert!(answers.iter().all(|a| a.as_deref().and_then(Answer::parse).is_some()), "{:?}", answers);
+    }
+
+    // ── session_file_name ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_session_file_is_named_after_the_model() {
+        assert_eq!(session_file_name("Llama-3.2-3B-Instruct-Q4_K_M.gguf"), "Llama-3.2-3B-Instruct-Q4_K_M.session");
+        assert_eq!(session_file_name("model.bin"), "model.bin.session");
+    }
+
     // ── load_new_model: invalid path returns error ────────────────────────────
     // This is the only load_new_model path we can test without the .gguf file.
 
     #[test]
     fn test_load_new_model_nonexistent_path_returns_error() {
-        let result = LlmEngine::load_new_model("/nonexistent/path/model.gguf");
+        let result = LlmEngine::load_new_model("/nonexistent/path/model.gguf", LlmConfig::default());
         assert!(result.is_err(), "loading a missing model file should fail");
     }
 }
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use crate::dirs;
+
+/// Held for as long as this process uses a database, so a second instance
+/// can't open it at the same time. The lock file sits next to the database,
+/// as `<db>.lock`, and holds the owner's pid.
+///
+/// On Unix the file is locked with `flock`, which the kernel releases when the
+/// process exits, so a crash never leaves a stale lock. Elsewhere the file's
+/// existence is the lock and it is removed on drop.
+#[derive(Debug)]
+pub(crate) struct InstanceLock {
+    /// Kept open for as long as the lock is held.
+    _file: File,
+    #[cfg(not(unix))]
+    path: PathBuf,
+}
+
+/// Why the database could not be locked.
+#[derive(Debug)]
+pub(crate) enum LockError {
+    /// Another process holds the lock; `pid` is what it wrote into the file.
+    Held { db: PathBuf, pid: Option<u32> },
+    Io { path: PathBuf, error: io::Error },
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Held { db, pid: Some(pid) } => {
+                write!(f, "{} is in use by another CruftCrawler (pid {})", db.display(), pid)
+            }
+            LockError::Held { db, pid: None } => write!(f, "{} is in use by another CruftCrawler", db.display()),
+            LockError::Io { path, error } => write!(f, "could not lock {}: {}", path.display(), error),
+        }
+    }
+}
+
+impl Error for LockError {}
+
+/// `<db>.lock`, next to the database directory.
+pub(crate) fn lock_path(db: &Path) -> PathBuf {
+    let mut name = db.file_name().map(|n| n.to_os_string()).unwrap_or_else(|| "db".into());
+    name.push(".lock");
+    db.with_file_name(name)
+}
+
+impl InstanceLock {
+    /// Locks the database at `db`, creating its parent directory if needed.
+    pub fn acquire(db: &Path) -> Result<Self, LockError> {
+        let path = lock_path(db);
+        let io_error = |error: io::Error| LockError::Io { path: path.clone(), error };
+        dirs::create_parent(&path).map_err(io_error)?;
+
+        let mut file = match open_locked(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(LockError::Held { db: db.to_path_buf(), pid: read_pid(&path) });
+            }
+            Err(e) => return Err(io_error(e)),
+        };
+
+        file.set_len(0).map_err(io_error)?;
+        write!(file, "{}", std::process::id()).map_err(io_error)?;
+        file.flush().map_err(io_error)?;
+        Ok(Self {
+            _file: file,
+            #[cfg(not(unix))]
+            path,
+        })
+    }
+}
+
+#[cfg(unix)]
+fn open_locked(path: &Path) -> io::Result<File> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+    // SAFETY: the descriptor belongs to `file`, which outlives the call
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn open_locked(path: &Path) -> io::Result<File> {
+    OpenOptions::new().read(true).write(true).create_new(true).open(path)
+}
+
+#[cfg(not(unix))]
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn temp_db(name: &str) -> PathBuf {
+        let base = std::env::temp_dir().join(format!("cruft_lock_{}", name));
+        let _ = fs::remove_dir_all(&base);
+        base.join("data").join("db")
+    }
+
+    // ── lock_path ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_lock_file_sits_next_to_database() {
+        assert_eq!(lock_path(Path::new("/data/cruft-crawler/db")), PathBuf::from("/data/cruft-crawler/db.lock"));
+        assert_eq!(lock_path(Path::new("./src/db")), PathBuf::from("./src/db.lock"));
+    }
+
+    // ── InstanceLock ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_acquire_creates_directory_and_records_pid() {
+        let db = temp_db("acquire");
+        let _lock = InstanceLock::acquire(&db).unwrap();
+
+        assert!(db.parent().unwrap().is_dir());
+        assert_eq!(read_pid(&lock_path(&db)), Some(std::process::id()));
+    }
+
+    #[test]
+    fn test_second_acquire_is_refused_with_owner_pid() {
+        let db = temp_db("held");
+        let _first = InstanceLock::acquire(&db).unwrap();
+
+        match InstanceLock::acquire(&db) {
+            Err(LockError::Held { pid, .. }) => assert_eq!(pid, Some(std::process::id())),
+            other => panic!("expected the lock to be held, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lock_is_released_on_drop() {
+        let db = temp_db("released");
+        drop(InstanceLock::acquire(&db).unwrap());
+        assert!(InstanceLock::acquire(&db).is_ok());
+    }
+
+    #[test]
+    fn test_held_message_names_database_and_pid() {
+        let err = LockError::Held { db: PathBuf::from("/d/db"), pid: Some(42) };
+        assert_eq!(err.to_string(), "/d/db is in use by another CruftCrawler (pid 42)");
+    }
+}
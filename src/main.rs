@@ -1,6 +1,8 @@
 use steady_state::*;
 use std::time::Duration;
 use std::path::PathBuf;
+use crate::cli::{Cli, Command, ConfigCommand, DbCommand, ReviewArgs};
+use crate::config::Config;
 
 // Actor modules — file_handler removed
 pub(crate) mod actor {
@@ -8,33 +10,169 @@ pub(crate) mod actor {
     pub(crate) mod db_manager;
     pub(crate) mod ai_model;
     pub(crate) mod user_interface;
+    pub(crate) mod headless;
 }
 pub(crate) mod llm_engine;
+pub(crate) mod verdict_engine;
+pub(crate) mod governor;
+pub(crate) mod scan_roots;
+pub(crate) mod hashing;
+pub(crate) mod duplicates;
+pub(crate) mod file_index;
+pub(crate) mod exclude;
+pub(crate) mod protection;
+pub(crate) mod quarantine;
+pub(crate) mod trash;
+pub(crate) mod verify;
+pub(crate) mod journal;
+pub(crate) mod cli;
+pub(crate) mod config;
+pub(crate) mod dirs;
+pub(crate) mod lock;
+pub(crate) mod plan;
+pub(crate) mod commands;
 
 // TODO: Add functionality for priority setting using screensaver api
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-     
+    let cli = Cli::parse();
 
+    init_logging(cli.log_level)?;
 
-    init_logging(LogLevel::Info)?;
+    let command = cli.subcommand();
+    let mut out = std::io::stdout();
 
-    // pass unit value into .build() to ignore cli_args for now
-    let mut graph = GraphBuilder::default().build(());
+    // Checking the setup must work even when the database can't be opened
+    if let Command::Config { command: ConfigCommand::Check } = command {
+        return commands::config_check(&cli, &mut out);
+    }
+
+    // Every setting is checked before anything runs, and all problems are shown at once
+    let config = match Config::from_cli(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("\n  ✗ The configuration can't be used:");
+            for line in e.to_string().lines() {
+                eprintln!("  {}", line);
+            }
+            eprintln!();
+            std::process::exit(2);
+        }
+    };
+
+    // Held until we exit, so a second instance can't open the same database
+    let _lock = match lock::InstanceLock::acquire(&config.database.path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("\n  ✗ {}", e);
+            eprintln!();
+            std::process::exit(1);
+        }
+    };
 
-    build_graph(&mut graph);
+    // One handle shared by every actor; sled allows a single opener per process
+    let db = sled::open(&config.database.path)?;
+
+    match command {
+        Command::Review(args) => review(&cli, &config, &args, db),
+        Command::Scan => commands::scan(&db, &config, &mut out),
+        Command::Report(args) => {
+            let journal = journal::Journal::open(&db, &config.database.journal)?;
+            commands::report(&journal, &args, &mut out)
+        }
+        Command::Apply(args) => {
+            let plan = plan::DeletionPlan::load(&args.plan)?;
+            let index = file_index::FileIndex::open(&db)?;
+            let protections = protection::Protections::open(&db)?;
+            let quarantine = config.quarantine.open(&db)?;
+            let journal = journal::Journal::open(&db, &config.database.journal)?;
+            let trash = desktop_trash(args.trash);
+            commands::apply(&plan, &index, &protections, &quarantine, trash.as_ref(), &journal, &mut out)
+        }
+        Command::Restore(args) => {
+            let index = file_index::FileIndex::open(&db)?;
+            let quarantine = config.quarantine.open(&db)?;
+            let journal = journal::Journal::open(&db, &config.database.journal)?;
+            commands::restore(&index, &quarantine, &journal, &args, &mut out)
+        }
+        Command::Purge(args) => {
+            let quarantine = config.quarantine.open(&db)?;
+            let journal = journal::Journal::open(&db, &config.database.journal)?;
+            commands::purge(&quarantine, &journal, &args, &mut out)
+        }
+        Command::Marks { command } => {
+            let protections = protection::Protections::open(&db)?;
+            commands::marks(&protections, &command, &mut out)
+        }
+        Command::Db { command: DbCommand::Stats } => commands::print_db_stats(&commands::db_stats(&db, &config)?, &mut out),
+        Command::Config { .. } => unreachable!("handled before the database is opened"),
+    }
+}
+
+// Crawl, review and delete: the full actor graph with the terminal UI, or with
+// the headless sink, which stops the graph once every file has been reviewed
+fn review(cli: &Cli, config: &Config, args: &ReviewArgs, db: sled::Db) -> Result<(), Box<dyn std::error::Error>> {
+    let mut graph = GraphBuilder::default().build(cli.clone());
+
+    build_graph(&mut graph, db, config, args)?;
 
     graph.start();
 
     graph.block_until_stopped(Duration::from_secs(1))
 }
 
+// The desktop trash when asked for and there is a home directory to keep it in
+fn desktop_trash(wanted: bool) -> Option<trash::Trash> {
+    if !wanted {
+        return None;
+    }
+    let trash = trash::Trash::from_env();
+    if trash.is_none() {
+        eprintln!("  ⚠ No home directory to keep a trash in; deleted files will be quarantined");
+    }
+    trash
+}
+
 const NAME_CRAWLER:  &str = "CRAWLER";
 const NAME_DB:       &str = "DB_MANAGER";
 const NAME_AI_MODEL: &str = "AI_MODEL";
 const NAME_UI_ACTOR: &str = "UI_ACTOR";
+const NAME_HEADLESS: &str = "HEADLESS";
+
+fn build_graph(graph: &mut Graph, db: sled::Db, config: &Config, args: &ReviewArgs) -> Result<(), Box<dyn std::error::Error>> {
+
+    // Path-keyed records of every scan: written by the DB actor, read by the crawler and AI model
+    let index = file_index::FileIndex::open(&db)?;
+
+    // Never-delete marks: written by the DB actor, checked by the crawler before review
+    let protections = protection::Protections::open(&db)?;
 
-fn build_graph(graph: &mut Graph) {
+    // Deleted files are moved here and kept for the retention period before being purged
+    let quarantine = config.quarantine.open(&db)?;
+
+    // Desktop trash for deletions with --trash; otherwise only the quarantine is used
+    let trash = desktop_trash(args.trash);
+
+    // A dry run deletes nothing and writes a plan of what it would have deleted
+    let plan_file = args.dry_run.then(|| args.plan.clone().unwrap_or_else(|| PathBuf::from(plan::PLAN_FILE)));
+
+    // Each actor gets its own section of the configuration
+    let directory_config = config.directory.clone();
+    let hash_config = config.hashing.clone();
+    // Lets the crawler tell which stored verdicts the engine would give again
+    let engine_name = actor::ai_model::engine_name(&config.llm);
+    let database_config = config.database.clone();
+    let keeper = config.duplicates.keeper();
+    let llm_config = config.llm.clone();
+    let ui_config = config.ui.clone();
+    let mut headless_config = config.headless.clone();
+    if let Some(report) = &args.report {
+        headless_config.report = report.clone();
+    }
+    let auto_apply = args.auto_apply;
+
+    // Append-only record of every deletion attempt, also written out as JSON Lines
+    let journal = journal::Journal::open(&db, &config.database.journal)?;
 
     // Channel monitoring: alert colors when channels fill up
     let channel_builder = graph.channel_builder()
@@ -42,18 +180,24 @@ fn build_graph(graph: &mut Graph) {
         .with_filled_trigger(Trigger::AvgAbove(Filled::p60()), AlertColor::Orange)
         .with_filled_percentile(Percentile::p80());
 
-    // Crawler → DB (FileMeta)
+    // Crawler → DB (ScanEvent: new/changed FileMeta or vanished path)
     let (crawler_to_db_tx, crawler_to_db_rx) = channel_builder.build();
 
     // Crawler → AI Model (String)
     let (crawler_to_ai_model_tx, crawler_to_ai_model_rx) = channel_builder.build();
 
-    // AI Model → UI (String verdict)
+    // AI Model → UI (Verdict: the file, keep/delete/unsure, confidence and reasoning)
     let (ai_model_to_ui_tx, ai_model_to_ui_rx) = channel_builder.build();
 
-    // UI → DB (PathBuf of confirmed deletions) — replaces the old two-hop UI→FileHandler→DB
+    // AI Model → DB (IndexEntry carrying the verdict to remember)
+    let (ai_model_to_db_tx, ai_model_to_db_rx) = channel_builder.build();
+
+    // UI → DB (UiRequest: confirmed deletions and never-delete marks) — replaces the old two-hop UI→FileHandler→DB
     let (ui_to_db_tx, ui_to_db_rx) = channel_builder.build();
 
+    // DB → UI (DbReport: duplicate groups once the crawl is complete, refused deletions)
+    let (db_to_ui_tx, db_to_ui_rx) = channel_builder.build();
+
     // Actor monitoring: track load and CPU averages
     let actor_builder = graph.actor_builder()
         .with_load_avg()
@@ -61,20 +205,38 @@ fn build_graph(graph: &mut Graph) {
 
     // Crawler actor
     let state = new_state();
+    let crawler_index = index.clone();
+    let crawler_protections = protections.clone();
     actor_builder.with_name(NAME_CRAWLER)
         .build(move |actor| actor::crawler::run(
             actor,
             crawler_to_db_tx.clone(),
             crawler_to_ai_model_tx.clone(),
+            crawler_index.clone(),
+            crawler_protections.clone(),
+            directory_config.clone(),
+            hash_config.clone(),
+            engine_name.clone(),
             state.clone(),
         ), SoloAct);
 
     // DB Manager actor — now receives PathBuf from UI instead of String from file handler
+    let db_index = index.clone();
     actor_builder.with_name(NAME_DB)
         .build(move |actor| actor::db_manager::run(
             actor,
             crawler_to_db_rx.clone(),
+            ai_model_to_db_rx.clone(),
             ui_to_db_rx.clone(),
+            db_to_ui_tx.clone(),
+            db_index.clone(),
+            protections.clone(),
+            quarantine.clone(),
+            trash.clone(),
+            journal.clone(),
+            database_config.clone(),
+            keeper.clone(),
+            plan_file.clone(),
         ), SoloAct);
 
     // AI Model actor
@@ -83,15 +245,35 @@ fn build_graph(graph: &mut Graph) {
             actor,
             crawler_to_ai_model_rx.clone(),
             ai_model_to_ui_tx.clone(),
+            ai_model_to_db_tx.clone(),
+            index.clone(),
+            llm_config.clone(),
         ), SoloAct);
 
-    // UI actor — now sends directly to DB, no file handler in between
-    actor_builder.with_name(NAME_UI_ACTOR)
-        .build(move |actor| actor::user_interface::run(
-            actor,
-            ai_model_to_ui_rx.clone(),
-            ui_to_db_tx.clone(),
-        ), SoloAct);
+    if args.headless {
+        // Headless sink in place of the UI — reports suggestions and applies them within the policy
+        actor_builder.with_name(NAME_HEADLESS)
+            .build(move |actor| actor::headless::run(
+                actor,
+                ai_model_to_ui_rx.clone(),
+                db_to_ui_rx.clone(),
+                ui_to_db_tx.clone(),
+                headless_config.clone(),
+                auto_apply,
+            ), SoloAct);
+    } else {
+        // UI actor — now sends directly to DB, no file handler in between
+        actor_builder.with_name(NAME_UI_ACTOR)
+            .build(move |actor| actor::user_interface::run(
+                actor,
+                ai_model_to_ui_rx.clone(),
+                db_to_ui_rx.clone(),
+                ui_to_db_tx.clone(),
+                ui_config.clone(),
+            ), SoloAct);
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -120,23 +302,28 @@ mod tests {
         assert_eq!(NAME_UI_ACTOR, "UI_ACTOR");
     }
 
+    #[test]
+    fn test_name_headless_is_correct() {
+        assert_eq!(NAME_HEADLESS, "HEADLESS");
+    }
+
     #[test]
     fn test_all_actor_names_are_unique() {
-        let names = [NAME_CRAWLER, NAME_DB, NAME_AI_MODEL, NAME_UI_ACTOR];
+        let names = [NAME_CRAWLER, NAME_DB, NAME_AI_MODEL, NAME_UI_ACTOR, NAME_HEADLESS];
         let unique: std::collections::HashSet<&str> = names.iter().copied().collect();
         assert_eq!(unique.len(), names.len(), "all actor names must be unique");
     }
 
     #[test]
     fn test_all_actor_names_are_nonempty() {
-        for name in [NAME_CRAWLER, NAME_DB, NAME_AI_MODEL, NAME_UI_ACTOR] {
+        for name in [NAME_CRAWLER, NAME_DB, NAME_AI_MODEL, NAME_UI_ACTOR, NAME_HEADLESS] {
             assert!(!name.is_empty(), "actor name '{}' must not be empty", name);
         }
     }
 
     #[test]
     fn test_all_actor_names_are_uppercase() {
-        for name in [NAME_CRAWLER, NAME_DB, NAME_AI_MODEL, NAME_UI_ACTOR] {
+        for name in [NAME_CRAWLER, NAME_DB, NAME_AI_MODEL, NAME_UI_ACTOR, NAME_HEADLESS] {
             assert_eq!(
                 name, name.to_uppercase(),
                 "actor name '{}' should be uppercase",
@@ -163,4 +350,86 @@ mod tests {
     fn test_llm_engine_module_exists() {
         let _ = std::stringify!(crate::llm_engine);
     }
+
+    // ── full graph ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_headless_review_with_mock_engine() {
+        use std::fs;
+        use std::time::SystemTime;
+        use crate::cli::AutoApply;
+        use crate::config::Engine;
+
+        let base = std::env::temp_dir().join("cruft_test_graph_mock");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("files");
+        fs::create_dir_all(&root).unwrap();
+        let stale = root.join("cache_session.tmp");
+        let fresh = root.join("report_final.pdf");
+        fs::write(&stale, b"left behind").unwrap();
+        fs::write(&fresh, b"still needed").unwrap();
+        fs::File::options().write(true).open(&stale).unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(400 * 86400)).unwrap();
+
+        let mut config = Config::default();
+        config.place(&dirs::Dirs { config: base.join("config"), data: base.join("data"), cache: base.join("cache") });
+        config.directory.path = root.clone();
+        // Only the temp root: no scan list or .cruftignore from the working directory
+        config.directory.scan_list_dir = base.clone();
+        config.llm.engine = Engine::Mock;
+        config.headless.report = base.join("report.txt");
+        let args = ReviewArgs { headless: true, auto_apply: Some(AutoApply::All), ..Default::default() };
+
+        let db = sled::open(&config.database.path).unwrap();
+        let cli = Cli::try_parse_from(["cruft-crawler"]).unwrap();
+        let mut graph = GraphBuilder::default().build(cli);
+        build_graph(&mut graph, db, &config, &args).unwrap();
+        graph.start();
+        graph.block_until_stopped(Duration::from_secs(5)).unwrap();
+
+        let report = fs::read_to_string(&config.headless.report).unwrap();
+        assert!(report.contains("deleted") && report.contains(&stale.display().to_string()), "{}", report);
+        assert!(!report.contains(&fresh.display().to_string()), "{}", report);
+        assert!(!stale.exists(), "the stale file is quarantined: {}", report);
+        assert!(fresh.exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_review_after_scan_asks_about_scanned_files() {
+        use std::fs;
+        use crate::config::Engine;
+        use crate::file_index::FileIndex;
+
+        let base = std::env::temp_dir().join("cruft_test_graph_scan_then_review");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("files");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("notes.txt"), b"meeting notes").unwrap();
+        fs::write(root.join("old_build.log"), b"compiling").unwrap();
+
+        let mut config = Config::default();
+        config.place(&dirs::Dirs { config: base.join("config"), data: base.join("data"), cache: base.join("cache") });
+        config.directory.path = root.clone();
+        config.directory.scan_list_dir = base.clone();
+        config.llm.engine = Engine::Mock;
+        config.headless.report = base.join("report.txt");
+
+        let db = sled::open(&config.database.path).unwrap();
+        commands::scan(&db, &config, &mut Vec::new()).unwrap();
+        let index = FileIndex::open(&db).unwrap();
+        assert!(index.entries().all(|entry| entry.verdict.is_none()), "scan stores no verdicts");
+
+        let args = ReviewArgs { headless: true, ..Default::default() };
+        let cli = Cli::try_parse_from(["cruft-crawler"]).unwrap();
+        let mut graph = GraphBuilder::default().build(cli);
+        build_graph(&mut graph, db.clone(), &config, &args).unwrap();
+        graph.start();
+        graph.block_until_stopped(Duration::from_secs(5)).unwrap();
+
+        let files: Vec<_> = index.entries().filter(|entry| entry.meta.is_file).collect();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|entry| entry.verdict.is_some()), "{:?}", files);
+        let _ = fs::remove_dir_all(&base);
+    }
 }
@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::actor::crawler::FileMeta;
+use crate::commands::human_size;
+use crate::dirs;
+use crate::duplicates::DuplicateGroup;
+use crate::file_index::FileIndex;
+use crate::protection::Protections;
+use crate::quarantine::unix_now;
+
+/// Where a dry run writes its plan when `--plan` is not given, relative to the
+/// working directory. The readable copy goes next to it with a `.txt` extension.
+pub(crate) const PLAN_FILE: &str = "deletion-plan.json";
+
+/// Why a file is in the plan.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum PlanReason {
+    /// The model judged the file safe to delete.
+    Ai,
+    /// Identical copy of `keeper`, which stays.
+    Duplicate { keeper: PathBuf },
+}
+
+/// One file a dry run would have deleted.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct PlanEntry {
+    pub path:      PathBuf,
+    pub size:      u64,
+    pub reason:    PlanReason,
+    pub verdict:   Option<String>,
+    pub reasoning: Option<String>,
+    /// The file as it was scanned; applying the plan deletes it only if it still matches.
+    pub scanned:   FileMeta,
+}
+
+/// Everything the pipeline would delete, written by a dry run and carried out
+/// later with `apply`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct DeletionPlan {
+    /// When the plan was made (unix seconds).
+    pub created_at:  i64,
+    pub entries:     Vec<PlanEntry>,
+    /// Bytes freed if every entry is deleted.
+    pub reclaimable: u64,
+}
+
+impl DeletionPlan {
+    /// Every file the model flagged for deletion plus every redundant copy in
+    /// `groups`, leaving out never-delete files and files that are gone. A file
+    /// that is both is listed once, for the model's verdict.
+    pub fn build(index: &FileIndex, protections: &Protections, groups: &[DuplicateGroup]) -> Self {
+        let mut entries: Vec<PlanEntry> = index.entries()
+            .filter(|e| e.meta.is_file && e.verdict.as_ref().is_some_and(|v| v.is_delete()))
+            .filter(|e| !protections.is_protected(&e.meta) && e.meta.abs_path.is_file())
+            .map(|e| {
+                let verdict = e.verdict.unwrap_or_else(|| unreachable!("filtered on the verdict"));
+                PlanEntry {
+                    path:      e.meta.abs_path.clone(),
+                    size:      e.meta.size,
+                    reason:    PlanReason::Ai,
+                    verdict:   Some(verdict.verdict),
+                    reasoning: Some(verdict.reasoning).filter(|r| !r.is_empty()),
+                    scanned:   e.meta,
+                }
+            })
+            .collect();
+
+        let mut listed: HashSet<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+        for group in groups {
+            for copy in &group.redundant {
+                if protections.is_protected(copy) || !listed.insert(copy.abs_path.clone()) {
+                    continue;
+                }
+                entries.push(PlanEntry {
+                    path:      copy.abs_path.clone(),
+                    size:      copy.size,
+                    reason:    PlanReason::Duplicate { keeper: group.keeper.abs_path.clone() },
+                    verdict:   None,
+                    reasoning: None,
+                    scanned:   copy.clone(),
+                });
+            }
+        }
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let reclaimable = entries.iter().map(|e| e.size).sum();
+        Self { created_at: unix_now(), entries, reclaimable }
+    }
+
+    /// Writes the plan as JSON to `file` and as text next to it; returns the text file's path.
+    pub fn write(&self, file: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        dirs::create_parent(file)?;
+        fs::write(file, serde_json::to_vec_pretty(self)?)?;
+
+        let text_file = file.with_extension("txt");
+        let mut text = Vec::new();
+        self.render(&mut text)?;
+        fs::write(&text_file, text)?;
+        Ok(text_file)
+    }
+
+    pub fn load(file: &Path) -> Result<Self, Box<dyn Error>> {
+        let bytes = fs::read(file).map_err(|e| format!("{} could not be read: {}", file.display(), e))?;
+        Ok(serde_json::from_slice(&bytes).map_err(|e| format!("{} is not a deletion plan: {}", file.display(), e))?)
+    }
+
+    /// The readable form: one line per file with its size, then why it is listed.
+    pub fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Deletion plan made {}: {} file(s), {} reclaimable",
+                 crate::trash::local_timestamp(self.created_at), self.entries.len(), human_size(self.reclaimable))?;
+        writeln!(out, "Nothing has been deleted. Apply it with: cruft-crawler apply <plan.json>")?;
+        for entry in &self.entries {
+            writeln!(out)?;
+            writeln!(out, "{:>10}  {}", human_size(entry.size), entry.path.display())?;
+            match &entry.reason {
+                PlanReason::Ai => {
+                    let verdict = entry.verdict.as_deref().unwrap_or("delete");
+                    match &entry.reasoning {
+                        Some(why) => writeln!(out, "            model: {} — {}", verdict, why)?,
+                        None => writeln!(out, "            model: {}", verdict)?,
+                    }
+                }
+                PlanReason::Duplicate { keeper } => writeln!(out, "            duplicate of {}", keeper.display())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::ai_model::VerdictRecord;
+    use crate::duplicates::{DuplicateFinder, KeeperPolicy};
+    use crate::file_index::IndexEntry;
+    use crate::hashing::{HashConfig, HashStrategy};
+    use crate::protection::Protection;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    struct Setup {
+        base:        PathBuf,
+        index:       FileIndex,
+        protections: Protections,
+    }
+
+    fn setup(name: &str) -> Setup {
+        let base = std::env::temp_dir().join(format!("cruft_plan_{}", name));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("files")).unwrap();
+        let db = sled::open(base.join("db")).unwrap();
+        Setup { index: FileIndex::open(&db).unwrap(), protections: Protections::open(&db).unwrap(), base }
+    }
+
+    /// Writes a file and indexes it with `verdict`, returning what was indexed.
+    fn indexed(s: &Setup, name: &str, contents: &[u8], verdict: Option<&str>) -> FileMeta {
+        let path = s.base.join("files").join(name);
+        fs::write(&path, contents).unwrap();
+        let config = HashConfig { strategy: HashStrategy::Full, ..HashConfig::default() };
+        let meta = crate::actor::crawler::file_meta_from_path(&path, &fs::metadata(&path).unwrap(), &config).unwrap();
+        let verdict = verdict.map(|v| VerdictRecord::from_output(&format!("Old build output.\nDecision: {}", v), "m.gguf"));
+        s.index.put(&IndexEntry::new(meta.clone(), verdict)).unwrap();
+        meta
+    }
+
+    fn groups(metas: &[&FileMeta]) -> Vec<DuplicateGroup> {
+        let mut finder = DuplicateFinder::default();
+        for meta in metas {
+            finder.add((*meta).clone());
+        }
+        finder.groups(&KeeperPolicy::default())
+    }
+
+    // ── build ─────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_lists_delete_verdicts_only() {
+        let s = setup("verdicts");
+        let junk = indexed(&s, "junk.o", b"object", Some("delete"));
+        indexed(&s, "notes.txt", b"notes", Some("keep"));
+        indexed(&s, "new.txt", b"new", None);
+
+        let plan = DeletionPlan::build(&s.index, &s.protections, &[]);
+
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, junk.abs_path);
+        assert_eq!(plan.entries[0].reason, PlanReason::Ai);
+        assert_eq!(plan.entries[0].verdict.as_deref(), Some("delete"));
+        assert_eq!(plan.reclaimable, 6);
+    }
+
+    #[test]
+    fn test_leaves_out_protected_and_missing_files() {
+        let s = setup("skipped");
+        let kept = indexed(&s, "kept.o", b"a", Some("delete"));
+        let gone = indexed(&s, "gone.o", b"b", Some("delete"));
+        s.protections.add(&Protection::Path(kept.abs_path.clone())).unwrap();
+        fs::remove_file(&gone.abs_path).unwrap();
+
+        assert!(DeletionPlan::build(&s.index, &s.protections, &[]).entries.is_empty());
+    }
+
+    #[test]
+    fn test_adds_redundant_copies_once() {
+        let s = setup("duplicates");
+        let a = indexed(&s, "a.iso", b"same bytes", None);
+        let b = indexed(&s, "b.iso", b"same bytes", Some("delete"));
+        let c = indexed(&s, "c.iso", b"same bytes", None);
+
+        let plan = DeletionPlan::build(&s.index, &s.protections, &groups(&[&a, &b, &c]));
+
+        assert_eq!(plan.entries.len(), 2, "{:?}", plan.entries);
+        assert_eq!(plan.reclaimable, 20);
+        let keeper = groups(&[&a, &b, &c])[0].keeper.abs_path.clone();
+        assert!(plan.entries.iter().all(|e| e.path != keeper), "the keeper is never listed");
+        assert!(plan.entries.iter().any(|e| e.path == b.abs_path && e.reason == PlanReason::Ai));
+    }
+
+    // ── write / load ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_write_then_load_round_trips() {
+        let s = setup("round_trip");
+        indexed(&s, "junk.o", b"object", Some("delete"));
+        let plan = DeletionPlan::build(&s.index, &s.protections, &[]);
+        let file = s.base.join("out").join(PLAN_FILE);
+
+        let text_file = plan.write(&file).unwrap();
+
+        assert_eq!(DeletionPlan::load(&file).unwrap(), plan);
+        assert_eq!(text_file, s.base.join("out").join("deletion-plan.txt"));
+        let text = fs::read_to_string(text_file).unwrap();
+        assert!(text.contains("1 file(s), 6 B reclaimable"));
+        assert!(text.contains("model: delete — Old build output."));
+    }
+
+    #[test]
+    fn test_load_rejects_other_json() {
+        let s = setup("bad_json");
+        let file = s.base.join("plan.json");
+        fs::write(&file, "{\"entries\": 3}").unwrap();
+        assert!(DeletionPlan::load(&file).unwrap_err().to_string().contains("is not a deletion plan"));
+    }
+}
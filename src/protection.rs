@@ -0,0 +1,315 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::actor::crawler::FileMeta;
+use crate::file_index::{self, FileIndex};
+use crate::hashing;
+
+/// Never-delete marks: key is a one-byte kind tag followed by the path or
+/// content key, value is the cbor `Protection`.
+pub(crate) const PROTECTED_TREE: &str = "never_delete";
+
+const TAG_PATH: u8 = b'p';
+const TAG_CONTENT: u8 = b'c';
+const TAG_DIR: u8 = b'd';
+
+/// A never-delete mark. Files it covers are never sent for review or offered
+/// as a redundant duplicate.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub(crate) enum Protection {
+    /// Exactly this path.
+    Path(PathBuf),
+    /// Any file with this size and full-content hash, wherever it lives.
+    Content { size: u64, hash: String },
+    /// This directory and everything below it.
+    Dir(PathBuf),
+}
+
+/// What a never-delete request from the UI should cover, relative to one file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProtectScope {
+    File,
+    Content,
+    /// The file's parent directory.
+    Directory,
+}
+
+impl Protection {
+    /// Builds the mark `scope` asks for around `path`. A content mark needs the
+    /// full hash; the indexed one is used when present, otherwise the file is read.
+    pub fn for_path(path: &Path, scope: ProtectScope, index: &FileIndex) -> Result<Self, Box<dyn Error>> {
+        match scope {
+            ProtectScope::File => Ok(Protection::Path(path.to_path_buf())),
+            ProtectScope::Directory => {
+                let dir = path.parent().ok_or_else(|| format!("{} has no parent directory", path.display()))?;
+                Ok(Protection::Dir(dir.to_path_buf()))
+            }
+            ProtectScope::Content => {
+                if let Some(entry) = index.get(path)?
+                    && let Some(hash) = entry.meta.content_hash() {
+                    return Ok(Protection::Content { size: entry.meta.size, hash: hash.to_string() });
+                }
+                let size = fs::metadata(path)?.len();
+                Ok(Protection::Content { size, hash: hashing::full_hash(path)? })
+            }
+        }
+    }
+
+    fn key(&self) -> Vec<u8> {
+        match self {
+            Protection::Path(path) => tagged(TAG_PATH, file_index::path_key(path)),
+            Protection::Content { size, hash } => {
+                let mut key = content_prefix(*size);
+                key.extend_from_slice(hash.as_bytes());
+                key
+            }
+            Protection::Dir(path) => tagged(TAG_DIR, file_index::path_key(path)),
+        }
+    }
+}
+
+impl std::fmt::Display for Protection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protection::Path(path) => write!(f, "file {}", path.display()),
+            Protection::Content { size, hash } => write!(f, "content {} ({} bytes)", hash, size),
+            Protection::Dir(path) => write!(f, "directory {}", path.display()),
+        }
+    }
+}
+
+fn tagged(tag: u8, rest: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(rest.len() + 1);
+    key.push(tag);
+    key.extend_from_slice(rest);
+    key
+}
+
+// Size first, so checking a file only touches marks for files of its size
+fn content_prefix(size: u64) -> Vec<u8> {
+    tagged(TAG_CONTENT, &size.to_be_bytes())
+}
+
+/// Never-delete marks stored in sled. Cheap to clone; every clone sees the same tree.
+#[derive(Clone)]
+pub(crate) struct Protections {
+    tree: sled::Tree,
+}
+
+impl Protections {
+    pub fn open(db: &sled::Db) -> sled::Result<Self> {
+        Ok(Self { tree: db.open_tree(PROTECTED_TREE)? })
+    }
+
+    /// Adds a mark; false if it was already there.
+    pub fn add(&self, protection: &Protection) -> Result<bool, Box<dyn Error>> {
+        Ok(self.tree.insert(protection.key(), serde_cbor::to_vec(protection)?)?.is_none())
+    }
+
+    /// Removes a mark; false if it was not there.
+    pub fn remove(&self, protection: &Protection) -> Result<bool, Box<dyn Error>> {
+        Ok(self.tree.remove(protection.key())?.is_some())
+    }
+
+    /// Every mark, grouped by kind.
+    pub fn list(&self) -> Vec<Protection> {
+        self.tree
+            .iter()
+            .values()
+            .filter_map(|raw| raw.ok())
+            .filter_map(|raw| serde_cbor::from_slice(&raw).ok())
+            .collect()
+    }
+
+    /// The mark covering this file, if any. A file without a full hash is only
+    /// read when a content mark exists for its exact size.
+    pub fn protecting(&self, meta: &FileMeta) -> Result<Option<Protection>, Box<dyn Error>> {
+        let path = &meta.abs_path;
+
+        let exact = Protection::Path(path.clone());
+        if self.tree.contains_key(exact.key())? {
+            return Ok(Some(exact));
+        }
+
+        for dir in path.ancestors() {
+            let mark = Protection::Dir(dir.to_path_buf());
+            if self.tree.contains_key(mark.key())? {
+                return Ok(Some(mark));
+            }
+        }
+
+        if meta.is_file && self.tree.scan_prefix(content_prefix(meta.size)).next().is_some() {
+            let hash = match meta.content_hash() {
+                Some(h) => h.to_string(),
+                None => hashing::full_hash(path)?,
+            };
+            let mark = Protection::Content { size: meta.size, hash };
+            if self.tree.contains_key(mark.key())? {
+                return Ok(Some(mark));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// True if the file must not be suggested. When the check itself fails the
+    /// answer is yes: a missed suggestion is cheap, a wrong one is not.
+    pub fn is_protected(&self, meta: &FileMeta) -> bool {
+        match self.protecting(meta) {
+            Ok(mark) => mark.is_some(),
+            Err(e) => {
+                eprintln!("  ⚠ Could not check never-delete marks for {}: {}", meta.abs_path.display(), e);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_index::IndexEntry;
+    use crate::hashing::HashStrategy;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn open_temp_db(name: &str) -> sled::Db {
+        let path = std::env::temp_dir().join(format!("cruft_test_protect_{}", name));
+        let _ = fs::remove_dir_all(&path);
+        sled::open(&path).unwrap()
+    }
+
+    fn make_meta(abs_path: &str, size: u64, full_hash: Option<&str>) -> FileMeta {
+        let path = PathBuf::from(abs_path);
+        FileMeta {
+            rel_path: path.clone(),
+            abs_path: path.clone(),
+            file_name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            hash: "sampled".to_string(),
+            is_file: true,
+            size,
+            modified: 1,
+            created: 1,
+            readonly: false,
+            hash_strategy: HashStrategy::Sampled,
+            full_hash: full_hash.map(str::to_string),
+            inode: 0,
+        }
+    }
+
+    // ── Protections ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_path_mark_covers_only_that_path() {
+        let marks = Protections::open(&open_temp_db("path")).unwrap();
+        marks.add(&Protection::Path(PathBuf::from("/home/me/a.txt"))).unwrap();
+
+        assert!(marks.is_protected(&make_meta("/home/me/a.txt", 1, None)));
+        assert!(!marks.is_protected(&make_meta("/home/me/a.txt.bak", 1, None)));
+    }
+
+    #[test]
+    fn test_dir_mark_covers_everything_below() {
+        let marks = Protections::open(&open_temp_db("dir")).unwrap();
+        let mark = Protection::Dir(PathBuf::from("/home/me/photos"));
+        marks.add(&mark).unwrap();
+
+        assert_eq!(marks.protecting(&make_meta("/home/me/photos/2020/a.jpg", 1, None)).unwrap(), Some(mark));
+        assert!(!marks.is_protected(&make_meta("/home/me/photos2/a.jpg", 1, None)));
+    }
+
+    #[test]
+    fn test_content_mark_covers_every_copy() {
+        let marks = Protections::open(&open_temp_db("content")).unwrap();
+        marks.add(&Protection::Content { size: 4, hash: "h1".into() }).unwrap();
+
+        assert!(marks.is_protected(&make_meta("/x/one", 4, Some("h1"))));
+        assert!(marks.is_protected(&make_meta("/y/two", 4, Some("h1"))));
+        assert!(!marks.is_protected(&make_meta("/z/other", 4, Some("h2"))));
+        // same hash, different size: not the same content
+        assert!(!marks.is_protected(&make_meta("/z/bigger", 5, Some("h1"))));
+    }
+
+    #[test]
+    fn test_content_mark_hashes_file_without_full_hash() {
+        let dir = std::env::temp_dir().join("cruft_protect_hash_on_demand");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("big.bin");
+        fs::write(&file, b"abc").unwrap();
+
+        let marks = Protections::open(&open_temp_db("on_demand")).unwrap();
+        marks.add(&Protection::Content { size: 3, hash: hashing::full_hash(&file).unwrap() }).unwrap();
+
+        assert!(marks.is_protected(&make_meta(file.to_str().unwrap(), 3, None)));
+    }
+
+    #[test]
+    fn test_failed_check_counts_as_protected() {
+        let marks = Protections::open(&open_temp_db("failed")).unwrap();
+        marks.add(&Protection::Content { size: 3, hash: "h".into() }).unwrap();
+        // size matches a mark but the file cannot be read to hash it
+        assert!(marks.is_protected(&make_meta("/nonexistent_dir_xyz/f", 3, None)));
+    }
+
+    #[test]
+    fn test_add_list_remove() {
+        let marks = Protections::open(&open_temp_db("list")).unwrap();
+        let path = Protection::Path(PathBuf::from("/a"));
+        let dir = Protection::Dir(PathBuf::from("/b"));
+        marks.add(&path).unwrap();
+        marks.add(&dir).unwrap();
+        marks.add(&path).unwrap();
+
+        assert_eq!(marks.list().len(), 2);
+        assert!(marks.remove(&path).unwrap());
+        assert!(!marks.remove(&path).unwrap());
+        assert_eq!(marks.list(), vec![dir]);
+    }
+
+    #[test]
+    fn test_marks_survive_reopen() {
+        let db = open_temp_db("reopen");
+        Protections::open(&db).unwrap().add(&Protection::Path(PathBuf::from("/a"))).unwrap();
+        assert_eq!(Protections::open(&db).unwrap().list().len(), 1);
+    }
+
+    // ── Protection::for_path ──────────────────────────────────────────────────
+
+    #[test]
+    fn test_for_path_scopes() {
+        let db = open_temp_db("for_path");
+        let index = FileIndex::open(&db).unwrap();
+        let path = Path::new("/home/me/docs/a.txt");
+
+        assert_eq!(
+            Protection::for_path(path, ProtectScope::File, &index).unwrap(),
+            Protection::Path(path.to_path_buf())
+        );
+        assert_eq!(
+            Protection::for_path(path, ProtectScope::Directory, &index).unwrap(),
+            Protection::Dir(PathBuf::from("/home/me/docs"))
+        );
+    }
+
+    #[test]
+    fn test_for_path_content_uses_indexed_hash() {
+        let db = open_temp_db("for_path_indexed");
+        let index = FileIndex::open(&db).unwrap();
+        // not on disk, so the hash can only come from the index
+        let meta = make_meta("/nonexistent_dir_xyz/a.txt", 9, Some("indexed"));
+        index.put(&IndexEntry::new(meta.clone(), None)).unwrap();
+
+        assert_eq!(
+            Protection::for_path(&meta.abs_path, ProtectScope::Content, &index).unwrap(),
+            Protection::Content { size: 9, hash: "indexed".into() }
+        );
+    }
+
+    #[test]
+    fn test_for_path_content_of_missing_file_is_error() {
+        let index = FileIndex::open(&open_temp_db("for_path_missing")).unwrap();
+        assert!(Protection::for_path(Path::new("/nonexistent_dir_xyz/a"), ProtectScope::Content, &index).is_err());
+    }
+}
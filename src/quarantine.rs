@@ -0,0 +1,388 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use filetime::FileTime;
+use serde::{Serialize, Deserialize};
+use crate::file_index::IndexEntry;
+
+/// Manifest of quarantined files: big-endian record id → cbor `QuarantineRecord`.
+pub(crate) const QUARANTINE_TREE: &str = "quarantine";
+/// How long a quarantined file can still be restored.
+pub(crate) const DEFAULT_RETENTION: Duration = Duration::from_secs(30 * 86400);
+/// Longest retention `quarantine.retention-days` accepts: a hundred years.
+pub(crate) const MAX_RETENTION_DAYS: u64 = 36_500;
+
+/// Filesystem metadata of a file at the moment it was quarantined, put back on restore.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct OriginalMeta {
+    pub size:           u64,
+    pub modified_secs:  i64,
+    pub modified_nanos: u32,
+    pub accessed_secs:  i64,
+    pub accessed_nanos: u32,
+    pub readonly:       bool,
+    /// Unix permission bits; `None` elsewhere.
+    pub mode:           Option<u32>,
+}
+
+impl OriginalMeta {
+    fn capture(md: &fs::Metadata) -> Self {
+        let modified = FileTime::from_last_modification_time(md);
+        let accessed = FileTime::from_last_access_time(md);
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(md.permissions().mode())
+        };
+        #[cfg(not(unix))]
+        let mode = None;
+
+        Self {
+            size: md.len(),
+            modified_secs: modified.unix_seconds(),
+            modified_nanos: modified.nanoseconds(),
+            accessed_secs: accessed.unix_seconds(),
+            accessed_nanos: accessed.nanoseconds(),
+            readonly: md.permissions().readonly(),
+            mode,
+        }
+    }
+
+    fn apply(&self, path: &Path) -> io::Result<()> {
+        filetime::set_file_times(
+            path,
+            FileTime::from_unix_time(self.accessed_secs, self.accessed_nanos),
+            FileTime::from_unix_time(self.modified_secs, self.modified_nanos),
+        )?;
+        let mut permissions = fs::metadata(path)?.permissions();
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::PermissionsExt;
+            permissions.set_mode(mode);
+        }
+        #[cfg(not(unix))]
+        permissions.set_readonly(self.readonly);
+        fs::set_permissions(path, permissions)
+    }
+}
+
+/// One file in quarantine.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct QuarantineRecord {
+    pub id:             u64,
+    pub original_path:  PathBuf,
+    pub stored_path:    PathBuf,
+    pub original:       OriginalMeta,
+    /// When the file was moved in (unix seconds).
+    pub quarantined_at: i64,
+    /// What the index knew about the file when it was deleted, if anything.
+    pub indexed:        Option<IndexEntry>,
+}
+
+impl QuarantineRecord {
+    pub fn expires_at(&self, retention: Duration) -> i64 {
+        let retention = i64::try_from(retention.as_secs()).unwrap_or(i64::MAX);
+        self.quarantined_at.saturating_add(retention)
+    }
+}
+
+/// Holding area for deleted files. Each file is stored under `<dir>/<id>/` at
+/// its original path with the root stripped, so names never collide and the
+/// layout stays readable. Cheap to clone; every clone shares the manifest.
+#[derive(Clone)]
+pub(crate) struct Quarantine {
+    db:        sled::Db,
+    records:   sled::Tree,
+    dir:       PathBuf,
+    retention: Duration,
+}
+
+impl Quarantine {
+    pub fn open(db: &sled::Db, dir: impl Into<PathBuf>, retention: Duration) -> sled::Result<Self> {
+        Ok(Self {
+            db:        db.clone(),
+            records:   db.open_tree(QUARANTINE_TREE)?,
+            dir:       dir.into(),
+            retention,
+        })
+    }
+
+    pub fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// Moves the file at `path` into quarantine and records where it came from.
+    pub fn quarantine(&self, path: &Path, indexed: Option<IndexEntry>) -> Result<QuarantineRecord, Box<dyn Error>> {
+        let md = fs::symlink_metadata(path)?;
+        if !md.is_file() {
+            return Err(format!("{} is not a regular file", path.display()).into());
+        }
+
+        let id = self.db.generate_id()?;
+        let stored_path = self.dir.join(id.to_string()).join(relative_to_root(path));
+        let record = QuarantineRecord {
+            id,
+            original_path: path.to_path_buf(),
+            stored_path,
+            original: OriginalMeta::capture(&md),
+            quarantined_at: unix_now(),
+            indexed,
+        };
+
+        if let Some(parent) = record.stored_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_file(path, &record.stored_path)?;
+
+        if let Err(e) = self.records.insert(id.to_be_bytes(), serde_cbor::to_vec(&record)?) {
+            // without a manifest entry the file could never be found again
+            let _ = move_file(&record.stored_path, path);
+            return Err(e.into());
+        }
+        Ok(record)
+    }
+
+    /// Puts a quarantined file back at its original path with its original
+    /// timestamps and permissions. Refuses to overwrite anything found there.
+    pub fn restore(&self, id: u64) -> Result<QuarantineRecord, Box<dyn Error>> {
+        let record = self.get(id)?.ok_or_else(|| format!("no quarantined file with id {}", id))?;
+        if fs::symlink_metadata(&record.original_path).is_ok() {
+            return Err(format!("{} already exists; not overwriting it", record.original_path.display()).into());
+        }
+
+        if let Some(parent) = record.original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_file(&record.stored_path, &record.original_path)?;
+        if let Err(e) = record.original.apply(&record.original_path) {
+            eprintln!("  ⚠ Restored {} but could not reset its metadata: {}", record.original_path.display(), e);
+        }
+
+        self.records.remove(id.to_be_bytes())?;
+        let _ = fs::remove_dir_all(self.dir.join(id.to_string()));
+        Ok(record)
+    }
+
+    pub fn get(&self, id: u64) -> Result<Option<QuarantineRecord>, Box<dyn Error>> {
+        match self.records.get(id.to_be_bytes())? {
+            Some(raw) => Ok(Some(serde_cbor::from_slice(&raw)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Every quarantined file, oldest first.
+    pub fn list(&self) -> Vec<QuarantineRecord> {
+        self.records
+            .iter()
+            .values()
+            .filter_map(|raw| raw.ok())
+            .filter_map(|raw| serde_cbor::from_slice(&raw).ok())
+            .collect()
+    }
+
+    /// The most recently quarantined copy of `path`, if any is still held.
+    pub fn latest_for(&self, path: &Path) -> Option<QuarantineRecord> {
+        self.records
+            .iter()
+            .values()
+            .rev()
+            .filter_map(|raw| raw.ok())
+            .filter_map(|raw| serde_cbor::from_slice::<QuarantineRecord>(&raw).ok())
+            .find(|r| r.original_path == path)
+    }
+
+    /// Permanently removes every file held longer than the retention period
+    /// as of `now` (unix seconds) and returns their records.
+    pub fn purge_expired(&self, now: i64) -> Result<Vec<QuarantineRecord>, Box<dyn Error>> {
+        let mut purged = Vec::new();
+        for record in self.list() {
+            if record.expires_at(self.retention) > now {
+                continue;
+            }
+            match fs::remove_dir_all(self.dir.join(record.id.to_string())) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    eprintln!("  ⚠ Could not purge {}: {}", record.stored_path.display(), e);
+                    continue;
+                }
+            }
+            self.records.remove(record.id.to_be_bytes())?;
+            purged.push(record);
+        }
+        Ok(purged)
+    }
+}
+
+/// `path` with its root and any drive prefix removed, e.g. `/home/me/a.txt` → `home/me/a.txt`.
+fn relative_to_root(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect()
+}
+
+/// Renames `from` to `to`, copying across filesystems when a rename can't.
+pub(crate) fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            let original = OriginalMeta::capture(&fs::metadata(from)?);
+            fs::copy(from, to)?;
+            original.apply(to)?;
+            fs::remove_file(from)
+        }
+        other => other,
+    }
+}
+
+pub(crate) fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    /// Fresh quarantine plus a directory of files to delete from.
+    fn setup(name: &str, retention: Duration) -> (Quarantine, PathBuf) {
+        let base = std::env::temp_dir().join(format!("cruft_quarantine_{}", name));
+        let _ = fs::remove_dir_all(&base);
+        let files = base.join("files");
+        fs::create_dir_all(&files).unwrap();
+        let db = sled::open(base.join("db")).unwrap();
+        (Quarantine::open(&db, base.join("held"), retention).unwrap(), files)
+    }
+
+    fn write(dir: &Path, name: &str, contents: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    // ── quarantine ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_quarantine_moves_file_under_its_original_path() {
+        let (q, files) = setup("moves", DEFAULT_RETENTION);
+        let path = write(&files, "docs/old.tmp", b"stale");
+
+        let record = q.quarantine(&path, None).unwrap();
+
+        assert!(!path.exists());
+        assert_eq!(fs::read(&record.stored_path).unwrap(), b"stale");
+        assert!(record.stored_path.ends_with(relative_to_root(&path)));
+        assert_eq!(record.original.size, 5);
+        assert_eq!(q.list(), vec![record]);
+    }
+
+    #[test]
+    fn test_same_path_quarantined_twice_keeps_both() {
+        let (q, files) = setup("twice", DEFAULT_RETENTION);
+        let path = write(&files, "a.txt", b"first");
+        let first = q.quarantine(&path, None).unwrap();
+        write(&files, "a.txt", b"second");
+        let second = q.quarantine(&path, None).unwrap();
+
+        assert_ne!(first.stored_path, second.stored_path);
+        assert_eq!(q.latest_for(&path), Some(second));
+    }
+
+    #[test]
+    fn test_quarantine_missing_file_is_error() {
+        let (q, files) = setup("missing", DEFAULT_RETENTION);
+        assert!(q.quarantine(&files.join("nope"), None).is_err());
+        assert!(q.list().is_empty());
+    }
+
+    #[test]
+    fn test_quarantine_refuses_directories() {
+        let (q, files) = setup("dir", DEFAULT_RETENTION);
+        assert!(q.quarantine(&files, None).is_err());
+        assert!(files.exists());
+    }
+
+    // ── restore ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_restore_puts_file_back_with_its_mtime() {
+        let (q, files) = setup("restore", DEFAULT_RETENTION);
+        let path = write(&files, "nested/deep/keep.txt", b"precious");
+        filetime::set_file_mtime(&path, FileTime::from_unix_time(1_600_000_000, 0)).unwrap();
+        let record = q.quarantine(&path, None).unwrap();
+        // the folder it lived in may be gone by the time it is restored
+        fs::remove_dir_all(files.join("nested")).unwrap();
+
+        q.restore(record.id).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"precious");
+        let mtime = FileTime::from_last_modification_time(&fs::metadata(&path).unwrap());
+        assert_eq!(mtime.unix_seconds(), 1_600_000_000);
+        assert!(q.list().is_empty());
+        assert!(!record.stored_path.exists());
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let (q, files) = setup("occupied", DEFAULT_RETENTION);
+        let path = write(&files, "a.txt", b"old");
+        let record = q.quarantine(&path, None).unwrap();
+        write(&files, "a.txt", b"new");
+
+        assert!(q.restore(record.id).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert!(record.stored_path.exists());
+    }
+
+    #[test]
+    fn test_restore_unknown_id_is_error() {
+        let (q, _) = setup("unknown", DEFAULT_RETENTION);
+        assert!(q.restore(12345).is_err());
+    }
+
+    // ── purge_expired ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_purge_removes_only_expired_files() {
+        let (q, files) = setup("purge", Duration::from_secs(100));
+        let record = q.quarantine(&write(&files, "a.txt", b"x"), None).unwrap();
+
+        assert!(q.purge_expired(record.quarantined_at + 99).unwrap().is_empty());
+        assert!(record.stored_path.exists());
+
+        let purged = q.purge_expired(record.quarantined_at + 100).unwrap();
+        assert_eq!(purged, vec![record.clone()]);
+        assert!(!record.stored_path.exists());
+        assert!(q.list().is_empty());
+    }
+
+    #[test]
+    fn test_huge_retention_never_expires() {
+        let (q, files) = setup("forever", Duration::MAX);
+        let record = q.quarantine(&write(&files, "a.txt", b"x"), None).unwrap();
+        assert_eq!(record.expires_at(Duration::MAX), i64::MAX);
+        assert!(q.purge_expired(i64::MAX - 1).unwrap().is_empty());
+        assert!(record.stored_path.exists());
+    }
+
+    // ── helpers under test ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_relative_to_root_strips_root() {
+        assert_eq!(relative_to_root(Path::new("/home/me/a.txt")), PathBuf::from("home/me/a.txt"));
+    }
+
+    #[test]
+    fn test_record_survives_reopen() {
+        let (q, files) = setup("reopen", DEFAULT_RETENTION);
+        let record = q.quarantine(&write(&files, "a.txt", b"x"), None).unwrap();
+        let reopened = Quarantine::open(&q.db, q.dir.clone(), DEFAULT_RETENTION).unwrap();
+        assert_eq!(reopened.get(record.id).unwrap(), Some(record));
+    }
+}
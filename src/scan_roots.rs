@@ -0,0 +1,302 @@
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::exclude;
+
+/// Name of the scan list read from the working directory.
+pub(crate) const SCAN_FILE: &str = "scan_paths.txt";
+/// Older single-line name, still accepted when `scan_paths.txt` is absent.
+pub(crate) const LEGACY_SCAN_FILE: &str = "scan_path.txt";
+
+/// One directory the crawler should walk, plus the options given on its line.
+///
+/// Line format: `<path> [| option option ...]`, e.g.
+/// `/home/me/projects | max_depth=4 follow_symlinks exclude=node_modules,*.o include=keep.o`
+///
+/// `exclude` and `include` take gitignore-style patterns; see `exclude::ExcludeRules`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ScanRoot {
+    pub path:            PathBuf,
+    pub max_depth:       Option<usize>,
+    pub follow_symlinks: bool,
+    pub excludes:        Vec<String>,
+    pub includes:        Vec<String>,
+    /// Honor `.gitignore` and `.cruftignore` files found while walking.
+    pub ignore_files:    bool,
+}
+
+impl ScanRoot {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            max_depth: None,
+            follow_symlinks: false,
+            excludes: Vec::new(),
+            includes: Vec::new(),
+            ignore_files: true,
+        }
+    }
+}
+
+/// A line of the scan list that could not be used. The rest of the file still applies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ScanLineError {
+    pub line:    usize,
+    pub content: String,
+    pub reason:  String,
+}
+
+impl fmt::Display for ScanLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} ({})", self.line, self.reason, self.content)
+    }
+}
+
+impl Error for ScanLineError {}
+
+/// Returns the scan list to read: `scan_paths.txt`, else the legacy `scan_path.txt`.
+pub(crate) fn find_scan_file(dir: &Path) -> Option<PathBuf> {
+    [SCAN_FILE, LEGACY_SCAN_FILE]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Reads and parses a scan list. Only a missing or unreadable file is an error;
+/// bad lines come back alongside the roots that did parse.
+pub(crate) fn load_scan_roots(file: &Path) -> Result<(Vec<ScanRoot>, Vec<ScanLineError>), Box<dyn Error>> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| format!("could not read '{}': {}", file.display(), e))?;
+    Ok(parse_scan_roots(&contents))
+}
+
+/// Parses scan list contents. Blank lines and lines starting with `#` are ignored.
+pub(crate) fn parse_scan_roots(contents: &str) -> (Vec<ScanRoot>, Vec<ScanLineError>) {
+    let mut roots  = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, raw) in contents.lines().enumerate() {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        match parse_line(trimmed) {
+            Ok(root) => roots.push(root),
+            Err(reason) => errors.push(ScanLineError {
+                line: idx + 1,
+                content: trimmed.to_string(),
+                reason,
+            }),
+        }
+    }
+
+    (roots, errors)
+}
+
+fn parse_line(line: &str) -> Result<ScanRoot, String> {
+    let (path_part, opts_part) = match line.split_once('|') {
+        Some((p, o)) => (p.trim(), o.trim()),
+        None => (line, ""),
+    };
+
+    if path_part.is_empty() {
+        return Err("no path given".to_string());
+    }
+
+    let mut root = ScanRoot::new(PathBuf::from(path_part));
+
+    for opt in opts_part.split_whitespace() {
+        let (key, value) = match opt.split_once('=') {
+            Some((k, v)) => (k, Some(v)),
+            None => (opt, None),
+        };
+
+        match (key, value) {
+            ("max_depth", Some(v)) => {
+                let depth = v.parse::<usize>()
+                    .map_err(|_| format!("max_depth must be a whole number, got '{}'", v))?;
+                root.max_depth = Some(depth);
+            }
+            ("follow_symlinks", None) => root.follow_symlinks = true,
+            ("follow_symlinks", Some(v)) => {
+                root.follow_symlinks = v.parse::<bool>()
+                    .map_err(|_| format!("follow_symlinks must be true or false, got '{}'", v))?;
+            }
+            ("exclude", Some(v)) => root.excludes.extend(parse_patterns(v)?),
+            ("include", Some(v)) => root.includes.extend(parse_patterns(v)?),
+            ("ignore_files", None) => root.ignore_files = true,
+            ("ignore_files", Some(v)) => {
+                root.ignore_files = v.parse::<bool>()
+                    .map_err(|_| format!("ignore_files must be true or false, got '{}'", v))?;
+            }
+            _ => return Err(format!("unknown option '{}'", opt)),
+        }
+    }
+
+    if !root.path.exists() {
+        return Err("path does not exist".to_string());
+    }
+    if !root.path.is_dir() {
+        return Err("path is not a directory".to_string());
+    }
+
+    Ok(root)
+}
+
+/// Splits a comma-separated pattern list, rejecting anything that is not valid
+/// gitignore syntax.
+fn parse_patterns(value: &str) -> Result<Vec<String>, String> {
+    value
+        .split(',')
+        .filter(|p| !p.is_empty())
+        .map(|p| exclude::validate_pattern(p).map(|_| p.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cruft_scan_roots_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // ── parse_scan_roots ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_multiple_roots() {
+        let a = temp_dir("multi_a");
+        let b = temp_dir("multi_b");
+        let contents = format!("{}\n{}\n", a.display(), b.display());
+
+        let (roots, errors) = parse_scan_roots(&contents);
+
+        assert!(errors.is_empty());
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].path, a);
+        assert_eq!(roots[1].path, b);
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_blank_lines() {
+        let a = temp_dir("comments");
+        let contents = format!("# my roots\n\n   \n{}\n  # indented comment\n", a.display());
+
+        let (roots, errors) = parse_scan_roots(&contents);
+
+        assert!(errors.is_empty());
+        assert_eq!(roots.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let a = temp_dir("options");
+        let contents = format!(
+            "{} | max_depth=3 follow_symlinks exclude=node_modules,*.o exclude=.git include=keep.o ignore_files=false",
+            a.display()
+        );
+
+        let (roots, errors) = parse_scan_roots(&contents);
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        let root = &roots[0];
+        assert_eq!(root.max_depth, Some(3));
+        assert!(root.follow_symlinks);
+        assert_eq!(root.excludes, vec!["node_modules", "*.o", ".git"]);
+        assert_eq!(root.includes, vec!["keep.o"]);
+        assert!(!root.ignore_files);
+    }
+
+    #[test]
+    fn test_parse_invalid_glob_is_reported() {
+        let a = temp_dir("bad_glob");
+        let (roots, errors) = parse_scan_roots(&format!("{} | exclude=ok,[z-a]", a.display()));
+        assert!(roots.is_empty());
+        assert!(errors[0].reason.contains("bad pattern '[z-a]'"));
+    }
+
+    #[test]
+    fn test_parse_follow_symlinks_explicit_false() {
+        let a = temp_dir("follow_false");
+        let (roots, _) = parse_scan_roots(&format!("{} | follow_symlinks=false", a.display()));
+        assert!(!roots[0].follow_symlinks);
+    }
+
+    #[test]
+    fn test_parse_defaults_without_options() {
+        let a = temp_dir("defaults");
+        let (roots, _) = parse_scan_roots(&a.display().to_string());
+        assert_eq!(roots[0], ScanRoot::new(a));
+    }
+
+    #[test]
+    fn test_parse_bad_lines_are_reported_not_fatal() {
+        let good = temp_dir("bad_lines_good");
+        let contents = format!(
+            "/definitely/not/a/real/dir_xyz\n{}\n{} | max_depth=deep\n{} | colour=blue\n",
+            good.display(), good.display(), good.display()
+        );
+
+        let (roots, errors) = parse_scan_roots(&contents);
+
+        assert_eq!(roots.len(), 1);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].line, 1);
+        assert!(errors[0].reason.contains("does not exist"));
+        assert!(errors[1].reason.contains("max_depth"));
+        assert!(errors[2].reason.contains("unknown option"));
+    }
+
+    #[test]
+    fn test_parse_file_path_is_rejected() {
+        let dir = temp_dir("file_path");
+        let file = dir.join("a.txt");
+        fs::write(&file, b"x").unwrap();
+
+        let (roots, errors) = parse_scan_roots(&file.display().to_string());
+
+        assert!(roots.is_empty());
+        assert!(errors[0].reason.contains("not a directory"));
+    }
+
+    #[test]
+    fn test_parse_missing_path_before_options() {
+        let (_, errors) = parse_scan_roots("  | max_depth=2");
+        assert_eq!(errors[0].reason, "no path given");
+    }
+
+    // ── find_scan_file / load_scan_roots ─────────────────────────────────────
+
+    #[test]
+    fn test_find_scan_file_prefers_new_name() {
+        let dir = temp_dir("find_new");
+        fs::write(dir.join(SCAN_FILE), "").unwrap();
+        fs::write(dir.join(LEGACY_SCAN_FILE), "").unwrap();
+        assert_eq!(find_scan_file(&dir), Some(dir.join(SCAN_FILE)));
+    }
+
+    #[test]
+    fn test_find_scan_file_falls_back_to_legacy() {
+        let dir = temp_dir("find_legacy");
+        fs::write(dir.join(LEGACY_SCAN_FILE), "").unwrap();
+        assert_eq!(find_scan_file(&dir), Some(dir.join(LEGACY_SCAN_FILE)));
+    }
+
+    #[test]
+    fn test_find_scan_file_none_when_absent() {
+        let dir = temp_dir("find_none");
+        assert_eq!(find_scan_file(&dir), None);
+    }
+
+    #[test]
+    fn test_load_scan_roots_missing_file_is_error() {
+        assert!(load_scan_roots(Path::new("/nonexistent_dir_xyz/scan_paths.txt")).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use crate::quarantine::unix_now;
+
+/// A file moved into a freedesktop.org trash can.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TrashedFile {
+    /// The trash can used, i.e. the directory holding `files/` and `info/`.
+    pub trash_dir: PathBuf,
+    pub stored_path: PathBuf,
+    pub info_path: PathBuf,
+}
+
+/// Deletion into the desktop trash, per the freedesktop.org Trash specification.
+/// Files on the home filesystem go to the home trash; files on other mounts go to
+/// `$topdir/.Trash/$uid` when the admin set one up, otherwise `$topdir/.Trash-$uid`.
+#[derive(Clone, Debug)]
+pub(crate) struct Trash {
+    home_trash: PathBuf,
+}
+
+impl Trash {
+    /// The home trash: `$XDG_DATA_HOME/Trash`, or `~/.local/share/Trash`.
+    /// `None` when neither variable is set.
+    pub fn from_env() -> Option<Self> {
+        Some(Self::new(crate::dirs::data_home()?.join("Trash")))
+    }
+
+    pub fn new(home_trash: PathBuf) -> Self {
+        Self { home_trash }
+    }
+
+    /// Moves `path` into the trash for its filesystem and writes its `.trashinfo`.
+    /// `Ok(None)` means there is no usable trash on that filesystem, so the file
+    /// was left alone and the caller should delete it some other way.
+    pub fn trash(&self, path: &Path) -> Result<Option<TrashedFile>, Box<dyn Error>> {
+        let path = std::path::absolute(path)?;
+        let Some((trash_dir, topdir)) = self.trash_dir_for(&path)? else {
+            return Ok(None);
+        };
+
+        let files = trash_dir.join("files");
+        let info = trash_dir.join("info");
+        fs::create_dir_all(&files)?;
+        fs::create_dir_all(&info)?;
+
+        // Paths in a mount's own trash are stored relative to its top directory
+        let recorded = match &topdir {
+            Some(top) => path.strip_prefix(top).unwrap_or(&path),
+            None => path.as_path(),
+        };
+        let file_name = path.file_name().ok_or_else(|| format!("{} has no file name", path.display()))?;
+        let (name, info_path) = reserve_name(&info, &file_name.to_string_lossy(), &trashinfo(recorded, unix_now()))?;
+
+        let stored_path = files.join(&name);
+        match fs::rename(&path, &stored_path) {
+            Ok(()) => Ok(Some(TrashedFile { trash_dir, stored_path, info_path })),
+            // the trash turned out to be elsewhere after all
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                let _ = fs::remove_file(&info_path);
+                Ok(None)
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&info_path);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// The trash can for a file, plus the mount's top directory when that trash
+    /// is not the home one.
+    fn trash_dir_for(&self, path: &Path) -> io::Result<Option<(PathBuf, Option<PathBuf>)>> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let dev = fs::symlink_metadata(path)?.dev();
+            if existing_ancestor_dev(&self.home_trash) == Some(dev) {
+                return Ok(Some((self.home_trash.clone(), None)));
+            }
+
+            let top = mount_top(path, dev);
+            let uid = unsafe { libc::getuid() };
+
+            // $topdir/.Trash must be a real directory with the sticky bit (0o1000) set
+            let shared = top.join(".Trash");
+            if let Ok(md) = fs::symlink_metadata(&shared)
+                && md.is_dir()
+                && md.mode() & 0o1000 != 0 {
+                let own = shared.join(uid.to_string());
+                if own.is_dir() || create_private_dir(&own).is_ok() {
+                    return Ok(Some((own, Some(top))));
+                }
+            }
+
+            let own = top.join(format!(".Trash-{}", uid));
+            match fs::symlink_metadata(&own) {
+                Ok(md) if md.is_dir() => Ok(Some((own, Some(top)))),
+                Ok(_) => Ok(None),
+                Err(_) => match create_private_dir(&own) {
+                    Ok(()) => Ok(Some((own, Some(top)))),
+                    Err(_) => Ok(None),
+                },
+            }
+        }
+        #[cfg(not(unix))]
+        Ok(None)
+    }
+}
+
+/// Creates `dir` with mode 0700: the spec requires a user's trash on a mount
+/// to be readable by that user alone.
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().mode(0o700).create(dir)
+}
+
+/// Device of `path`, or of its nearest existing ancestor; the home trash may not
+/// have been created yet.
+#[cfg(unix)]
+fn existing_ancestor_dev(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    path.ancestors().find_map(|p| fs::metadata(p).ok()).map(|md| md.dev())
+}
+
+/// Topmost directory above `path` still on device `dev`, i.e. its mount point.
+#[cfg(unix)]
+fn mount_top(path: &Path, dev: u64) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+    let mut top = path.parent().unwrap_or(path).to_path_buf();
+    while let Some(parent) = top.parent() {
+        match fs::metadata(parent) {
+            Ok(md) if md.dev() == dev => top = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    top
+}
+
+/// Creates `<info>/<name>.trashinfo` exclusively, adding a counter to `name`
+/// until it is free, and returns the name used. Creating the info file first
+/// is what reserves the name in `files/`.
+fn reserve_name(info: &Path, name: &str, contents: &str) -> io::Result<(String, PathBuf)> {
+    for n in 1.. {
+        let candidate = if n == 1 { name.to_string() } else { numbered(name, n) };
+        let info_path = info.join(format!("{}.trashinfo", candidate));
+        match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                return Ok((candidate, info_path));
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+/// `report.pdf` → `report.2.pdf`; names without an extension get a plain suffix.
+fn numbered(name: &str, n: u32) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}.{}.{}", stem, n, ext),
+        _ => format!("{}.{}", name, n),
+    }
+}
+
+/// Contents of a `.trashinfo` file.
+fn trashinfo(path: &Path, deleted_at: i64) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(path),
+        local_timestamp(deleted_at)
+    )
+}
+
+/// Percent-encodes a path the way the spec asks (RFC 2396), keeping `/`.
+fn percent_encode(path: &Path) -> String {
+    let mut out = String::new();
+    for &b in path.as_os_str().as_encoded_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.!~*'()".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// `YYYY-MM-DDThh:mm:ss` in local time, as `DeletionDate` requires.
+#[cfg(unix)]
+pub(crate) fn local_timestamp(secs: i64) -> String {
+    let t = secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::localtime_r(&t, &mut tm);
+    }
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min, tm.tm_sec
+    )
+}
+
+/// Without `localtime_r` the timestamp is given in UTC.
+#[cfg(not(unix))]
+pub(crate) fn local_timestamp(secs: i64) -> String {
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, rem / 3600, rem % 3600 / 60, rem % 60
+    )
+}
+
+/// Year, month and day of the date `days` after 1970-01-01 (proleptic Gregorian).
+#[cfg(not(unix))]
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cruft_trash_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // ── Trash::trash ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_trash_moves_file_and_writes_info() {
+        let dir = temp_dir("moves");
+        let trash = Trash::new(dir.join("Trash"));
+        let file = dir.join("old report.txt");
+        fs::write(&file, b"x").unwrap();
+
+        let trashed = trash.trash(&file).unwrap().expect("same filesystem as the home trash");
+
+        assert!(!file.exists());
+        assert_eq!(trashed.stored_path, dir.join("Trash/files/old report.txt"));
+        assert_eq!(fs::read(&trashed.stored_path).unwrap(), b"x");
+        let info = fs::read_to_string(&trashed.info_path).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("Path={}\n", percent_encode(&file))));
+        assert!(info.contains("DeletionDate="));
+    }
+
+    #[test]
+    fn test_trash_same_name_twice_gets_numbered() {
+        let dir = temp_dir("twice");
+        let trash = Trash::new(dir.join("Trash"));
+        let file = dir.join("a.txt");
+
+        fs::write(&file, b"1").unwrap();
+        trash.trash(&file).unwrap().unwrap();
+        fs::write(&file, b"2").unwrap();
+        let second = trash.trash(&file).unwrap().unwrap();
+
+        assert_eq!(second.stored_path, dir.join("Trash/files/a.2.txt"));
+        assert!(dir.join("Trash/info/a.2.txt.trashinfo").exists());
+    }
+
+    #[test]
+    fn test_trash_missing_file_is_error() {
+        let dir = temp_dir("missing");
+        let trash = Trash::new(dir.join("Trash"));
+        assert!(trash.trash(&dir.join("nope")).is_err());
+    }
+
+    // ── trashinfo helpers ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_percent_encode_keeps_slashes() {
+        assert_eq!(percent_encode(Path::new("/home/me/a b%.txt")), "/home/me/a%20b%25.txt");
+        assert_eq!(percent_encode(Path::new("/tmp/é")), "/tmp/%C3%A9");
+    }
+
+    #[test]
+    fn test_numbered_keeps_extension() {
+        assert_eq!(numbered("report.pdf", 2), "report.2.pdf");
+        assert_eq!(numbered("Makefile", 3), "Makefile.3");
+        assert_eq!(numbered(".bashrc", 2), ".bashrc.2");
+    }
+
+    #[test]
+    fn test_local_timestamp_format() {
+        let ts = local_timestamp(1_700_000_000);
+        assert_eq!(ts.len(), 19);
+        assert_eq!(&ts[4..5], "-");
+        assert_eq!(&ts[10..11], "T");
+        assert!(ts.starts_with("2023-11-1"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mount_trash_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("private");
+        let own = dir.join(".Trash-1000");
+        create_private_dir(&own).unwrap();
+        assert_eq!(fs::metadata(&own).unwrap().permissions().mode() & 0o777, 0o700);
+        assert!(create_private_dir(&own).is_err(), "an existing directory is left alone");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mount_top_stays_on_device() {
+        use std::os::unix::fs::MetadataExt;
+        let dir = temp_dir("mount_top");
+        let dev = fs::metadata(&dir).unwrap().dev();
+        let top = mount_top(&dir.join("file"), dev);
+        assert!(dir.starts_with(&top));
+        assert_eq!(fs::metadata(&top).unwrap().dev(), dev);
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::actor::ai_model::{Answer, Decision};
+use crate::actor::crawler::FileMeta;
+use crate::quarantine::unix_now;
+
+/// Something that can answer the AI actor's prompt about a file. The answer is
+/// one line of JSON in the shape of `ai_model::Answer`, which the model's
+/// grammar holds it to, and is checked with `Answer::parse` whichever engine
+/// wrote it.
+pub(crate) trait VerdictEngine: Send {
+    /// The answer for `meta`, asked with `prompt`.
+    fn infer(&self, meta: &FileMeta, prompt: &str) -> anyhow::Result<String>;
+
+    /// Answers for every file in `metas`, asked about together with `prompt`,
+    /// in the same order; `None` for a file the answer left out. An engine
+    /// that can't answer for several files at once leaves them all out, and
+    /// each is then asked about on its own.
+    fn infer_batch(&self, metas: &[FileMeta], _prompt: &str) -> anyhow::Result<Vec<Option<String>>> {
+        Ok(vec![None; metas.len()])
+    }
+
+    /// Stored with each verdict, so one engine's answers are never reused for another.
+    fn name(&self) -> &str;
+}
+
+/// The answers in a batched reply of `count` numbered lines, `1: <answer>`
+/// to `<count>: <answer>`, by number. A number that is missing, out of range
+/// or left empty gives `None`; when one is repeated the first line counts.
+pub(crate) fn split_numbered(raw: &str, count: usize) -> Vec<Option<String>> {
+    let mut answers = vec![None; count];
+    for line in raw.lines() {
+        let Some((number, answer)) = line.split_once(':') else { continue };
+        let answer = answer.trim();
+        if let Ok(n) = number.trim().parse::<usize>()
+            && (1..=count).contains(&n)
+            && !answer.is_empty()
+            && answers[n - 1].is_none()
+        {
+            answers[n - 1] = Some(answer.to_string());
+        }
+    }
+    answers
+}
+
+/// Words in a file name that suggest it is temporary, a cache or a stray copy.
+const TEMP_MARKERS: [&str; 7] = ["tmp", "temp", "cache", "copy", "backup", "old", "~"];
+
+/// Extensions of files that are left behind or regenerated as needed.
+const TEMP_EXTENSIONS: [&str; 3] = [".log", ".bak", ".swp"];
+const ARTIFACT_EXTENSIONS: [&str; 6] = [".o", ".pyc", ".class", ".part", ".crdownload", ".dmp"];
+
+fn is_temp_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    TEMP_MARKERS.iter().any(|m| name.contains(m)) || TEMP_EXTENSIONS.iter().any(|e| name.ends_with(e))
+}
+
+fn is_artifact_name(name: &str) -> bool {
+    is_temp_name(name) || ARTIFACT_EXTENSIONS.iter().any(|e| name.to_lowercase().ends_with(e))
+}
+
+/// Whether a rule applies to a file, given how many days ago it was modified.
+type Applies = Box<dyn Fn(&FileMeta, i64) -> bool + Send>;
+
+/// One rule of the mock engine: when it applies to a file, the answer is `decision`.
+pub(crate) struct MockRule {
+    applies:   Applies,
+    decision:  Decision,
+    reasoning: String,
+}
+
+impl MockRule {
+    pub fn new(decision: Decision, reasoning: &str, applies: impl Fn(&FileMeta, i64) -> bool + Send + 'static) -> Self {
+        Self { applies: Box::new(applies), decision, reasoning: reasoning.to_string() }
+    }
+}
+
+/// Name the mock engine stores with its verdicts.
+pub(crate) const MOCK_ENGINE: &str = "mock";
+/// Name the replay engine stores with its verdicts.
+pub(crate) const REPLAY_ENGINE: &str = "replay";
+
+/// Answers from fixed rules instead of a model: the heuristics the prompt asks
+/// the model to follow, checked in order. Needs no model file and always gives
+/// the same answer for the same file, so the whole graph can run in tests.
+pub(crate) struct MockEngine {
+    rules: Vec<MockRule>,
+    /// Unix time ages are measured from; fixed when the engine is created.
+    now:   i64,
+}
+
+impl MockEngine {
+    pub fn new() -> Self {
+        Self::at(unix_now())
+    }
+
+    /// A mock that measures ages from `now` (unix seconds).
+    pub fn at(now: i64) -> Self {
+        let rules = vec![
+            MockRule::new(Decision::Keep, "Read-only, so likely a system or protected file.",
+                          |meta, _| meta.readonly),
+            MockRule::new(Decision::Keep, "Modified within the last 30 days.",
+                          |_, days| days < 30),
+            MockRule::new(Decision::Delete, "Name suggests a temporary, cache or backup file, untouched for over a year.",
+                          |meta, days| days > 365 && is_temp_name(&meta.file_name)),
+            MockRule::new(Decision::Delete, "Small leftover artifact, untouched for over 180 days.",
+                          |meta, days| meta.size < 512 && days > 180 && is_artifact_name(&meta.file_name)),
+        ];
+        Self { rules, now }
+    }
+
+    /// The first rule that applies, if any.
+    fn decide(&self, meta: &FileMeta) -> Option<&MockRule> {
+        let days = (self.now - meta.modified) / 86400;
+        self.rules.iter().find(|rule| (rule.applies)(meta, days))
+    }
+}
+
+impl Default for MockEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VerdictEngine for MockEngine {
+    fn infer(&self, meta: &FileMeta, _prompt: &str) -> anyhow::Result<String> {
+        // The same answer the grammar holds the model to; a rule is as sure as the prompt's examples
+        let answer = match self.decide(meta) {
+            Some(rule) => Answer { decision: rule.decision, confidence: 90, reason: rule.reasoning.clone() },
+            None => Answer { decision: Decision::Unsure, confidence: 0, reason: "No rule matched.".into() },
+        };
+        Ok(serde_json::to_string(&answer)?)
+    }
+
+    fn infer_batch(&self, metas: &[FileMeta], prompt: &str) -> anyhow::Result<Vec<Option<String>>> {
+        metas.iter().map(|meta| self.infer(meta, prompt).map(Some)).collect()
+    }
+
+    fn name(&self) -> &str {
+        MOCK_ENGINE
+    }
+}
+
+/// One answer in a recording, as a line of JSON.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct Recorded {
+    pub path:   PathBuf,
+    pub engine: String,
+    pub output: String,
+}
+
+/// Passes every question to another engine and appends its answers to a
+/// JSON Lines file, for `ReplayEngine` to give back later.
+pub(crate) struct RecordingEngine {
+    inner: Box<dyn VerdictEngine>,
+    file:  PathBuf,
+}
+
+impl RecordingEngine {
+    pub fn new(inner: Box<dyn VerdictEngine>, file: impl Into<PathBuf>) -> Self {
+        Self { inner, file: file.into() }
+    }
+
+    /// Appends the answer for `meta`; one answer from a batch is recorded like any other.
+    fn record(&self, meta: &FileMeta, output: &str) -> anyhow::Result<()> {
+        let recorded = Recorded { path: meta.abs_path.clone(), engine: self.inner.name().to_string(), output: output.to_string() };
+
+        crate::dirs::create_parent(&self.file)?;
+        let mut line = serde_json::to_vec(&recorded)?;
+        line.push(b'\n');
+        fs::OpenOptions::new().append(true).create(true).open(&self.file)?.write_all(&line)?;
+        Ok(())
+    }
+}
+
+impl VerdictEngine for RecordingEngine {
+    fn infer(&self, meta: &FileMeta, prompt: &str) -> anyhow::Result<String> {
+        let output = self.inner.infer(meta, prompt)?;
+        self.record(meta, &output)?;
+        Ok(output)
+    }
+
+    fn infer_batch(&self, metas: &[FileMeta], prompt: &str) -> anyhow::Result<Vec<Option<String>>> {
+        let outputs = self.inner.infer_batch(metas, prompt)?;
+        for (meta, output) in metas.iter().zip(&outputs) {
+            if let Some(output) = output {
+                self.record(meta, output)?;
+            }
+        }
+        Ok(outputs)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+/// Gives back the answers a `RecordingEngine` wrote, by file path. A file that
+/// was not recorded is an error, so it is left unreviewed rather than guessed at.
+pub(crate) struct ReplayEngine {
+    answers: HashMap<PathBuf, String>,
+}
+
+impl ReplayEngine {
+    /// Reads `file`; when a path was recorded more than once, the last answer wins.
+    pub fn load(file: &Path) -> anyhow::Result<Self> {
+        let reader = BufReader::new(fs::File::open(file)
+            .map_err(|e| anyhow::anyhow!("Could not open recording '{}': {}", file.display(), e))?);
+        let mut answers = HashMap::new();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let recorded: Recorded = serde_json::from_str(&line)
+                .map_err(|e| anyhow::anyhow!("{} line {}: {}", file.display(), n + 1, e))?;
+            answers.insert(recorded.path, recorded.output);
+        }
+        Ok(Self { answers })
+    }
+}
+
+impl VerdictEngine for ReplayEngine {
+    fn infer(&self, meta: &FileMeta, _prompt: &str) -> anyhow::Result<String> {
+        self.answers
+            .get(&meta.abs_path)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No recorded answer for {}", meta.abs_path.display()))
+    }
+
+    fn infer_batch(&self, metas: &[FileMeta], _prompt: &str) -> anyhow::Result<Vec<Option<String>>> {
+        Ok(metas.iter().map(|meta| self.answers.get(&meta.abs_path).cloned()).collect())
+    }
+
+    fn name(&self) -> &str {
+        REPLAY_ENGINE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::ai_model::parse_decision;
+    use crate::hashing::HashStrategy;
+
+    const NOW: i64 = 1_800_000_000;
+    const DAY: i64 = 86400;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn meta(file_name: &str, size: u64, days_old: i64, readonly: bool) -> FileMeta {
+        FileMeta {
+            rel_path: PathBuf::from(file_name),
+            abs_path: PathBuf::from("/data").join(file_name),
+            file_name: file_name.to_string(),
+            hash: String::new(),
+            is_file: true,
+            size,
+            modified: NOW - days_old * DAY,
+            created: 0,
+            readonly,
+            hash_strategy: HashStrategy::default(),
+            full_hash: None,
+            inode: 0,
+        }
+    }
+
+    fn decision(engine: &dyn VerdictEngine, meta: &FileMeta) -> Decision {
+        parse_decision(&engine.infer(meta, "prompt").unwrap()).0
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("cruft_engine_{}.jsonl", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    // ── MockEngine ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_mock_follows_the_prompt_rules() {
+        let mock = MockEngine::at(NOW);
+        assert_eq!(decision(&mock, &meta("cache_session.tmp", 204, 412, false)), Decision::Delete);
+        assert_eq!(decision(&mock, &meta("notes_backup_old.txt", 1100, 400, false)), Decision::Delete);
+        assert_eq!(decision(&mock, &meta("main.o", 300, 200, false)), Decision::Delete);
+        assert_eq!(decision(&mock, &meta("report_final.pdf", 84200, 5, false)), Decision::Keep);
+        assert_eq!(decision(&mock, &meta("libsystem.tmp", 512, 730, true)), Decision::Keep, "read-only wins");
+        assert_eq!(decision(&mock, &meta("scratch.tmp", 10, 3, false)), Decision::Keep, "too recent");
+    }
+
+    #[test]
+    fn test_mock_is_unsure_when_no_rule_matches() {
+        let mock = MockEngine::at(NOW);
+        let answer = mock.infer(&meta("thesis.pdf", 84200, 400, false), "prompt").unwrap();
+        assert_eq!(parse_decision(&answer), (Decision::Unsure, 0));
+        assert!(Answer::parse(&answer).is_some(), "{}", answer);
+    }
+
+    #[test]
+    fn test_mock_ignores_the_prompt() {
+        let mock = MockEngine::at(NOW);
+        let file = meta("cache.tmp", 10, 400, false);
+        assert_eq!(mock.infer(&file, "one").unwrap(), mock.infer(&file, "two").unwrap());
+        assert_eq!(mock.name(), "mock");
+    }
+
+    #[test]
+    fn test_mock_batch_matches_one_at_a_time() {
+        let mock = MockEngine::at(NOW);
+        let files = [meta("cache.tmp", 10, 400, false), meta("report.pdf", 10, 1, false), meta("thesis.pdf", 84200, 400, false)];
+        let batch = mock.infer_batch(&files, "prompt").unwrap();
+        let single: Vec<Option<String>> = files.iter().map(|f| Some(mock.infer(f, "prompt").unwrap())).collect();
+        assert_eq!(batch, single);
+    }
+
+    #[test]
+    fn test_engines_without_batching_leave_every_file_out() {
+        struct OneAtATime;
+        impl VerdictEngine for OneAtATime {
+            fn infer(&self, _meta: &FileMeta, _prompt: &str) -> anyhow::Result<String> {
+                Ok("Decision: keep".into())
+            }
+            fn name(&self) -> &str {
+                "one"
+            }
+        }
+        let files = [meta("a.txt", 1, 1, false), meta("b.txt", 1, 1, false)];
+        assert_eq!(OneAtATime.infer_batch(&files, "prompt").unwrap(), vec![None, None]);
+    }
+
+    // ── split_numbered ────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_split_numbered_maps_lines_by_number() {
+        let raw = "2: {\"b\":1}\n1: {\"a\":1}\n3: {\"c\":1}";
+        assert_eq!(split_numbered(raw, 3), vec![
+            Some("{\"a\":1}".to_string()),
+            Some("{\"b\":1}".to_string()),
+            Some("{\"c\":1}".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_split_numbered_leaves_out_what_is_missing() {
+        let raw = "1: first\n1: again\n3:\n4: out of range\n0: zero\nno number\n x: bad";
+        assert_eq!(split_numbered(raw, 3), vec![Some("first".to_string()), None, None]);
+        assert_eq!(split_numbered("", 2), vec![None, None]);
+    }
+
+    // ── RecordingEngine / ReplayEngine ────────────────────────────────────────
+
+    #[test]
+    fn test_replay_gives_back_what_was_recorded() {
+        let file = temp_file("round_trip");
+        let recorder = RecordingEngine::new(Box::new(MockEngine::at(NOW)), &file);
+        let old = meta("cache.tmp", 10, 400, false);
+        let new = meta("report.pdf", 10, 1, false);
+        let answers = [recorder.infer(&old, "p").unwrap(), recorder.infer(&new, "p").unwrap()];
+        assert_eq!(recorder.name(), "mock");
+
+        let replay = ReplayEngine::load(&file).unwrap();
+        assert_eq!(replay.answers.len(), 2);
+        assert_eq!(replay.infer(&old, "other prompt").unwrap(), answers[0]);
+        assert_eq!(replay.infer(&new, "other prompt").unwrap(), answers[1]);
+        assert_eq!(replay.name(), "replay");
+        let _ = fs::remove_file(&file);
+    }
+
+    #[test]
+    fn test_replay_misses_are_errors() {
+        let file = temp_file("miss");
+        RecordingEngine::new(Box::new(MockEngine::at(NOW)), &file).infer(&meta("a.tmp", 1, 400, false), "p").unwrap();
+
+        let replay = ReplayEngine::load(&file).unwrap();
+        assert!(replay.infer(&meta("b.tmp", 1, 400, false), "p").is_err());
+        let _ = fs::remove_file(&file);
+    }
+
+    #[test]
+    fn test_batch_answers_are_recorded_and_replayed_per_file() {
+        let file = temp_file("batch");
+        let recorder = RecordingEngine::new(Box::new(MockEngine::at(NOW)), &file);
+        let files = [meta("cache.tmp", 10, 400, false), meta("report.pdf", 10, 1, false)];
+        let answers = recorder.infer_batch(&files, "p").unwrap();
+
+        let replay = ReplayEngine::load(&file).unwrap();
+        assert_eq!(replay.answers.len(), 2);
+        assert_eq!(Some(replay.infer(&files[1], "p").unwrap()), answers[1]);
+        let missing = meta("b.tmp", 1, 400, false);
+        assert_eq!(replay.infer_batch(&[files[0].clone(), missing], "p").unwrap(), vec![answers[0].clone(), None]);
+        let _ = fs::remove_file(&file);
+    }
+
+    #[test]
+    fn test_replay_last_recording_wins() {
+        let file = temp_file("last_wins");
+        let line = |output: &str| serde_json::to_string(&Recorded {
+            path: PathBuf::from("/data/a.txt"),
+            engine: "m.gguf".into(),
+            output: output.into(),
+        }).unwrap();
+        fs::write(&file, format!("{}\n\n{}\n", line("Decision: keep"), line("Decision: delete"))).unwrap();
+
+        let replay = ReplayEngine::load(&file).unwrap();
+        assert_eq!(replay.infer(&meta("a.txt", 1, 1, false), "p").unwrap(), "Decision: delete");
+        let _ = fs::remove_file(&file);
+    }
+
+    #[test]
+    fn test_replay_rejects_a_bad_recording() {
+        let file = temp_file("bad");
+        fs::write(&file, "not json\n").unwrap();
+        let err = ReplayEngine::load(&file).err().unwrap().to_string();
+        assert!(err.contains("line 1"), "{}", err);
+        assert!(ReplayEngine::load(Path::new("/nonexistent/recording.jsonl")).is_err());
+        let _ = fs::remove_file(&file);
+    }
+}
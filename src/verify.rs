@@ -0,0 +1,249 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::actor::crawler::{self, FileMeta};
+use crate::file_index;
+use crate::hashing::{self, HashConfig};
+
+/// Why a confirmed deletion was not carried out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Refusal {
+    /// Nothing is at the path any more.
+    Missing,
+    /// The path was never indexed, so there is nothing to compare against.
+    NotIndexed,
+    /// The path is now a symlink; deleting it would not delete what was reviewed.
+    Symlink,
+    /// The path is now a directory or special file.
+    NotAFile,
+    /// Size, modification time or inode differ from the reviewed file.
+    Changed,
+    /// Same metadata, different contents.
+    ContentChanged,
+    /// Another process has the file open.
+    InUse { pid: u32 },
+    /// The file could not be read to check it.
+    Unreadable(String),
+    /// The file was marked never-delete after it was reviewed.
+    Protected,
+    /// The copy kept in place of this duplicate is gone or no longer identical.
+    KeeperChanged(PathBuf),
+}
+
+impl std::error::Error for Refusal {}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::Missing => write!(f, "the file no longer exists"),
+            Refusal::NotIndexed => write!(f, "the file is not in the index"),
+            Refusal::Symlink => write!(f, "the path is now a symlink"),
+            Refusal::NotAFile => write!(f, "the path is no longer a regular file"),
+            Refusal::Changed => write!(f, "the file changed since it was scanned"),
+            Refusal::ContentChanged => write!(f, "the file's contents changed since it was scanned"),
+            Refusal::InUse { pid } => write!(f, "the file is open in process {}", pid),
+            Refusal::Unreadable(e) => write!(f, "the file could not be checked: {}", e),
+            Refusal::Protected => write!(f, "the file is marked never-delete"),
+            Refusal::KeeperChanged(keeper) => write!(f, "the copy being kept, {}, is gone or changed", keeper.display()),
+        }
+    }
+}
+
+/// Checks that the file at `expected.abs_path` is still the one that was
+/// reviewed: a regular file (not a symlink) with the same size, mtime, inode
+/// and hash, that no other process has open. The full-content hash is compared
+/// when one was recorded, so a duplicate is never deleted once it stops being one.
+pub(crate) fn verify_before_delete(expected: &FileMeta) -> Result<(), Refusal> {
+    let path = &expected.abs_path;
+    let md = fs::symlink_metadata(path).map_err(|_| Refusal::Missing)?;
+    if md.file_type().is_symlink() {
+        return Err(Refusal::Symlink);
+    }
+    if !md.is_file() {
+        return Err(Refusal::NotAFile);
+    }
+
+    let current = crawler::stat_meta(path, &md, expected.hash_strategy).ok_or(Refusal::Missing)?;
+    if !file_index::same_file_version(expected, &current) {
+        return Err(Refusal::Changed);
+    }
+
+    let unreadable = |e: std::io::Error| Refusal::Unreadable(e.to_string());
+    let unchanged = match expected.content_hash() {
+        Some(full) => hashing::full_hash(path).map_err(unreadable)? == full,
+        None => {
+            let config = HashConfig { strategy: expected.hash_strategy, ..HashConfig::default() };
+            hashing::hash_file(path, current.size, &config).map_err(unreadable)?.hash == expected.hash
+        }
+    };
+    if !unchanged {
+        return Err(Refusal::ContentChanged);
+    }
+
+    if let Some(pid) = open_by_other_process(path, std::process::id()) {
+        return Err(Refusal::InUse { pid });
+    }
+    Ok(())
+}
+
+/// Checks that `keeper` is still a regular file whose full-content hash is
+/// `content_hash`, so deleting a copy of it loses nothing.
+pub(crate) fn verify_keeper(keeper: &Path, content_hash: Option<&str>) -> Result<(), Refusal> {
+    let changed = || Refusal::KeeperChanged(keeper.to_path_buf());
+    let md = fs::symlink_metadata(keeper).map_err(|_| changed())?;
+    if !md.is_file() {
+        return Err(changed());
+    }
+    match (content_hash, hashing::full_hash(keeper)) {
+        (Some(expected), Ok(actual)) if actual == expected => Ok(()),
+        _ => Err(changed()),
+    }
+}
+
+/// Id of a process other than `own_pid` holding `path` open, found by reading
+/// every `/proc/<pid>/fd`. Processes we may not inspect are skipped, and on
+/// systems without `/proc` nothing is ever reported.
+pub(crate) fn open_by_other_process(path: &Path, own_pid: u32) -> Option<u32> {
+    let target = fs::canonicalize(path).ok()?;
+    let procs = fs::read_dir("/proc").ok()?;
+
+    for proc_entry in procs.filter_map(|e| e.ok()) {
+        let Some(pid) = proc_entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        let Ok(fds) = fs::read_dir(proc_entry.path().join("fd")) else {
+            continue;
+        };
+        if fds.filter_map(|fd| fd.ok()).any(|fd| fs::read_link(fd.path()).is_ok_and(|p| p == target)) {
+            return Some(pid);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::HashStrategy;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cruft_verify_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a file and returns the record a scan would have stored for it.
+    fn scanned(dir: &Path, name: &str, contents: &[u8], strategy: HashStrategy) -> FileMeta {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        let config = HashConfig { strategy, ..HashConfig::default() };
+        crawler::file_meta_from_path(&path, &fs::metadata(&path).unwrap(), &config).unwrap()
+    }
+
+    // ── verify_before_delete ──────────────────────────────────────────────────
+
+    #[test]
+    fn test_unchanged_file_passes() {
+        let dir = temp_dir("unchanged");
+        let meta = scanned(&dir, "a.txt", b"hello", HashStrategy::Sampled);
+        assert_eq!(verify_before_delete(&meta), Ok(()));
+    }
+
+    #[test]
+    fn test_missing_file_is_refused() {
+        let dir = temp_dir("missing");
+        let meta = scanned(&dir, "a.txt", b"hello", HashStrategy::Sampled);
+        fs::remove_file(&meta.abs_path).unwrap();
+        assert_eq!(verify_before_delete(&meta), Err(Refusal::Missing));
+    }
+
+    #[test]
+    fn test_resized_file_is_refused() {
+        let dir = temp_dir("resized");
+        let meta = scanned(&dir, "a.txt", b"hello", HashStrategy::Sampled);
+        fs::write(&meta.abs_path, b"hello, world").unwrap();
+        assert_eq!(verify_before_delete(&meta), Err(Refusal::Changed));
+    }
+
+    #[test]
+    fn test_same_size_new_content_is_refused() {
+        let dir = temp_dir("content");
+        let meta = scanned(&dir, "a.txt", b"hello", HashStrategy::Quick);
+        let mtime = filetime::FileTime::from_last_modification_time(&fs::metadata(&meta.abs_path).unwrap());
+        // rewrite in place and put the mtime back, so only the hash can tell
+        fs::write(&meta.abs_path, b"jello").unwrap();
+        filetime::set_file_mtime(&meta.abs_path, mtime).unwrap();
+
+        assert_eq!(verify_before_delete(&meta), Err(Refusal::ContentChanged));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_is_refused() {
+        let dir = temp_dir("symlink");
+        let meta = scanned(&dir, "a.txt", b"hello", HashStrategy::Sampled);
+        fs::write(dir.join("other.txt"), b"hello").unwrap();
+        fs::remove_file(&meta.abs_path).unwrap();
+        std::os::unix::fs::symlink(dir.join("other.txt"), &meta.abs_path).unwrap();
+
+        assert_eq!(verify_before_delete(&meta), Err(Refusal::Symlink));
+    }
+
+    #[test]
+    fn test_directory_is_refused() {
+        let dir = temp_dir("now_dir");
+        let meta = scanned(&dir, "a.txt", b"hello", HashStrategy::Sampled);
+        fs::remove_file(&meta.abs_path).unwrap();
+        fs::create_dir(&meta.abs_path).unwrap();
+
+        assert_eq!(verify_before_delete(&meta), Err(Refusal::NotAFile));
+    }
+
+    // ── open_by_other_process ─────────────────────────────────────────────────
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_open_file_is_found_in_proc() {
+        let dir = temp_dir("open");
+        let path = dir.join("held.txt");
+        fs::write(&path, b"x").unwrap();
+        let _held = fs::File::open(&path).unwrap();
+
+        // pid 0 is never a real process, so our own handle counts as another's
+        assert_eq!(open_by_other_process(&path, 0), Some(std::process::id()));
+        assert_eq!(open_by_other_process(&path, std::process::id()), None);
+    }
+
+    // ── verify_keeper ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_keeper_with_same_content_passes() {
+        let dir = temp_dir("keeper");
+        let meta = scanned(&dir, "keep.iso", b"same", HashStrategy::Full);
+        assert_eq!(verify_keeper(&meta.abs_path, meta.content_hash()), Ok(()));
+    }
+
+    #[test]
+    fn test_changed_or_missing_keeper_is_refused() {
+        let dir = temp_dir("keeper_changed");
+        let meta = scanned(&dir, "keep.iso", b"same", HashStrategy::Full);
+        let refused = Err(Refusal::KeeperChanged(meta.abs_path.clone()));
+
+        fs::write(&meta.abs_path, b"different").unwrap();
+        assert_eq!(verify_keeper(&meta.abs_path, meta.content_hash()), refused);
+        fs::remove_file(&meta.abs_path).unwrap();
+        assert_eq!(verify_keeper(&meta.abs_path, meta.content_hash()), refused);
+    }
+
+    #[test]
+    fn test_refusal_messages_name_the_problem() {
+        assert!(Refusal::InUse { pid: 42 }.to_string().contains("42"));
+        assert!(Refusal::Changed.to_string().contains("changed"));
+    }
+}